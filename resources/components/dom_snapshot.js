@@ -0,0 +1,60 @@
+// Snapshot of the DOM of a page for a versoview controller.
+//
+// Walks the document in tree order and returns a JSON `DomSnapshot` of every node, with the
+// attributes, computed style and border box of elements. Boxes are relative to the document so
+// they don't depend on the scroll position. Shadow roots and the documents of frames are skipped.
+(() => {
+  const nodes = [];
+  const stack = [[document, null]];
+  while (stack.length > 0) {
+    const [node, parent] = stack.pop();
+    const index = nodes.length;
+    const snapshot = {
+      parent,
+      nodeType: node.nodeType,
+      nodeName: node.nodeName,
+      nodeValue:
+        node.nodeType === Node.TEXT_NODE || node.nodeType === Node.COMMENT_NODE
+          ? node.nodeValue
+          : null,
+      attributes: [],
+      computedStyle: [],
+      bounds: null,
+    };
+    if (node.nodeType === Node.ELEMENT_NODE) {
+      for (const attribute of node.attributes) {
+        snapshot.attributes.push([attribute.name, attribute.value]);
+      }
+      const style = window.getComputedStyle(node);
+      for (let i = 0; i < style.length; i++) {
+        const property = style.item(i);
+        snapshot.computedStyle.push([property, style.getPropertyValue(property)]);
+      }
+      if (node.getClientRects().length > 0) {
+        const rect = node.getBoundingClientRect();
+        snapshot.bounds = {
+          x: rect.x + window.scrollX,
+          y: rect.y + window.scrollY,
+          width: rect.width,
+          height: rect.height,
+        };
+      }
+    }
+    nodes.push(snapshot);
+    // Pushed in reverse so the first child is visited next.
+    for (let child = node.lastChild; child; child = child.previousSibling) {
+      stack.push([child, index]);
+    }
+  }
+  return JSON.stringify({
+    url: document.URL,
+    title: document.title,
+    viewport: {
+      x: window.scrollX,
+      y: window.scrollY,
+      width: window.innerWidth,
+      height: window.innerHeight,
+    },
+    nodes,
+  });
+})()
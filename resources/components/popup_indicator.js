@@ -0,0 +1,38 @@
+// Indicator of a popup blocked in the current tab, injected into the browser UI page.
+//
+// Verso calls this with the origin of the page which tried to open the popup, or `null` to hide
+// the indicator. The buttons are sent back as `POPUP_DECISION` prompts like other panel commands.
+(origin) => {
+  let indicator = document.getElementById('verso-popup-blocked');
+  if (origin === null) {
+    indicator?.remove();
+    return;
+  }
+  if (!indicator) {
+    indicator = document.createElement('div');
+    indicator.id = 'verso-popup-blocked';
+    indicator.style.cssText =
+      'position: fixed; bottom: 2px; right: 8px; z-index: 2147483647; display: flex; gap: 6px; align-items: center; padding: 2px 6px; border-radius: 4px; background: #fff3cd; color: #333; font: 12px Arial, Helvetica, sans-serif; box-shadow: 0 1px 3px rgba(0, 0, 0, 0.3)';
+    document.body.appendChild(indicator);
+  }
+  indicator.replaceChildren();
+
+  const text = document.createElement('span');
+  text.textContent = `Popup blocked from ${origin}`;
+  indicator.appendChild(text);
+
+  const buttons = [
+    ['Allow once', 'allowOnce'],
+    ['Always allow', 'allowSite'],
+    ['×', 'dismiss'],
+  ];
+  for (const [label, decision] of buttons) {
+    const button = document.createElement('button');
+    button.textContent = label;
+    button.style.cssText = 'font: inherit; padding: 0 6px; cursor: pointer';
+    button.addEventListener('click', () =>
+      window.prompt(`POPUP_DECISION:${JSON.stringify(decision)}`)
+    );
+    indicator.appendChild(button);
+  }
+}
@@ -0,0 +1,60 @@
+// Console of a page for the REPL of a versoview controller.
+//
+// Verso prepends this script to everything the REPL runs in the page, so it's installed again
+// after the page navigates. Messages logged before that are lost. Code is evaluated in the global
+// scope, `var` and function declarations are kept between evaluations but `let` and `const` aren't.
+if (!window.__versoRepl) {
+  window.__versoRepl = (() => {
+    // Messages kept while nobody takes them, older ones are dropped.
+    const MAX_MESSAGES = 1000;
+    const LEVELS = ['log', 'info', 'warn', 'error', 'debug'];
+    const messages = [];
+
+    const format = (value) => {
+      if (typeof value === 'string') {
+        return JSON.stringify(value);
+      }
+      if (typeof value === 'function' || typeof value === 'symbol') {
+        return String(value);
+      }
+      if (value instanceof Error) {
+        return `${value}`;
+      }
+      if (value !== null && typeof value === 'object') {
+        try {
+          return JSON.stringify(value);
+        } catch (error) {
+          return Object.prototype.toString.call(value);
+        }
+      }
+      return String(value);
+    };
+
+    for (const level of LEVELS) {
+      const original = console[level];
+      console[level] = function (...args) {
+        const message = args
+          .map((arg) => (typeof arg === 'string' ? arg : format(arg)))
+          .join(' ');
+        messages.push({ level, message });
+        if (messages.length > MAX_MESSAGES) {
+          messages.shift();
+        }
+        return original.apply(this, args);
+      };
+    }
+
+    return {
+      evaluate(code) {
+        try {
+          return JSON.stringify({ value: format((0, eval)(code)) });
+        } catch (error) {
+          return JSON.stringify({ error: `${error}` });
+        }
+      },
+      takeMessages() {
+        return JSON.stringify(messages.splice(0));
+      },
+    };
+  })();
+}
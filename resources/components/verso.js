@@ -0,0 +1,70 @@
+// Privileged API of the browser UI page.
+//
+// Verso draws one chrome webview per window, the built-in panel or a custom page from
+// `--chrome-url`, `--chrome-path` or `CliArgs::chrome`, and the tabs below it. This script is
+// injected into the chrome webview only, when its document starts loading. Only prompts from the
+// chrome webview are handled as commands, other pages get regular dialogs.
+//
+// Verso calls these functions of the page, which it has to define:
+// - `window.navbar.addTab(id, active)` when a tab is created
+// - `window.navbar.closeTab(id)` when a tab is closed, returning the id of the tab to activate
+// - `window.navbar.setTabTitle(id, title)` when the title of a tab changes
+// - `window.navbar.setNavbarUrl(url)` when the current tab navigates
+// - `window.navbar.setSuggestions(input, suggestions)` with search suggestions for the URL bar
+//   input, only with `--search-suggestions` and optional to define
+//
+// Tab ids are opaque strings, pass them back as they are.
+(() => {
+  const send = (message) => window.prompt(message);
+
+  window.verso = {
+    /** Load a URL in the current tab, or search for the input if it isn't a URL. */
+    navigate: (url) => send(`NAVIGATE_TO:${url}`),
+    /**
+     * Ask for search suggestions for the URL bar input, they're sent to
+     * `window.navbar.setSuggestions` later.
+     */
+    suggest: (input) => send(`SUGGEST:${input}`),
+    /** Go back in the history of the current tab. */
+    back: () => send('PREV'),
+    /** Go forward in the history of the current tab. */
+    forward: () => send('FORWARD'),
+    /** Reload the current tab. */
+    reload: () => send('REFRESH'),
+    /** Load the home page in the current tab. */
+    home: () => send('HOME'),
+    /** Open a new tab and return its id. */
+    newTab: () => JSON.stringify(JSON.parse(send('NEW_TAB')).id),
+    /** Close a tab. */
+    closeTab: (id) => send(`CLOSE_TAB:${JSON.stringify({ id: JSON.parse(id) })}`),
+    /** Show a tab. */
+    activateTab: (id) => send(`ACTIVATE_TAB:${JSON.stringify({ id: JSON.parse(id) })}`),
+    /** Open a new window. */
+    newWindow: () => send('NEW_WINDOW'),
+    /** Minimize the window. */
+    minimize: () => send('MINIMIZE'),
+    /** Maximize the window, or restore it if it's maximized. */
+    toggleMaximized: () => send('MAXIMIZE'),
+    /** Move the window with the left mouse button until it's released. */
+    startDragging: () => send('DRAG_WINDOW'),
+    /**
+     * Set the areas of the window which move it when dragged, as a list of
+     * `{ x, y, width, height }` in CSS pixels, replacing the previous ones.
+     */
+    setDragRegions: (regions) => send(`SET_DRAG_REGIONS:${JSON.stringify(regions)}`),
+    /**
+     * Run the action of a keyboard shortcut, like `'ZoomIn'`, `'ToggleStats'` or `'Quit'`,
+     * see `KeyAction` of versoview_messages.
+     */
+    runAction: (action) => send(`RUN_ACTION:${JSON.stringify(action)}`),
+    /**
+     * Decide on the popup blocked in the current tab, `'allowOnce'` lets the page open its next
+     * popup, `'allowSite'` always allows its origin and `'dismiss'` keeps blocking.
+     */
+    decidePopup: (decision) => send(`POPUP_DECISION:${JSON.stringify(decision)}`),
+    /** Get the window's settings and Servo's preferences, `{ httpsOnly, mouseGestures, prefs }`. */
+    getSettings: () => JSON.parse(send('GET_SETTINGS')),
+    /** Change some of the settings or preferences, the others are left as they are. */
+    setSettings: (settings) => send(`SET_SETTINGS:${JSON.stringify(settings)}`),
+  };
+})();
@@ -0,0 +1,55 @@
+// Result collection of the WPT mode, run as the init script of every test page.
+//
+// testharness.js exposes its API by assigning it to the global object, so catching the assignment
+// of `add_completion_callback` registers a callback before any test has run. Results are sent back
+// as a `WPT_RESULT` prompt. Pages without testharness.js are reported once they've loaded, as a
+// reftest if they link to a reference.
+(() => {
+  if (window.__versoWpt) {
+    return;
+  }
+  window.__versoWpt = true;
+
+  const HARNESS_STATUSES = ['OK', 'ERROR', 'TIMEOUT', 'PRECONDITION_FAILED'];
+  const TEST_STATUSES = ['PASS', 'FAIL', 'TIMEOUT', 'NOTRUN', 'PRECONDITION_FAILED'];
+  const report = (result) => window.prompt(`WPT_RESULT:${JSON.stringify(result)}`);
+
+  let harness = false;
+  let addCompletionCallback;
+  Object.defineProperty(window, 'add_completion_callback', {
+    configurable: true,
+    get: () => addCompletionCallback,
+    set(value) {
+      addCompletionCallback = value;
+      if (harness) {
+        return;
+      }
+      harness = true;
+      value((tests, status) =>
+        report({
+          status: HARNESS_STATUSES[status.status] ?? 'ERROR',
+          message: status.message ?? null,
+          subtests: tests.map((test) => ({
+            name: test.name,
+            status: TEST_STATUSES[test.status] ?? 'FAIL',
+            message: test.message ?? null,
+          })),
+        })
+      );
+    },
+  });
+
+  window.addEventListener('load', () =>
+    setTimeout(() => {
+      if (harness) {
+        return;
+      }
+      const reference = document.querySelector('link[rel~="match"], link[rel~="mismatch"]');
+      report(
+        reference
+          ? { reftest: true }
+          : { status: 'ERROR', message: 'The page doesn\'t load testharness.js', subtests: [] }
+      );
+    })
+  );
+})();
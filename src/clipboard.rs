@@ -0,0 +1,64 @@
+use raw_window_handle::HasDisplayHandle;
+#[cfg(linux)]
+use raw_window_handle::RawDisplayHandle;
+
+/// Text clipboard of the system.
+///
+/// On Wayland, the clipboard is only reachable through the display connection of a window with
+/// keyboard focus. arboard opens its own connection and needs the data control protocol for that,
+/// which isn't available in Flatpak and most desktops, so it goes through the window's connection
+/// instead.
+pub enum Clipboard {
+    /// Clipboard of X11, macOS and Windows.
+    Arboard(arboard::Clipboard),
+    /// Clipboard of the window's Wayland connection.
+    #[cfg(linux)]
+    Wayland(smithay_clipboard::Clipboard),
+}
+
+impl Clipboard {
+    /// Create a clipboard for the display of this window.
+    pub fn new(window: &impl HasDisplayHandle) -> Option<Self> {
+        #[cfg(linux)]
+        if let Ok(handle) = window.display_handle() {
+            if let RawDisplayHandle::Wayland(handle) = handle.as_raw() {
+                // SAFETY: The display connection belongs to the event loop, which outlives Verso
+                // and its clipboard.
+                let clipboard =
+                    unsafe { smithay_clipboard::Clipboard::new(handle.display.as_ptr()) };
+                return Some(Self::Wayland(clipboard));
+            }
+        }
+        #[cfg(not(linux))]
+        let _ = window;
+
+        match arboard::Clipboard::new() {
+            Ok(clipboard) => Some(Self::Arboard(clipboard)),
+            Err(e) => {
+                log::warn!("Failed to create clipboard: {e}");
+                None
+            }
+        }
+    }
+
+    /// Get the text content of the clipboard.
+    pub fn get_text(&mut self) -> Result<String, String> {
+        match self {
+            Self::Arboard(clipboard) => clipboard.get_text().map_err(|e| e.to_string()),
+            #[cfg(linux)]
+            Self::Wayland(clipboard) => clipboard.load().map_err(|e| e.to_string()),
+        }
+    }
+
+    /// Set the text content of the clipboard.
+    pub fn set_text(&mut self, text: String) -> Result<(), String> {
+        match self {
+            Self::Arboard(clipboard) => clipboard.set_text(text).map_err(|e| e.to_string()),
+            #[cfg(linux)]
+            Self::Wayland(clipboard) => {
+                clipboard.store(text);
+                Ok(())
+            }
+        }
+    }
+}
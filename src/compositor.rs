@@ -20,6 +20,7 @@ use euclid::{vec2, Point2D, Scale, Size2D, Transform3D, Vector2D};
 use gleam::gl;
 use ipc_channel::ipc::{self, IpcSharedMemory};
 use log::{debug, error, trace, warn};
+use profile_traits::mem::{Report, ReportKind, ReportsChan};
 use profile_traits::time::{self as profile_time, ProfilerCategory};
 use profile_traits::{mem, time, time_profile};
 use script_traits::{
@@ -28,6 +29,9 @@ use script_traits::{
 };
 use servo_geometry::{DeviceIndependentIntSize, DeviceIndependentPixel};
 use style_traits::{CSSPixel, PinchZoomFactor};
+use versoview_messages::{
+    PipelineDiagnostics, VersoWebviewId, WebRenderDebugFlag, WebViewDiagnostics, WindowShape,
+};
 use webrender::{RenderApi, Transaction};
 use webrender_api::units::{
     DeviceIntPoint, DeviceIntRect, DeviceIntSize, DevicePixel, DevicePoint, LayoutPoint,
@@ -36,21 +40,46 @@ use webrender_api::units::{
 use webrender_api::{
     BorderRadius, BoxShadowClipMode, BuiltDisplayList, ClipMode, ColorF, CommonItemProperties,
     ComplexClipRegion, DirtyRect, DisplayListPayload, DocumentId, Epoch as WebRenderEpoch,
-    ExternalScrollId, FontInstanceFlags, FontInstanceKey, FontInstanceOptions, FontKey,
-    HitTestFlags, PipelineId as WebRenderPipelineId, PropertyBinding, ReferenceFrameKind,
-    RenderReasons, SampledScrollOffset, ScrollLocation, SpaceAndClipInfo, SpatialId,
-    SpatialTreeItemKey, TransformStyle,
+    ExternalScrollId, FillRule, FontInstanceFlags, FontInstanceKey, FontInstanceOptions, FontKey,
+    HitTestFlags, ImageData, ImageDescriptor, ImageDescriptorFlags, ImageFormat, ImageMask,
+    PipelineId as WebRenderPipelineId, PropertyBinding, ReferenceFrameKind, RenderReasons,
+    SampledScrollOffset, ScrollLocation, SpaceAndClipInfo, SpatialId, SpatialTreeItemKey,
+    TransformStyle,
 };
 use webrender_traits::display_list::{HitTestInfo, ScrollTree};
 use webrender_traits::{
     CompositorHitTestResult, CrossProcessCompositorMessage, ImageUpdate, UntrustedNodeAddress,
 };
 use winit::window::WindowId;
+use wr_malloc_size_of::MallocSizeOfOps;
 
 use crate::rendering::RenderingContext;
+use crate::stats::FrameStats;
 use crate::touch::{TouchAction, TouchHandler};
 use crate::window::Window;
 
+/// Minimum number of `DeviceIndependentPixel` a swipe has to overscroll the page to navigate.
+pub const SWIPE_NAVIGATION_MIN_SCREEN_PX: f32 = 120.0;
+
+/// Maximum width in `DeviceIndependentPixel` of the swipe navigation preview at the page edge.
+const SWIPE_PREVIEW_MAX_WIDTH: f32 = 48.0;
+
+/// Fraction of the fling velocity that is left after one second.
+const FLING_FRICTION: f32 = 0.05;
+
+/// Speed in `DevicePixel` per second below which a fling stops.
+const FLING_STOP_SPEED: f32 = 20.0;
+
+/// Scrolling inertia after a touch pan has been released.
+struct Fling {
+    /// Current velocity in `DevicePixel` per second.
+    velocity: Vector2D<f32, DevicePixel>,
+    /// Where the pan was released, scroll events are hit tested here.
+    cursor: DeviceIntPoint,
+    /// Time the fling was last advanced.
+    last_tick: Instant,
+}
+
 /// Data used to construct a compositor.
 pub struct InitialCompositorState {
     /// A channel to the compositor.
@@ -75,6 +104,15 @@ pub struct InitialCompositorState {
     pub webrender_gl: Rc<dyn gl::Gl>,
 }
 
+/// Items of WebRender's profiler shown in the performance overlay: the frame rate, the frame
+/// times of the backend and renderer with their graphs, and the memory of WebRender's caches.
+const OVERLAY_PROFILER_UI: &str = "FPS,|,Frame times,_,Time graphs,|,Memory";
+
+/// Debug flags WebRender needs to draw the performance overlay.
+const OVERLAY_DEBUG_FLAGS: webrender::DebugFlags = webrender::DebugFlags::PROFILER_DBG
+    .union(webrender::DebugFlags::GPU_TIME_QUERIES)
+    .union(webrender::DebugFlags::GPU_SAMPLE_QUERIES);
+
 /// Various debug and profiling flags that WebRender supports.
 #[derive(Clone)]
 pub enum WebRenderDebugOption {
@@ -101,6 +139,20 @@ pub enum MouseWindowEvent {
 const MAX_ZOOM: f32 = 8.0;
 const MIN_ZOOM: f32 = 0.1;
 
+/// Compositor state that belongs to a single window. The compositor keeps the state of every
+/// window that isn't the current one, and restores it when the window becomes current again.
+#[derive(Clone, Copy, Debug)]
+struct WindowState {
+    /// Size of the window's viewport.
+    viewport: DeviceIntSize,
+    /// The pixel density of the window's display.
+    scale_factor: Scale<f32, DeviceIndependentPixel, DevicePixel>,
+    /// "Desktop-style" zoom of the window.
+    page_zoom: Scale<f32, CSSPixel, DeviceIndependentPixel>,
+    /// "Mobile-style" zoom of the window.
+    viewport_zoom: PinchZoomFactor,
+}
+
 // NB: Never block on the Constellation, because sometimes the Constellation blocks on us.
 /// The Verso compositor contains a GL rendering context with a WebRender instance.
 /// The compositor will communicate with Servo using messages from the Constellation,
@@ -115,6 +167,15 @@ pub struct IOCompositor {
     /// The pixel density of the display.
     scale_factor: Scale<f32, DeviceIndependentPixel, DevicePixel>,
 
+    /// Saved state of the windows which aren't the current window.
+    window_states: HashMap<WindowId, WindowState>,
+
+    /// Recent composite timings of each window.
+    frame_stats: HashMap<WindowId, FrameStats>,
+
+    /// Debug flags from before the performance overlay was shown, `None` if it's hidden.
+    overlay: Option<webrender::DebugFlags>,
+
     /// The active webrender document.
     webrender_document: DocumentId,
 
@@ -140,8 +201,20 @@ pub struct IOCompositor {
     /// Tracks whether we should composite this frame.
     composition_request: CompositionRequest,
 
-    /// check if the surface is ready to present.
-    pub ready_to_present: bool,
+    /// The window whose surface has a newly composited frame ready to present.
+    pub ready_to_present: Option<WindowId>,
+
+    /// Region of the last composited frame that has changed. Empty means the whole surface.
+    pub damage_rects: Vec<DeviceIntRect>,
+
+    /// The window composited last. WebRender tracks the damage of previous frames for a single
+    /// surface, so it only applies if the same window is composited again.
+    last_composited_window: Option<WindowId>,
+
+    /// Horizontal distance a trackpad swipe has scrolled past the edge of the page, in
+    /// `DeviceIndependentPixel` like the trackpad scroll deltas. `None` if there's no swipe in
+    /// progress.
+    swipe_overscroll: Option<f32>,
 
     /// Tracks whether we are in the process of shutting down, or have shut down and should close
     /// the compositor.
@@ -165,6 +238,9 @@ pub struct IOCompositor {
     /// Touch input state machine
     touch_handler: TouchHandler,
 
+    /// The fling in progress after a touch pan, if any.
+    fling: Option<Fling>,
+
     /// Pending scroll/zoom events.
     pending_scroll_zoom_events: Vec<ScrollZoomEvent>,
 
@@ -354,8 +430,12 @@ impl IOCompositor {
             webviews: HashMap::new(),
             pipeline_details: HashMap::new(),
             scale_factor,
+            window_states: HashMap::new(),
+            frame_stats: HashMap::new(),
+            overlay: None,
             composition_request: CompositionRequest::NoCompositingNecessary,
             touch_handler: TouchHandler::new(),
+            fling: None,
             pending_scroll_zoom_events: Vec::new(),
             shutdown_state: ShutdownState::NotShuttingDown,
             page_zoom: Scale::new(1.0),
@@ -381,7 +461,10 @@ impl IOCompositor {
             pending_frames: 0,
             last_animation_tick: Instant::now(),
             is_animating: false,
-            ready_to_present: false,
+            ready_to_present: None,
+            damage_rects: Vec::new(),
+            last_composited_window: None,
+            swipe_overscroll: None,
         };
 
         // Make sure the GL state is OK
@@ -455,6 +538,7 @@ impl IOCompositor {
         self.shutdown_state = ShutdownState::FinishedShuttingDown;
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, level = "trace"))]
     fn handle_browser_message(
         &mut self,
         msg: CompositorMsg,
@@ -962,8 +1046,33 @@ impl IOCompositor {
         let scaled_viewport_rect =
             LayoutRect::from_origin_and_size(LayoutPoint::zero(), scaled_viewport_size);
 
-        let root_clip_id = builder.define_clip_rect(zoom_reference_frame, scaled_viewport_rect);
-        let root_clip_chain_id = builder.define_clip_chain(None, [root_clip_id]);
+        let mut root_clip_ids =
+            vec![builder.define_clip_rect(zoom_reference_frame, scaled_viewport_rect)];
+        match (&window.shape, window.shape_mask) {
+            (WindowShape::RoundedRectangle { radius }, _) => {
+                let radius = *radius as f32 * self.scale_factor.get() / zoom_factor;
+                let complex = ComplexClipRegion::new(
+                    scaled_viewport_rect,
+                    BorderRadius::uniform(radius),
+                    ClipMode::Clip,
+                );
+                root_clip_ids.push(builder.define_clip_rounded_rect(zoom_reference_frame, complex));
+            }
+            (WindowShape::Mask { .. }, Some(image)) => {
+                let mask = ImageMask {
+                    image,
+                    rect: scaled_viewport_rect,
+                };
+                root_clip_ids.push(builder.define_clip_image_mask(
+                    zoom_reference_frame,
+                    mask,
+                    &[],
+                    FillRule::Nonzero,
+                ));
+            }
+            _ => {}
+        }
+        let root_clip_chain_id = builder.define_clip_chain(None, root_clip_ids);
         for webview in window.painting_order() {
             if let Some(pipeline_id) = self.webviews.get(&webview.webview_id) {
                 let scaled_webview_rect =
@@ -1011,6 +1120,37 @@ impl IOCompositor {
             }
         }
 
+        // Preview of the swipe navigation at the edge the user is pulling on.
+        if let (Some(overscroll), Some(tab)) =
+            (self.swipe_overscroll, window.tab_manager.current_tab())
+        {
+            let scale = self.scale_factor.get();
+            let progress = (overscroll.abs() / SWIPE_NAVIGATION_MIN_SCREEN_PX).min(1.);
+            if progress > 0. {
+                let webview_rect = LayoutRect::from_untyped(
+                    &(tab.webview().rect.to_f32() / zoom_factor).to_untyped(),
+                );
+                let width = SWIPE_PREVIEW_MAX_WIDTH * scale * progress / zoom_factor;
+                let mut preview_rect = webview_rect;
+                if overscroll > 0. {
+                    preview_rect.max.x = preview_rect.min.x + width;
+                } else {
+                    preview_rect.min.x = preview_rect.max.x - width;
+                }
+                let root_space = SpaceAndClipInfo {
+                    spatial_id: zoom_reference_frame,
+                    clip_chain_id: root_clip_chain_id,
+                };
+                // The preview gets more opaque once releasing would navigate.
+                let alpha = if progress >= 1. { 0.5 } else { 0.2 };
+                builder.push_rect(
+                    &CommonItemProperties::new(preview_rect, root_space),
+                    preview_rect,
+                    ColorF::new(0.0, 0.0, 0.0, alpha),
+                );
+            }
+        }
+
         let built_display_list = builder.end();
 
         // NB: We are always passing 0 as the epoch here, but this doesn't seem to
@@ -1020,6 +1160,93 @@ impl IOCompositor {
         self.update_transaction_with_all_scroll_offsets(transaction);
     }
 
+    /// Mask the composited output of the window to a shape. The alpha mask of a
+    /// [`WindowShape::Mask`] is uploaded to WebRender and stretched over the window.
+    pub fn set_window_shape(&mut self, window: &mut Window, shape: WindowShape) {
+        let mut transaction = Transaction::new();
+        if let Some(image) = window.shape_mask.take() {
+            transaction.delete_image(image);
+        }
+        window.shape = match shape {
+            WindowShape::Mask {
+                width,
+                height,
+                ref alpha,
+            } if width == 0 || height == 0 || alpha.len() != width as usize * height as usize => {
+                error!(
+                    "Window shape mask of {width}x{height} has {} pixels",
+                    alpha.len()
+                );
+                WindowShape::Rectangle
+            }
+            WindowShape::Mask {
+                width,
+                height,
+                ref alpha,
+            } => {
+                let image = self.webrender_api.generate_image_key();
+                let descriptor = ImageDescriptor::new(
+                    width as i32,
+                    height as i32,
+                    ImageFormat::R8,
+                    ImageDescriptorFlags::empty(),
+                );
+                transaction.add_image(image, descriptor, ImageData::new(alpha.clone()), None);
+                window.shape_mask = Some(image);
+                shape
+            }
+            shape => shape,
+        };
+        if window.id() == self.current_window {
+            self.send_root_pipeline_display_list_in_transaction(&mut transaction, window);
+            self.generate_frame(&mut transaction, RenderReasons::SCENE);
+        }
+        self.webrender_api
+            .send_transaction(self.webrender_document, transaction);
+    }
+
+    /// Measure the memory used by WebRender, its scenes and resources on the CPU, and its
+    /// textures on the GPU.
+    pub fn collect_memory_reports(&self, reports_chan: ReportsChan) {
+        let Some(size_of_op) = crate::memory::USABLE_SIZE else {
+            reports_chan.send(Vec::new());
+            return;
+        };
+        let cpu = self
+            .webrender_api
+            .report_memory(MallocSizeOfOps::new(size_of_op, None));
+        let gpu = self
+            .webrender
+            .as_ref()
+            .map(|webrender| webrender.report_memory(std::ptr::null_mut()))
+            .unwrap_or_default();
+        let measurements = [
+            ("display-lists", cpu.display_list),
+            ("images", cpu.images),
+            ("fonts", cpu.fonts + cpu.weak_fonts),
+            ("clip-stores", cpu.clip_stores),
+            ("hit-testers", cpu.hit_testers),
+            ("shader-cache", cpu.shader_cache),
+            (
+                "gpu-cache",
+                cpu.gpu_cache_metadata + cpu.gpu_cache_cpu_mirror,
+            ),
+            ("gpu-cache-textures", gpu.gpu_cache_textures),
+            ("vertex-data-textures", gpu.vertex_data_textures),
+            ("render-target-textures", gpu.render_target_textures),
+            ("depth-target-textures", gpu.depth_target_textures),
+        ];
+        let reports = measurements
+            .into_iter()
+            .map(|(name, size)| Report {
+                path: vec!["webrender".to_owned(), name.to_owned()],
+                kind: ReportKind::ExplicitUnknownLocationSize,
+                size,
+            })
+            .collect();
+        reports_chan.send(reports);
+    }
+
     /// Update the given transaction with the scroll offsets of all active scroll nodes in
     /// the WebRender scene. This is necessary because WebRender does not preserve scroll
     /// offsets between scroll tree modifications. If a display list could potentially
@@ -1081,10 +1308,12 @@ impl IOCompositor {
             top_level_browsing_context_id
         );
         let mut window_id = None;
+        let mut found = false;
         for (window, _) in windows.values_mut() {
             let (webview, close_window) =
                 window.remove_webview(top_level_browsing_context_id, self);
             if let Some(webview) = webview {
+                found = true;
                 if let Some(pipeline_id) = self.webviews.remove(&webview.webview_id) {
                     self.remove_pipeline_details_recursively(pipeline_id);
                 }
@@ -1103,7 +1332,32 @@ impl IOCompositor {
         }
 
         if let Some(id) = window_id {
-            windows.remove(&id);
+            if let Some(image) = windows
+                .remove(&id)
+                .and_then(|(window, _)| window.shape_mask)
+            {
+                let mut transaction = Transaction::new();
+                transaction.delete_image(image);
+                self.webrender_api
+                    .send_transaction(self.webrender_document, transaction);
+            }
+            // Show one of the remaining windows instead.
+            if id == self.current_window {
+                if let Some((window, _)) = windows.values_mut().next() {
+                    self.swap_current_window(window);
+                }
+            }
+            self.window_states.remove(&id);
+            self.frame_stats.remove(&id);
+        }
+
+        // The window may have detached the webview already, e.g. a prompt dismissed when its tab
+        // navigated, or a redirected webview which handled its WebViewClosed first. Its pipeline
+        // is still dropped so the entry doesn't leak.
+        if !found {
+            if let Some(pipeline_id) = self.webviews.remove(&top_level_browsing_context_id) {
+                self.remove_pipeline_details_recursively(pipeline_id);
+            }
         }
     }
 
@@ -1177,6 +1431,65 @@ impl IOCompositor {
         }
     }
 
+    /// Collect the pipeline and its descendants.
+    fn collect_pipelines(&self, pipeline_id: PipelineId, pipelines: &mut HashSet<PipelineId>) {
+        if !pipelines.insert(pipeline_id) {
+            return;
+        }
+        for (&child_id, details) in &self.pipeline_details {
+            if details.parent_pipeline_id == Some(pipeline_id) {
+                self.collect_pipelines(child_id, pipelines);
+            }
+        }
+    }
+
+    /// Webviews with a frame tree which don't belong to any window.
+    pub fn detached_webviews(
+        &self,
+        windows: &HashMap<WindowId, (Window, DocumentId)>,
+    ) -> Vec<TopLevelBrowsingContextId> {
+        self.webviews
+            .keys()
+            .filter(|id| !windows.values().any(|(window, _)| window.has_webview(**id)))
+            .copied()
+            .collect()
+    }
+
+    /// Report the live webviews and pipelines to diagnose leaks.
+    pub fn pipeline_diagnostics(
+        &self,
+        windows: &HashMap<WindowId, (Window, DocumentId)>,
+        mut public_id: impl FnMut(TopLevelBrowsingContextId) -> VersoWebviewId,
+    ) -> PipelineDiagnostics {
+        let mut attached_pipelines = HashSet::new();
+        let webviews = self
+            .webviews
+            .iter()
+            .map(|(webview_id, root_pipeline_id)| {
+                let mut pipelines = HashSet::new();
+                self.collect_pipelines(*root_pipeline_id, &mut pipelines);
+                let diagnostics = WebViewDiagnostics {
+                    webview_id: public_id(*webview_id),
+                    attached: windows
+                        .values()
+                        .any(|(window, _)| window.has_webview(*webview_id)),
+                    pipelines: pipelines.len(),
+                };
+                attached_pipelines.extend(pipelines);
+                diagnostics
+            })
+            .collect();
+        let orphaned_pipelines = self
+            .pipeline_details
+            .keys()
+            .filter(|id| !attached_pipelines.contains(id))
+            .count();
+        PipelineDiagnostics {
+            webviews,
+            orphaned_pipelines,
+        }
+    }
+
     fn remove_pipeline_details_recursively(&mut self, pipeline_id: PipelineId) {
         self.pipeline_details.remove(&pipeline_id);
 
@@ -1206,9 +1519,37 @@ impl IOCompositor {
                 self.current_window,
                 window.id()
             );
+            let old_state = WindowState {
+                viewport: self.viewport,
+                scale_factor: self.scale_factor,
+                page_zoom: self.page_zoom,
+                viewport_zoom: self.viewport_zoom,
+            };
+            self.window_states.insert(self.current_window, old_state);
             self.current_window = window.id();
-            self.scale_factor = Scale::new(window.scale_factor() as f32);
-            self.resize(window.size(), window);
+
+            let scale_factor = Scale::new(window.scale_factor() as f32);
+            match self.window_states.remove(&window.id()) {
+                // Nothing changed while the window wasn't current, so its webviews are still laid
+                // out for this state. Only the shared surface and document need to switch over.
+                Some(state)
+                    if state.viewport == window.size() && state.scale_factor == scale_factor =>
+                {
+                    self.scale_factor = state.scale_factor;
+                    self.page_zoom = state.page_zoom;
+                    self.viewport_zoom = state.viewport_zoom;
+                    self.on_resize_window_event(state.viewport, window);
+                    self.send_root_pipeline_display_list(window);
+                }
+                state => {
+                    if let Some(state) = state {
+                        self.page_zoom = state.page_zoom;
+                        self.viewport_zoom = state.viewport_zoom;
+                    }
+                    self.scale_factor = scale_factor;
+                    self.resize(window.size(), window);
+                }
+            }
         }
     }
 
@@ -1266,13 +1607,20 @@ impl IOCompositor {
 
     /// Handle the window scale factor event and return a boolean to tell embedder if it should further
     /// handle the scale factor event.
-    pub fn on_scale_factor_event(&mut self, scale_factor: f32, window: &Window) -> bool {
+    pub fn on_scale_factor_event(&mut self, scale_factor: f32, window: &mut Window) -> bool {
         if self.shutdown_state != ShutdownState::NotShuttingDown {
             return false;
         }
 
+        // Other windows pick up their new scale factor when they become current again.
+        if window.id() != self.current_window {
+            return false;
+        }
+
         self.scale_factor = Scale::new(scale_factor);
-        self.update_after_zoom_or_hidpi_change(window);
+        // The panel and tab bar are sized in logical pixels, so the webviews have to be laid out
+        // again, and every webview needs the new device pixel ratio.
+        self.resize(window.size(), window);
         self.composite_if_necessary(CompositingReason::Resize);
         true
     }
@@ -1403,6 +1751,8 @@ impl IOCompositor {
     }
 
     fn on_touch_down(&mut self, id: TouchId, point: DevicePoint) {
+        // Touching the screen stops the page, like on native platforms.
+        self.fling = None;
         self.touch_handler.on_touch_down(id, point);
         self.send_touch_event(TouchEvent {
             action: TouchEventAction::Down,
@@ -1450,8 +1800,16 @@ impl IOCompositor {
             point,
         });
 
-        if let TouchAction::Click = self.touch_handler.on_touch_up(id, point) {
-            self.simulate_mouse_click(point);
+        match self.touch_handler.on_touch_up(id, point) {
+            TouchAction::Click => self.simulate_mouse_click(point),
+            TouchAction::Fling(velocity) => {
+                self.fling = Some(Fling {
+                    velocity,
+                    cursor: point.cast(),
+                    last_tick: Instant::now(),
+                });
+            }
+            _ => {}
         }
     }
 
@@ -1574,12 +1932,28 @@ impl IOCompositor {
                 combined_event.scroll_location,
             )
         });
-        if !zoom_changed && scroll_result.is_none() {
+
+        // A horizontal swipe that can't scroll anything anymore is pulling on the page edge.
+        let mut swipe_changed = false;
+        if let (Some(overscroll), None, Some(combined_event)) = (
+            self.swipe_overscroll.as_mut(),
+            scroll_result,
+            combined_scroll_event,
+        ) {
+            if let ScrollLocation::Delta(delta) = combined_event.scroll_location {
+                if delta.x != 0. {
+                    *overscroll += delta.x;
+                    swipe_changed = true;
+                }
+            }
+        }
+
+        if !zoom_changed && !swipe_changed && scroll_result.is_none() {
             return;
         }
 
         let mut transaction = Transaction::new();
-        if zoom_changed {
+        if zoom_changed || swipe_changed {
             self.send_root_pipeline_display_list_in_transaction(&mut transaction, window);
         }
 
@@ -1600,6 +1974,21 @@ impl IOCompositor {
             .send_transaction(self.webrender_document, transaction);
     }
 
+    /// Start tracking a trackpad swipe for history navigation.
+    pub fn begin_swipe(&mut self) {
+        self.swipe_overscroll = Some(0.);
+    }
+
+    /// Stop tracking the trackpad swipe and return how far it has overscrolled the page
+    /// horizontally, in `DeviceIndependentPixel`.
+    pub fn end_swipe(&mut self, window: &Window) -> f32 {
+        let overscroll = self.swipe_overscroll.take().unwrap_or_default();
+        if overscroll != 0. {
+            self.send_root_pipeline_display_list(window);
+        }
+        overscroll
+    }
+
     /// Perform a hit test at the given [`DevicePoint`] and apply the [`ScrollLocation`]
     /// scrolling to the applicable scroll node under that point. If a scroll was
     /// performed, returns the [`PipelineId`] of the node scrolled, the id, and the final
@@ -1853,9 +2242,15 @@ impl IOCompositor {
     }
 
     /// Composite to the given target if any, or the current target otherwise.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, level = "trace"))]
     pub fn composite(&mut self, window: &Window) {
+        let start = Instant::now();
         match self.composite_specific_target(window) {
             Ok(_) => {
+                self.frame_stats
+                    .entry(window.id())
+                    .or_default()
+                    .record(start.elapsed());
                 if self.exit_after_load {
                     println!("Shutting down the Constellation after generating an output file or exit flag specified");
                     self.start_shutting_down();
@@ -1867,6 +2262,16 @@ impl IOCompositor {
         }
     }
 
+    /// Get the recent composite timings of a window.
+    pub fn frame_stats(&self, window_id: WindowId) -> Option<&FrameStats> {
+        self.frame_stats.get(&window_id)
+    }
+
+    /// Get the number of pipelines known to the compositor, in all windows.
+    pub fn pipeline_count(&self) -> usize {
+        self.pipeline_details.len()
+    }
+
     /// Composite to the given target if any, or the current target otherwise.
     fn composite_specific_target(&mut self, window: &Window) -> Result<(), UnableToComposite> {
         if let Err(err) = self
@@ -1907,7 +2312,26 @@ impl IOCompositor {
                 // Paint the scene.
                 // TODO(gw): Take notice of any errors the renderer returns!
                 if let Some(webrender) = self.webrender.as_mut() {
-                    webrender.render(self.viewport, 0 /* buffer_age */).ok();
+                    // The previous frames were drawn to another surface, so redraw and present
+                    // all of this one.
+                    let same_surface = self
+                        .last_composited_window
+                        .replace(window.id())
+                        .is_some_and(|id| id == window.id());
+                    let buffer_age = if same_surface {
+                        self.rendering_context.buffer_age(&window.surface)
+                    } else {
+                        0
+                    };
+                    let dirty_rects = webrender
+                        .render(self.viewport, buffer_age)
+                        .map(|results| results.dirty_rects)
+                        .unwrap_or_default();
+                    self.damage_rects = if same_surface {
+                        dirty_rects
+                    } else {
+                        Vec::new()
+                    };
                 }
             },
         );
@@ -1915,7 +2339,7 @@ impl IOCompositor {
         self.send_pending_paint_metrics_messages_after_composite();
 
         self.composition_request = CompositionRequest::NoCompositingNecessary;
-        self.ready_to_present = true;
+        self.ready_to_present = Some(window.id());
 
         self.process_animations(true);
 
@@ -1947,6 +2371,7 @@ impl IOCompositor {
     }
 
     /// Receive and handle compositor messages.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, level = "trace"))]
     pub fn receive_messages(
         &mut self,
         windows: &mut HashMap<WindowId, (Window, DocumentId)>,
@@ -1977,6 +2402,7 @@ impl IOCompositor {
     }
 
     /// Perform composition and related actions.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, level = "trace"))]
     pub fn perform_updates(
         &mut self,
         windows: &mut HashMap<WindowId, (Window, DocumentId)>,
@@ -2003,6 +2429,7 @@ impl IOCompositor {
                 }
             }
 
+            self.advance_fling();
             if !self.pending_scroll_zoom_events.is_empty() {
                 self.process_pending_scroll_events(window)
             }
@@ -2010,6 +2437,31 @@ impl IOCompositor {
         self.shutdown_state != ShutdownState::FinishedShuttingDown
     }
 
+    /// Whether a fling is in progress and needs to be advanced every frame.
+    pub fn is_flinging(&self) -> bool {
+        self.fling.is_some()
+    }
+
+    /// Scroll by the distance the fling has travelled since the last frame and slow it down.
+    fn advance_fling(&mut self) {
+        let Some(fling) = &mut self.fling else {
+            return;
+        };
+        let now = Instant::now();
+        let elapsed = (now - fling.last_tick).as_secs_f32();
+        fling.last_tick = now;
+        let delta = fling.velocity * elapsed;
+        let cursor = fling.cursor;
+        fling.velocity = fling.velocity * FLING_FRICTION.powf(elapsed);
+        if fling.velocity.length() < FLING_STOP_SPEED {
+            self.fling = None;
+        }
+        self.on_scroll_window_event(
+            ScrollLocation::Delta(LayoutVector2D::from_untyped(delta.to_untyped())),
+            cursor,
+        );
+    }
+
     fn pinch_zoom_level(&self) -> Scale<f32, DevicePixel, DevicePixel> {
         Scale::new(self.viewport_zoom.get())
     }
@@ -2050,6 +2502,71 @@ impl IOCompositor {
             .send_transaction(self.webrender_document, txn);
     }
 
+    /// Turn one of WebRender's debug flags on or off.
+    pub fn set_webrender_debug_flag(&mut self, flag: WebRenderDebugFlag, enabled: bool) {
+        let Some(webrender) = self.webrender.as_mut() else {
+            return;
+        };
+        let flag = match flag {
+            WebRenderDebugFlag::Profiler => webrender::DebugFlags::PROFILER_DBG,
+            WebRenderDebugFlag::GpuTimeQueries => webrender::DebugFlags::GPU_TIME_QUERIES,
+            WebRenderDebugFlag::GpuSampleQueries => webrender::DebugFlags::GPU_SAMPLE_QUERIES,
+            WebRenderDebugFlag::TextureCache => webrender::DebugFlags::TEXTURE_CACHE_DBG,
+            WebRenderDebugFlag::RenderTargets => webrender::DebugFlags::RENDER_TARGET_DBG,
+            WebRenderDebugFlag::GpuCache => webrender::DebugFlags::GPU_CACHE_DBG,
+            WebRenderDebugFlag::Overdraw => webrender::DebugFlags::SHOW_OVERDRAW,
+            WebRenderDebugFlag::PictureCaching => webrender::DebugFlags::PICTURE_CACHING_DBG,
+            WebRenderDebugFlag::Epochs => webrender::DebugFlags::EPOCHS,
+        };
+        let mut flags = webrender.get_debug_flags();
+        if flags.contains(flag) == enabled {
+            return;
+        }
+        flags.set(flag, enabled);
+        webrender.set_debug_flags(flags);
+
+        let mut txn = Transaction::new();
+        self.generate_frame(&mut txn, RenderReasons::TESTING);
+        self.webrender_api
+            .send_transaction(self.webrender_document, txn);
+    }
+
+    /// Show or hide the performance overlay. WebRender draws it over every frame with its
+    /// profiler, so it works without a panel and in every window. Hiding it restores the profiler
+    /// flags set before, by the controller for example.
+    pub fn set_overlay(&mut self, enabled: bool) {
+        let Some(webrender) = self.webrender.as_mut() else {
+            return;
+        };
+        if self.overlay.is_some() == enabled {
+            return;
+        }
+        let mut flags = webrender.get_debug_flags();
+        match self.overlay.take() {
+            None => {
+                self.overlay = Some(flags);
+                webrender.set_profiler_ui(OVERLAY_PROFILER_UI);
+                flags.insert(OVERLAY_DEBUG_FLAGS);
+            }
+            Some(previous) => {
+                webrender.set_profiler_ui("Default");
+                flags.remove(OVERLAY_DEBUG_FLAGS);
+                flags.insert(previous.intersection(OVERLAY_DEBUG_FLAGS));
+            }
+        }
+        webrender.set_debug_flags(flags);
+
+        let mut txn = Transaction::new();
+        self.generate_frame(&mut txn, RenderReasons::TESTING);
+        self.webrender_api
+            .send_transaction(self.webrender_document, txn);
+    }
+
+    /// Whether the performance overlay is shown.
+    pub fn overlay(&self) -> bool {
+        self.overlay.is_some()
+    }
+
     fn add_font_instance(
         &mut self,
         instance_key: FontInstanceKey,
@@ -1,10 +1,16 @@
-use std::{fs, path::PathBuf};
+use std::{
+    borrow::Cow,
+    fs,
+    path::{Component, Path, PathBuf},
+    time::Duration,
+};
 
+use base64::{engine::general_purpose::STANDARD, Engine};
 use embedder_traits::resources::{self, Resource, ResourceReaderMethods};
 use headers::{ContentType, HeaderMapExt};
 use net::protocols::{ProtocolHandler, ProtocolRegistry};
 use net_traits::{
-    request::Request,
+    request::{Destination, Request},
     response::{Response, ResponseBody},
     ResourceFetchTiming,
 };
@@ -12,8 +18,63 @@ use servo_config::{
     opts::{set_options, Opts, OutputOptions},
     prefs::Preferences,
 };
+use versoview_messages::{ColorScheme, NavigationPolicy, NewWebViewPolicy};
 use winit::{dpi, window::WindowAttributes};
 
+use crate::{
+    integrity::IntegrityManifest,
+    keybinding::KeyBindings,
+    memory::{memory_report_data, MEMORY_REPORT_PLACEHOLDER},
+    navigation_policy::load_navigation_policy,
+    newtab::{load_bookmarks, newtab_data, Page, NEWTAB_DATA_PLACEHOLDER, NEWTAB_URL},
+    playlist::{load_playlist, PlaylistItem},
+    rendering::{GlBackend, RenderingSettings},
+    search::{SearchEngine, SearchEngines},
+    settings::{SETTINGS_TOKEN, SETTINGS_TOKEN_PLACEHOLDER},
+    site_overrides::{load_site_overrides, SiteOverride},
+    window::winit_theme,
+    wpt::{load_tests, DEFAULT_TEST_TIMEOUT},
+};
+
+/// Default height of a custom chrome page, the built-in panel and its tab bar.
+const DEFAULT_CHROME_HEIGHT: f64 = 80.0;
+
+/// Environment variable with the management token, which unlike the command line isn't visible to
+/// other users of the system.
+const MANAGEMENT_TOKEN_VAR: &str = "VERSO_MANAGEMENT_TOKEN";
+
+/// Page drawn as the browser UI instead of the built-in panel. It gets the `verso.*` API
+/// documented in `resources/components/verso.js`.
+#[derive(Clone, Debug)]
+pub enum ChromeSource {
+    /// Load the page from a URL.
+    Url(url::Url),
+    /// Load the page from an HTML file.
+    Path(PathBuf),
+    /// Use this HTML as the page, it can only refer to other resources by absolute URLs.
+    Html(String),
+}
+
+impl ChromeSource {
+    /// Get the URL to load the page from.
+    pub fn to_url(&self) -> Result<url::Url, String> {
+        match self {
+            Self::Url(url) => Ok(url.clone()),
+            Self::Path(path) => {
+                let path = fs::canonicalize(path)
+                    .map_err(|e| format!("Failed to find {}: {e}", path.display()))?;
+                url::Url::from_file_path(&path)
+                    .map_err(|()| format!("Invalid chrome path {}", path.display()))
+            }
+            Self::Html(html) => url::Url::parse(&format!(
+                "data:text/html;charset=utf-8;base64,{}",
+                STANDARD.encode(html)
+            ))
+            .map_err(|e| e.to_string()),
+        }
+    }
+}
+
 /// Servo time profile settings
 #[derive(Clone, Debug)]
 pub struct ProfilerSettings {
@@ -29,16 +90,34 @@ pub struct ProfilerSettings {
 pub struct CliArgs {
     /// URL to load initially.
     pub url: Option<url::Url>,
+    /// Page to load initially if `url` isn't set and for the Home action
+    pub homepage: Option<url::Url>,
+    /// Bookmarks shown on the new tab page
+    pub bookmarks: Vec<Page>,
     /// The IPC channel name used to communicate with the webview controller.
     pub ipc_channel: Option<String>,
     /// Should launch without control panel
     pub no_panel: bool,
+    /// Page to draw as the browser UI instead of the built-in panel
+    pub chrome: Option<ChromeSource>,
+    /// Height of the `chrome` page in logical pixels, the webviews are laid out below it
+    pub chrome_height: f64,
     /// Window settings for the initial winit window
     pub window_attributes: WindowAttributes,
     /// Port number to start a server to listen to remote Firefox devtools connections. 0 for random port.
     pub devtools_port: Option<u16>,
+    /// Port number to start a classic WebDriver server on.
+    pub webdriver_port: Option<u16>,
     /// Servo time profile settings
     pub profiler_settings: Option<ProfilerSettings>,
+    /// Path to write a Chrome trace of the time profiler's timings to when Verso exits
+    pub profiler_chrome_trace: Option<PathBuf>,
+    /// Write logs to `verso.log` in the config directory too
+    pub log_file: bool,
+    /// Write logs as JSON objects, one per line
+    pub log_json: bool,
+    /// Write minidumps of native crashes to the config directory
+    pub crash_reports: bool,
     /// Path to resource directory. If None, Verso will try to get default directory. And if that
     /// still doesn't exist, all resource configuration will set to default values.
     pub resource_dir: Option<PathBuf>,
@@ -48,8 +127,56 @@ pub struct CliArgs {
     pub init_script: Option<String>,
     /// The directory to load userscripts from
     pub userscripts_directory: Option<String>,
+    /// Compatibility fixes for sites which don't work well with Servo
+    pub site_overrides: Vec<SiteOverride>,
     /// Initial window's zoom level
     pub zoom_level: Option<f32>,
+    /// GL platform and driver settings
+    pub rendering_settings: RenderingSettings,
+    /// Navigate with mouse gestures
+    pub mouse_gestures: bool,
+    /// URL of the probe to detect captive portals with. Disabled if None.
+    pub captive_portal_probe: Option<url::Url>,
+    /// Open the captive portal in a new tab when one is detected
+    pub open_captive_portal: bool,
+    /// Directory to keep persistent data like the HSTS list, cookies and local storage in
+    pub config_dir: Option<PathBuf>,
+    /// Upgrade navigations to `http` URLs to `https`
+    pub https_only: bool,
+    /// Expected hashes of the files served by the `verso://` protocol
+    pub integrity_manifest: Option<IntegrityManifest>,
+    /// Keyboard shortcuts, the defaults overridden by the `--keybindings` file
+    pub keybindings: KeyBindings,
+    /// Force the light or dark theme, or follow the system's if None
+    pub color_scheme: Option<ColorScheme>,
+    /// Restart with a fresh session after this long without user input, for kiosks
+    pub idle_reset: Option<Duration>,
+    /// Pages the main window rotates through for digital signage, disabled if empty
+    pub playlist: Vec<PlaylistItem>,
+    /// Web platform tests to run one after another before exiting, disabled if empty
+    pub wpt_tests: Vec<url::Url>,
+    /// How long each web platform test has to report its result
+    pub wpt_timeout: Duration,
+    /// Search engines for URL bar input which isn't a URL
+    pub search_engines: SearchEngines,
+    /// Send URL bar input to the search engine for suggestions
+    pub search_suggestions: bool,
+    /// Endpoint to poll for management commands and the bearer token sent to it, disabled if
+    /// None
+    pub management: Option<(url::Url, String)>,
+    /// How often the management endpoint is polled
+    pub management_interval: Duration,
+    /// URL patterns which pages can and can't navigate to
+    pub navigation_policy: NavigationPolicy,
+    /// Where pages open new webviews with `window.open` or `target="_blank"` links
+    pub new_webview_policy: NewWebViewPolicy,
+    /// Block popups which pages open without user activation
+    pub block_popups: bool,
+    /// Exit when the content of a webview crashes instead of showing the crash page in it
+    pub hard_fail: bool,
+    /// Feed to check for updates and the minisign public key their artifacts are signed with,
+    /// updates are disabled if None
+    pub update: Option<(url::Url, String)>,
 }
 
 /// Configuration of Verso instance.
@@ -62,6 +189,9 @@ pub struct Config {
     /// Path to resource directory. If None, Verso will try to get default directory. And if that
     /// still doesn't exist, all resource configuration will set to default values.
     pub resource_dir: PathBuf,
+    /// Page to load initially if no URL is given and for the Home action, the new tab page if
+    /// `--homepage` isn't set.
+    pub homepage: url::Url,
 }
 
 fn parse_cli_args() -> Result<CliArgs, getopts::Fail> {
@@ -69,6 +199,18 @@ fn parse_cli_args() -> Result<CliArgs, getopts::Fail> {
 
     let mut opts = getopts::Options::new();
     opts.optopt("", "url", "URL to load on start", "docs.rs");
+    opts.optopt(
+        "",
+        "homepage",
+        "Page to load on start without --url and for the Home action, verso://newtab by default",
+        "URL",
+    );
+    opts.optopt(
+        "",
+        "bookmarks",
+        "Path to a JSON file with bookmarks for the new tab page, a list of {\"url\", \"title\"}",
+        "PATH",
+    );
     opts.optopt("", "resources", "Path to resource directory", "PATH");
     opts.optopt(
         "",
@@ -77,12 +219,46 @@ fn parse_cli_args() -> Result<CliArgs, getopts::Fail> {
         "",
     );
     opts.optflag("", "no-panel", "Launch Verso without control panel");
+    opts.optflag(
+        "",
+        "no-popup-blocker",
+        "Let pages open popups without the user clicking or typing in them first",
+    );
+    opts.optflag(
+        "",
+        "hard-fail",
+        "Exit when a page crashes instead of showing the crash page in its tab",
+    );
+    opts.optopt(
+        "",
+        "chrome-url",
+        "Page to draw as the browser UI instead of the built-in panel, see resources/components/verso.js",
+        "URL",
+    );
+    opts.optopt(
+        "",
+        "chrome-path",
+        "HTML file to draw as the browser UI instead of the built-in panel",
+        "PATH",
+    );
+    opts.optopt(
+        "",
+        "chrome-height",
+        "Height of the --chrome-url or --chrome-path page in logical pixels, defaults to 80",
+        "PX",
+    );
     opts.optopt(
         "",
         "devtools-port",
         "Launch Verso with devtools server enabled and listen to port",
         "1234",
     );
+    opts.optopt(
+        "",
+        "webdriver-port",
+        "Launch Verso with a WebDriver server listening to port",
+        "7000",
+    );
     opts.optopt(
         "p",
         "profiler",
@@ -101,6 +277,29 @@ fn parse_cli_args() -> Result<CliArgs, getopts::Fail> {
         "Path to dump a self-contained HTML timeline of profiler traces",
         "out.html",
     );
+    opts.optopt(
+        "",
+        "profiler-chrome-trace",
+        "Record the timings of servo profiler from launch and write them as a Chrome trace_event \
+        JSON file for Perfetto or chrome://tracing when Verso exits",
+        "trace.json",
+    );
+    opts.optflag(
+        "",
+        "log-file",
+        "Write logs to verso.log in --config-dir too, rotated when it grows past 10 MiB",
+    );
+    opts.optflag(
+        "",
+        "log-json",
+        "Write logs as JSON objects, one per line, for log aggregation",
+    );
+    opts.optflag(
+        "",
+        "crash-reports",
+        "Write minidumps of native crashes to --config-dir, and report them to the controller on \
+        the next start",
+    );
 
     opts.optopt(
         "",
@@ -114,6 +313,12 @@ fn parse_cli_args() -> Result<CliArgs, getopts::Fail> {
         "Script to run on document started to load",
         "console.log('hello world')",
     );
+    opts.optopt(
+        "",
+        "site-overrides",
+        "Path to a JSON file with compatibility fixes for sites, a list of {\"domains\", \"userAgent\", \"script\"}",
+        "PATH",
+    );
     opts.optopt(
         "",
         "userscripts-directory",
@@ -152,6 +357,153 @@ fn parse_cli_args() -> Result<CliArgs, getopts::Fail> {
     );
 
     opts.optopt("", "zoom", "Initial window's zoom level", "1.5");
+    opts.optflag(
+        "",
+        "mouse-gestures",
+        "Navigate with mouse gestures: hold the right button and move left, right or down",
+    );
+    opts.optflagopt(
+        "",
+        "captive-portal-check",
+        "Check for a captive portal on startup with a generate_204 style probe",
+        crate::connectivity::DEFAULT_PROBE_URL,
+    );
+    opts.optflag(
+        "",
+        "open-captive-portal",
+        "Open the captive portal in a new tab when one is detected",
+    );
+    opts.optopt(
+        "",
+        "keybindings",
+        "Path to a JSON file overriding keyboard shortcuts, a list of {\"accelerator\", \"action\"}",
+        "PATH",
+    );
+    opts.optopt(
+        "",
+        "config-dir",
+        "Directory to keep persistent data like the HSTS list, cookies and local storage in",
+        "PATH",
+    );
+    opts.optflag(
+        "",
+        "https-only",
+        "Upgrade navigations to http URLs to https, except for localhost. It can also be turned \
+         on in verso://settings, which saves it in the config directory",
+    );
+    opts.optopt(
+        "",
+        "search-engine",
+        "Search engine for URL bar input which isn't a URL: DuckDuckGo, Google, Bing, Wikipedia, or a URL with %s in place of the query",
+        "NAME|TEMPLATE",
+    );
+    opts.optmulti(
+        "",
+        "search-keyword",
+        "Search with this URL when the URL bar input starts with the keyword and a space, can be repeated",
+        "KEYWORD=TEMPLATE",
+    );
+    opts.optflag(
+        "",
+        "search-suggestions",
+        "Send URL bar input to the search engine for suggestions",
+    );
+    opts.optopt(
+        "",
+        "integrity-manifest",
+        "Path to a JSON file with the SRI hashes of the files served by verso://, others are refused",
+        "PATH",
+    );
+    opts.optflag("", "opaque", "Launch Verso with an opaque window");
+    opts.optflag(
+        "",
+        "decorated",
+        "Launch Verso with the platform's window decorations",
+    );
+    opts.optflag(
+        "",
+        "software-rendering",
+        "Rasterize in software, useful for VMs and machines with broken GL drivers. Only supported \
+         with Mesa GL drivers on Linux and BSD",
+    );
+    opts.optopt(
+        "",
+        "idle-reset",
+        "Restart with a fresh session after this many minutes without user input once the user interacted, browsing data isn't saved",
+        "MINUTES",
+    );
+    opts.optopt(
+        "",
+        "playlist",
+        "Path to a JSON file with pages to rotate through for digital signage, a list of {\"url\", \"seconds\"}",
+        "PATH",
+    );
+    opts.optopt(
+        "",
+        "wpt",
+        "Path to a file with web platform test URLs, one per line, to run and print a wptreport JSON of",
+        "PATH",
+    );
+    opts.optopt(
+        "",
+        "wpt-timeout",
+        "Seconds each web platform test has to report its result, 20 by default",
+        "SECONDS",
+    );
+    opts.optopt(
+        "",
+        "navigation-policy",
+        "Path to a JSON file with URL patterns pages can navigate to, {\"allow\", \"deny\", \"openBlockedExternally\"}",
+        "PATH",
+    );
+    opts.optopt(
+        "",
+        "new-webview-policy",
+        "Where pages open new webviews with window.open or target=\"_blank\" links, a new tab by default",
+        "tab|window|same|deny",
+    );
+    opts.optopt(
+        "",
+        "update-feed",
+        "HTTPS URL of a JSON feed announcing releases to update to, needs update-public-key",
+        "URL",
+    );
+    opts.optopt(
+        "",
+        "update-public-key",
+        "Minisign public key which update artifacts have to be signed with",
+        "KEY",
+    );
+    opts.optopt(
+        "",
+        "management-url",
+        "HTTPS endpoint to poll for remote management commands, a list of {\"command\", ...}, needs management-token",
+        "URL",
+    );
+    opts.optopt(
+        "",
+        "management-token",
+        "Bearer token sent to the management endpoint, VERSO_MANAGEMENT_TOKEN if not set",
+        "TOKEN",
+    );
+    opts.optopt(
+        "",
+        "management-interval",
+        "Seconds between polls of the management endpoint",
+        "SECONDS",
+    );
+    opts.optopt(
+        "",
+        "color-scheme",
+        "Force the light or dark theme instead of following the system's",
+        "light|dark",
+    );
+    opts.optopt(
+        "",
+        "gl-backend",
+        "GL platform API to render with, egl uses ANGLE on Windows",
+        "native|egl",
+    );
 
     let matches: getopts::Matches = opts.parse(&args[1..])?;
     let url = matches
@@ -168,13 +520,98 @@ fn parse_cli_args() -> Result<CliArgs, getopts::Fail> {
                 None
             }
         });
+    let homepage = matches
+        .opt_str("homepage")
+        .and_then(|url| match url::Url::parse(&url) {
+            Ok(url_parsed) => Some(url_parsed),
+            Err(e) => {
+                log::error!("Failed to parse homepage command line argument: {e}");
+                None
+            }
+        });
+    let bookmarks = match matches.opt_str("bookmarks") {
+        Some(path) => load_bookmarks(std::path::Path::new(&path)).unwrap_or_else(|e| {
+            log::error!("Failed to load bookmarks from {path}: {e}");
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+    let navigation_policy = match matches.opt_str("navigation-policy") {
+        Some(path) => load_navigation_policy(std::path::Path::new(&path)).unwrap_or_else(|e| {
+            log::error!("Failed to load navigation policy from {path}: {e}");
+            NavigationPolicy::default()
+        }),
+        None => NavigationPolicy::default(),
+    };
+    let new_webview_policy = match matches.opt_str("new-webview-policy").as_deref() {
+        Some("tab") | None => NewWebViewPolicy::NewTab,
+        Some("window") => NewWebViewPolicy::NewWindow,
+        Some("same") => NewWebViewPolicy::SameWebView,
+        Some("deny") => NewWebViewPolicy::Deny,
+        Some(other) => {
+            log::error!("Invalid new-webview-policy command line argument: {other}");
+            NewWebViewPolicy::default()
+        }
+    };
+    let playlist = match matches.opt_str("playlist") {
+        Some(path) => load_playlist(std::path::Path::new(&path)).unwrap_or_else(|e| {
+            log::error!("Failed to load playlist from {path}: {e}");
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+    let wpt_tests = match matches.opt_str("wpt") {
+        Some(path) => load_tests(std::path::Path::new(&path)).unwrap_or_else(|e| {
+            log::error!("Failed to load web platform tests from {path}: {e}");
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+    let wpt_timeout = matches
+        .opt_get::<u64>("wpt-timeout")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse wpt-timeout command line argument: {e}");
+            None
+        })
+        .map_or(DEFAULT_TEST_TIMEOUT, Duration::from_secs);
     let resource_dir = matches.opt_str("resources").map(PathBuf::from);
     let ipc_channel = matches.opt_str("ipc-channel");
     let no_panel = matches.opt_present("no-panel");
+    let chrome = match (
+        matches.opt_str("chrome-url"),
+        matches.opt_str("chrome-path"),
+    ) {
+        (Some(_), Some(_)) => {
+            log::error!("Invalid chrome command line arguments, only one of chrome-url and chrome-path can be used");
+            None
+        }
+        (Some(url), None) => match url::Url::parse(&url) {
+            Ok(url) => Some(ChromeSource::Url(url)),
+            Err(e) => {
+                log::error!("Failed to parse chrome-url command line argument: {e}");
+                None
+            }
+        },
+        (None, Some(path)) => Some(ChromeSource::Path(PathBuf::from(path))),
+        (None, None) => None,
+    };
+    let chrome_height = matches
+        .opt_get::<f64>("chrome-height")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse chrome-height command line argument: {e}");
+            None
+        })
+        .unwrap_or(DEFAULT_CHROME_HEIGHT);
     let devtools_port = matches.opt_get::<u16>("devtools-port").unwrap_or_else(|e| {
         log::error!("Failed to parse devtools-port command line argument: {e}");
         None
     });
+    let webdriver_port = matches
+        .opt_get::<u16>("webdriver-port")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse webdriver-port command line argument: {e}");
+            None
+        });
 
     let profiler_settings = if let Ok(Some(profiler_interval)) = matches.opt_get("profiler") {
         let profile_output = matches.opt_str("profiler-output-file");
@@ -191,11 +628,25 @@ fn parse_cli_args() -> Result<CliArgs, getopts::Fail> {
         None
     };
 
+    let profiler_chrome_trace = matches.opt_str("profiler-chrome-trace").map(PathBuf::from);
+    let log_file = matches.opt_present("log-file");
+    let log_json = matches.opt_present("log-json");
+    let crash_reports = matches.opt_present("crash-reports");
+
     let user_agent = matches.opt_str("user-agent");
     let init_script = matches.opt_str("init-script");
     let userscripts_directory = matches.opt_str("userscripts-directory");
+    let site_overrides = match matches.opt_str("site-overrides") {
+        Some(path) => load_site_overrides(std::path::Path::new(&path)).unwrap_or_else(|e| {
+            log::error!("Failed to load site overrides from {path}: {e}");
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
 
-    let mut window_attributes = winit::window::Window::default_attributes();
+    let mut window_attributes = winit::window::Window::default_attributes()
+        .with_transparent(!matches.opt_present("opaque"))
+        .with_decorations(matches.opt_present("decorated"));
 
     // set min inner size
     // should be at least able to show the whole control panel
@@ -247,6 +698,65 @@ fn parse_cli_args() -> Result<CliArgs, getopts::Fail> {
         _ => {}
     };
 
+    let idle_reset = matches
+        .opt_get::<u64>("idle-reset")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse idle-reset command line argument: {e}");
+            None
+        })
+        .map(|minutes| Duration::from_secs(minutes * 60));
+    // A restarted process can't connect to the controller again.
+    let idle_reset = if idle_reset.is_some() && ipc_channel.is_some() {
+        log::error!("Invalid idle-reset command line argument, it can't be used with a controller");
+        None
+    } else {
+        idle_reset
+    };
+
+    let update_feed = match matches.opt_str("update-feed") {
+        Some(url) => match url::Url::parse(&url) {
+            // The feed decides which version gets installed, so it must not be tampered with.
+            Ok(url) if url.scheme() == "https" || crate::http::is_loopback(&url) => Some(url),
+            Ok(url) => {
+                log::error!(
+                    "Invalid update-feed command line argument, {url} must be https unless it's a loopback address"
+                );
+                None
+            }
+            Err(e) => {
+                log::error!("Failed to parse update-feed command line argument: {e}");
+                None
+            }
+        },
+        None => None,
+    };
+    let update = match (update_feed, matches.opt_str("update-public-key")) {
+        // Restarting into the update would lose the connection to the controller.
+        (Some(_), _) if ipc_channel.is_some() => {
+            log::error!(
+                "Invalid update-feed command line argument, it can't be used with a controller"
+            );
+            None
+        }
+        (Some(feed), Some(public_key)) => Some((feed, public_key)),
+        (Some(_), None) => {
+            log::error!("Invalid update-feed command line argument, it needs update-public-key");
+            None
+        }
+        (None, _) => None,
+    };
+
+    let color_scheme = match matches.opt_str("color-scheme").as_deref() {
+        Some("light") => Some(ColorScheme::Light),
+        Some("dark") => Some(ColorScheme::Dark),
+        Some(other) => {
+            log::error!("Invalid color-scheme command line argument: {other}");
+            None
+        }
+        None => None,
+    };
+    window_attributes = window_attributes.with_theme(color_scheme.map(winit_theme));
+
     if !matches.opt_present("no-maximized") {
         window_attributes = window_attributes.with_maximized(true);
     }
@@ -256,18 +766,161 @@ fn parse_cli_args() -> Result<CliArgs, getopts::Fail> {
         None
     });
 
+    let rendering_settings = RenderingSettings {
+        backend: matches
+            .opt_get::<GlBackend>("gl-backend")
+            .unwrap_or_else(|e| {
+                log::error!("Failed to parse gl-backend command line argument: {e}");
+                None
+            })
+            .unwrap_or_default(),
+    };
+
+    let mouse_gestures = matches.opt_present("mouse-gestures");
+
+    let captive_portal_probe = if matches.opt_present("captive-portal-check") {
+        let probe = matches
+            .opt_str("captive-portal-check")
+            .unwrap_or_else(|| crate::connectivity::DEFAULT_PROBE_URL.to_owned());
+        match url::Url::parse(&probe) {
+            Ok(probe) => Some(probe),
+            Err(e) => {
+                log::error!("Failed to parse captive-portal-check command line argument: {e}");
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let open_captive_portal = matches.opt_present("open-captive-portal");
+
+    let management_url = match matches.opt_str("management-url") {
+        Some(url) => match url::Url::parse(&url) {
+            // Commands can navigate and change settings, so they must not be tampered with.
+            Ok(url) if url.scheme() == "https" || crate::http::is_loopback(&url) => Some(url),
+            Ok(url) => {
+                log::error!(
+                    "Invalid management-url command line argument, {url} must be https unless it's a loopback address"
+                );
+                None
+            }
+            Err(e) => {
+                log::error!("Failed to parse management-url command line argument: {e}");
+                None
+            }
+        },
+        None => None,
+    };
+    let management_token = matches
+        .opt_str("management-token")
+        .or_else(|| std::env::var(MANAGEMENT_TOKEN_VAR).ok())
+        .filter(|token| !token.is_empty());
+    let management = match (management_url, management_token) {
+        (Some(url), Some(token)) => Some((url, token)),
+        (Some(_), None) => {
+            log::error!(
+                "Invalid management-url command line argument, it needs management-token or {MANAGEMENT_TOKEN_VAR}"
+            );
+            None
+        }
+        (None, _) => None,
+    };
+    let management_interval = matches
+        .opt_get::<u64>("management-interval")
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse management-interval command line argument: {e}");
+            None
+        })
+        // Polling in a tight loop would flood the endpoint.
+        .map(|seconds| Duration::from_secs(seconds.max(1)))
+        .unwrap_or(crate::management::DEFAULT_POLL_INTERVAL);
+
+    let config_dir = matches.opt_str("config-dir").map(PathBuf::from);
+    if let Some(config_dir) = &config_dir {
+        if let Err(e) = fs::create_dir_all(config_dir) {
+            log::error!("Failed to create config-dir {}: {e}", config_dir.display());
+        }
+    }
+    let https_only = matches.opt_present("https-only");
+
+    let mut search_engines = SearchEngines::default();
+    for keyword in matches.opt_strs("search-keyword") {
+        let engine = match keyword.split_once('=') {
+            Some((keyword, template)) => SearchEngine::custom(keyword, Some(keyword), template),
+            None => Err(format!("{keyword} isn't KEYWORD=TEMPLATE")),
+        };
+        match engine {
+            Ok(engine) => search_engines.add(engine),
+            Err(e) => log::error!("Failed to parse search-keyword command line argument: {e}"),
+        }
+    }
+    if let Some(engine) = matches.opt_str("search-engine") {
+        if let Err(e) = search_engines.set_default(&engine) {
+            log::error!("Failed to parse search-engine command line argument: {e}");
+        }
+    }
+    let search_suggestions = matches.opt_present("search-suggestions");
+
+    let integrity_manifest = matches.opt_str("integrity-manifest").map(|path| {
+        IntegrityManifest::load(std::path::Path::new(&path)).unwrap_or_else(|e| {
+            // Refuse every file rather than serving unverified ones.
+            log::error!("Failed to load integrity manifest from {path}: {e}");
+            IntegrityManifest::default()
+        })
+    });
+
+    let keybindings = match matches.opt_str("keybindings") {
+        Some(path) => KeyBindings::load(std::path::Path::new(&path)).unwrap_or_else(|e| {
+            log::error!("Failed to load keybindings from {path}: {e}");
+            KeyBindings::default()
+        }),
+        None => KeyBindings::default(),
+    };
+
     Ok(CliArgs {
         url,
+        homepage,
+        bookmarks,
         resource_dir,
         ipc_channel,
         no_panel,
+        chrome,
+        chrome_height,
         window_attributes,
         devtools_port,
+        webdriver_port,
         profiler_settings,
+        profiler_chrome_trace,
+        log_file,
+        log_json,
+        crash_reports,
         user_agent,
         init_script,
         userscripts_directory,
+        site_overrides,
         zoom_level,
+        rendering_settings,
+        mouse_gestures,
+        captive_portal_probe,
+        open_captive_portal,
+        config_dir,
+        https_only,
+        integrity_manifest,
+        keybindings,
+        color_scheme,
+        idle_reset,
+        playlist,
+        wpt_tests,
+        wpt_timeout,
+        search_engines,
+        search_suggestions,
+        management,
+        management_interval,
+        navigation_policy,
+        new_webview_policy,
+        block_popups: !matches.opt_present("no-popup-blocker"),
+        hard_fail: matches.opt_present("hard-fail"),
+        update,
     })
 }
 
@@ -295,22 +948,41 @@ impl Config {
             opts.time_profiler_trace_path = profiler_settings.trace_path.clone();
         }
 
+        opts.webdriver_port = args.webdriver_port;
+        // Crashed pipelines are replaced by the crash page instead of bringing Verso down.
+        opts.hard_fail = args.hard_fail;
+        // Servo's resource threads load and save the HSTS list, cookies and local storage in here.
+        opts.config_dir = args.config_dir.clone();
+        // Sessions which are reset when idle must not leave anything behind.
+        if args.idle_reset.is_some() && opts.config_dir.take().is_some() {
+            log::warn!("Ignoring config-dir, browsing data isn't saved with idle-reset");
+        }
+
         if let Some(ref userscripts_directory) = args.userscripts_directory {
             opts.userscripts = Some(userscripts_directory.clone());
         }
 
         let resource_dir = args.resource_dir.clone().unwrap_or(resources_dir_path());
+        let homepage = args
+            .homepage
+            .clone()
+            .unwrap_or_else(|| url::Url::parse(NEWTAB_URL).unwrap());
 
         Self {
             opts,
             args,
             resource_dir,
+            homepage,
         }
     }
 
     /// Register URL scheme protocols
     pub fn create_protocols(&self) -> ProtocolRegistry {
-        let handler = ResourceReader(self.resource_dir.clone());
+        let handler = VersoProtocolHandler {
+            resource_dir: self.resource_dir.clone(),
+            integrity_manifest: self.args.integrity_manifest.clone(),
+            bookmarks: self.args.bookmarks.clone(),
+        };
         let mut protocols = ProtocolRegistry::with_internal_protocols();
         protocols.register("verso", handler);
         protocols
@@ -349,7 +1021,16 @@ impl ResourceReaderMethods for ResourceReader {
     }
 }
 
-impl ProtocolHandler for ResourceReader {
+/// Handler of the `verso://` protocol, which serves files from the resource directory.
+struct VersoProtocolHandler {
+    resource_dir: PathBuf,
+    /// Files are only served if they match this manifest if it's set.
+    integrity_manifest: Option<IntegrityManifest>,
+    /// Bookmarks shown on `verso://newtab`.
+    bookmarks: Vec<Page>,
+}
+
+impl ProtocolHandler for VersoProtocolHandler {
     fn load(
         &self,
         request: &mut Request,
@@ -357,10 +1038,51 @@ impl ProtocolHandler for ResourceReader {
         _context: &net::fetch::methods::FetchContext,
     ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Response> + Send>> {
         let current_url = request.current_url();
+        let host = current_url.host_str();
         let path = current_url.path();
-        let path = self.0.join(path.strip_prefix('/').unwrap_or(path));
+        // The settings page carries the token to change settings with, so it's only loaded as a
+        // top-level document. Framing it would let other pages trick the user into changing them.
+        if host == Some("settings") && request.destination != Destination::Document {
+            log::warn!(
+                "Refusing to load {current_url} as {:?}",
+                request.destination
+            );
+            return Box::pin(std::future::ready(Response::network_internal_error(
+                "verso://settings can only be loaded as a top-level document",
+            )));
+        }
+        let relative_path: Cow<str> = match host {
+            Some("settings") => "components/settings.html".into(),
+            Some("newtab") => "components/newtab.html".into(),
+            Some("memory") => "components/memory.html".into(),
+            Some("https-only") => "components/https_only.html".into(),
+            // Files and the entries of the integrity manifest are named without the percent
+            // encoding of URLs.
+            _ => percent_encoding::percent_decode_str(path.strip_prefix('/').unwrap_or(path))
+                .decode_utf8_lossy(),
+        };
+        // Decoding may have turned `..%2F` into a parent directory.
+        if !Path::new(&*relative_path)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            log::warn!("Refusing to load {current_url} outside of the resource directory");
+            return Box::pin(std::future::ready(Response::network_internal_error(
+                "Invalid path",
+            )));
+        }
+        let path = self.resource_dir.join(&*relative_path);
 
-        let response = if let Ok(file) = fs::read(path.clone()) {
+        let file = fs::read(path.clone()).ok().filter(|file| {
+            let Some(manifest) = &self.integrity_manifest else {
+                return true;
+            };
+            manifest
+                .verify(&relative_path, file)
+                .inspect_err(|e| log::error!("Refusing to serve {current_url}: {e}"))
+                .is_ok()
+        });
+        let response = if let Some(file) = file {
             let mut response = Response::new(
                 request.current_url(),
                 ResourceFetchTiming::new(request.timing_type()),
@@ -381,6 +1103,20 @@ impl ProtocolHandler for ResourceReader {
                 }
             }
 
+            // Fill in internal pages, the settings page is the only one which gets the token to
+            // change settings with.
+            let file = match host {
+                Some("settings") => String::from_utf8_lossy(&file)
+                    .replace(SETTINGS_TOKEN_PLACEHOLDER, SETTINGS_TOKEN.as_str())
+                    .into_bytes(),
+                Some("newtab") => String::from_utf8_lossy(&file)
+                    .replace(NEWTAB_DATA_PLACEHOLDER, &newtab_data(&self.bookmarks))
+                    .into_bytes(),
+                Some("memory") => String::from_utf8_lossy(&file)
+                    .replace(MEMORY_REPORT_PLACEHOLDER, &memory_report_data())
+                    .into_bytes(),
+                _ => file,
+            };
             *response.body.lock().unwrap() = ResponseBody::Done(file);
 
             response
@@ -0,0 +1,157 @@
+use std::thread;
+use std::time::Duration;
+
+use url::Url;
+use versoview_messages::ConnectivityState;
+use winit::event_loop::EventLoopProxy;
+
+use crate::{http, verso::EventLoopProxyMessage};
+
+/// Probe used when `--captive-portal-check` is given without a URL.
+pub const DEFAULT_PROBE_URL: &str = "http://connectivitycheck.gstatic.com/generate_204";
+
+/// How long to wait for connecting to and reading from the probe.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Largest response we read from the probe, which is enough for the login page of a portal.
+const MAX_RESPONSE_SIZE: u64 = 1024 * 1024;
+
+/// Check the connectivity in a background thread with a `generate_204` style probe, and send the
+/// result back to the event loop as [`EventLoopProxyMessage::ConnectivityChecked`].
+///
+/// The probe has to be a plain `http` URL which answers with `204 No Content`. A captive portal
+/// intercepts the request and answers with a redirect or its login page instead.
+pub fn check_connectivity(probe: Url, proxy: EventLoopProxy<EventLoopProxyMessage>) {
+    let spawn_result = thread::Builder::new()
+        .name("ConnectivityCheck".to_owned())
+        .spawn(move || {
+            let state = probe_connectivity(&probe);
+            log::debug!("Connectivity check with {probe} finished: {state:?}");
+            if let Err(error) = proxy.send_event(EventLoopProxyMessage::ConnectivityChecked(state))
+            {
+                log::error!("Failed to send connectivity state to Verso: {error}");
+            }
+        });
+    if let Err(error) = spawn_result {
+        log::error!("Failed to spawn connectivity check thread: {error}");
+    }
+}
+
+fn probe_connectivity(probe: &Url) -> ConnectivityState {
+    match fetch(probe) {
+        Ok(response) => classify(probe, &response),
+        Err(error) => {
+            log::info!("Connectivity probe failed: {error}");
+            ConnectivityState::Offline
+        }
+    }
+}
+
+/// Tell from the response of the probe whether it was intercepted. Only a page or a redirect
+/// means a captive portal, errors of the probe or something in between don't.
+fn classify(probe: &Url, response: &http::Response) -> ConnectivityState {
+    let location = response
+        .header("location")
+        .and_then(|location| probe.join(location).ok());
+    match (response.status, location) {
+        (204, _) => ConnectivityState::Online,
+        (300..400, Some(location)) if matches!(location.scheme(), "http" | "https") => {
+            log::info!("Connectivity probe was redirected, assuming a captive portal");
+            ConnectivityState::CaptivePortal(location)
+        }
+        // Anyone on the network can answer the probe, so it mustn't point Verso to local files
+        // or other applications.
+        (300..400, Some(location)) => {
+            log::warn!(
+                "Connectivity probe was redirected to {location}, assuming a captive portal"
+            );
+            ConnectivityState::CaptivePortal(probe.clone())
+        }
+        (200..300, _) if !response.body.is_empty() => {
+            log::info!("Connectivity probe answered with a page, assuming a captive portal");
+            ConnectivityState::CaptivePortal(probe.clone())
+        }
+        // Some probes answer 200 without a body.
+        (200..300, _) => ConnectivityState::Online,
+        (status, _) => {
+            log::info!("Connectivity probe answered with {status}, assuming no connectivity");
+            ConnectivityState::Offline
+        }
+    }
+}
+
+/// Send a GET request to the probe without following redirects.
+fn fetch(probe: &Url) -> std::io::Result<http::Response> {
+    if probe.scheme() != "http" {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "captive portal probe must be an http URL",
+        ));
+    }
+    let options = http::Options {
+        // The redirect of a portal is what we're looking for.
+        follow_redirects: false,
+        ..http::Options::new(PROBE_TIMEOUT, MAX_RESPONSE_SIZE)
+    };
+    http::fetch(probe, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: u16, location: Option<&str>, body: &[u8]) -> http::Response {
+        http::Response {
+            status,
+            headers: location
+                .map(|location| ("location".to_owned(), location.to_owned()))
+                .into_iter()
+                .collect(),
+            body: body.to_vec(),
+        }
+    }
+
+    #[test]
+    fn classify_responses() {
+        let probe = Url::parse(DEFAULT_PROBE_URL).unwrap();
+        let classify = |response| classify(&probe, &response);
+        assert_eq!(
+            classify(response(204, None, b"")),
+            ConnectivityState::Online
+        );
+        assert_eq!(
+            classify(response(200, None, b"")),
+            ConnectivityState::Online
+        );
+        assert_eq!(
+            classify(response(302, Some("http://portal.example/login"), b"")),
+            ConnectivityState::CaptivePortal(Url::parse("http://portal.example/login").unwrap())
+        );
+        for location in [
+            "file:///etc/passwd",
+            "verso://settings",
+            "javascript:alert(1)",
+        ] {
+            assert_eq!(
+                classify(response(302, Some(location), b"")),
+                ConnectivityState::CaptivePortal(probe.clone())
+            );
+        }
+        assert_eq!(
+            classify(response(200, None, b"<html>Sign in</html>")),
+            ConnectivityState::CaptivePortal(probe.clone())
+        );
+        assert_eq!(
+            classify(response(302, None, b"")),
+            ConnectivityState::Offline
+        );
+        assert_eq!(
+            classify(response(503, None, b"unavailable")),
+            ConnectivityState::Offline
+        );
+        assert_eq!(
+            classify(response(404, None, b"not found")),
+            ConnectivityState::Offline
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    process::{Child, Command},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crash_handler::{make_crash_event, CrashContext, CrashEventResult, CrashHandler};
+use minidumper::{Client, LoopAction, MinidumpBinary, Server, ServerHandler};
+use versoview_messages::CrashReport;
+
+/// Environment variable starting Verso as the crash monitor of another Verso, listening on the
+/// socket it points to.
+pub const MONITOR_SOCKET_VAR: &str = "VERSO_CRASH_MONITOR";
+
+/// Environment variable with the directory the crash monitor writes minidumps to.
+const DUMP_DIR_VAR: &str = "VERSO_CRASH_DUMP_DIR";
+
+/// Directory in the config directory keeping crash reports.
+const CRASHES_DIR: &str = "crashes";
+
+/// Directory in [`CRASHES_DIR`] the crash monitor writes minidumps to, they're moved out of it
+/// once they're sent to the controller.
+const PENDING_DIR: &str = "pending";
+
+/// How long Verso waits for the crash monitor to listen.
+const MONITOR_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Captures native crashes of Verso. A crashed process can't be trusted to write its own dump, so
+/// another Verso process is started as a monitor, and the crash handler asks it to write a
+/// minidump of this one.
+pub struct CrashReporter {
+    _handler: CrashHandler,
+    _monitor: Child,
+}
+
+impl CrashReporter {
+    /// Start the crash monitor writing minidumps to the config directory, and attach the crash
+    /// handler to Verso.
+    pub fn start(config_dir: &Path) -> io::Result<Self> {
+        let crashes_dir = config_dir.join(CRASHES_DIR);
+        let dump_dir = crashes_dir.join(PENDING_DIR);
+        fs::create_dir_all(&dump_dir)?;
+        // Not in the shared temporary directory, where another user could take the name first
+        // and receive the crash context.
+        let socket = crashes_dir.join(format!("monitor-{}.sock", std::process::id()));
+        let _ = fs::remove_file(&socket);
+        let monitor = Command::new(std::env::current_exe()?)
+            .env(MONITOR_SOCKET_VAR, &socket)
+            .env(DUMP_DIR_VAR, &dump_dir)
+            .spawn()?;
+
+        // The monitor takes a moment to start listening.
+        let deadline = Instant::now() + MONITOR_CONNECT_TIMEOUT;
+        let client = loop {
+            match Client::with_name(socket.as_path()) {
+                Ok(client) => break client,
+                Err(e) if Instant::now() >= deadline => {
+                    return Err(io::Error::other(format!(
+                        "Failed to connect to the crash monitor: {e}"
+                    )));
+                }
+                Err(_) => thread::sleep(Duration::from_millis(50)),
+            }
+        };
+
+        // SAFETY: The crash event runs in the crashed process, it only sends the crash context to
+        // the monitor and doesn't touch Verso's state.
+        let handler = CrashHandler::attach(unsafe {
+            make_crash_event(move |context: &CrashContext| {
+                CrashEventResult::Handled(client.request_dump(context).is_ok())
+            })
+        })
+        .map_err(io::Error::other)?;
+
+        Ok(Self {
+            _handler: handler,
+            _monitor: monitor,
+        })
+    }
+}
+
+/// Run as the crash monitor if Verso was started as one. Returns `false` if it wasn't, and the
+/// monitor exits when the monitored Verso does.
+pub fn run_monitor() -> bool {
+    let (Some(socket), Some(dump_dir)) = (
+        std::env::var_os(MONITOR_SOCKET_VAR),
+        std::env::var_os(DUMP_DIR_VAR),
+    ) else {
+        return false;
+    };
+    let socket = PathBuf::from(socket);
+    let mut server = match Server::with_name(socket.as_path()) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("Failed to start the crash monitor: {e}");
+            return true;
+        }
+    };
+    let handler = Box::new(DumpWriter {
+        dump_dir: PathBuf::from(dump_dir),
+    });
+    let shutdown = std::sync::atomic::AtomicBool::new(false);
+    if let Err(e) = server.run(handler, &shutdown, None) {
+        eprintln!("Crash monitor failed: {e}");
+    }
+    let _ = fs::remove_file(socket);
+    true
+}
+
+/// Writes the minidumps requested by the crash handler of the monitored Verso.
+struct DumpWriter {
+    dump_dir: PathBuf,
+}
+
+impl ServerHandler for DumpWriter {
+    fn create_minidump_file(&self) -> Result<(File, PathBuf), io::Error> {
+        let path = self.dump_dir.join(format!("{}.dmp", uuid::Uuid::new_v4()));
+        Ok((File::create(&path)?, path))
+    }
+
+    fn on_minidump_created(&self, result: Result<MinidumpBinary, minidumper::Error>) -> LoopAction {
+        match result {
+            Ok(dump) => eprintln!("Verso crashed, wrote a minidump to {}", dump.path.display()),
+            Err(e) => eprintln!("Verso crashed, failed to write a minidump: {e}"),
+        }
+        // The monitored Verso is gone.
+        LoopAction::Exit
+    }
+
+    fn on_message(&self, _kind: u32, _buffer: Vec<u8>) {}
+
+    fn on_client_disconnected(&self, _num_clients: usize) -> LoopAction {
+        LoopAction::Exit
+    }
+}
+
+/// Get the crashes of previous runs which haven't been reported yet. Their minidumps stay in the
+/// pending directory until they're sent with [`send_report`].
+pub fn pending_reports(config_dir: &Path) -> Vec<CrashReport> {
+    let Ok(entries) = fs::read_dir(config_dir.join(CRASHES_DIR).join(PENDING_DIR)) else {
+        return Vec::new();
+    };
+    let mut reports: Vec<_> = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let crashed_at = entry.metadata().and_then(|m| m.modified()).ok()?;
+            Some(CrashReport {
+                minidump: entry.path(),
+                crashed_at,
+            })
+        })
+        .collect();
+    reports.sort_by_key(|report| report.crashed_at);
+    reports
+}
+
+/// Send a pending crash with `send`, which returns whether it succeeded. The minidump is moved
+/// out of the pending directory first so the report points to where it's kept, and moved back if
+/// it couldn't be sent, so it's reported on the next start instead.
+pub fn send_report(report: CrashReport, send: impl FnOnce(CrashReport) -> bool) {
+    let pending = report.minidump;
+    let Some(reported) = pending
+        .parent()
+        .and_then(Path::parent)
+        .zip(pending.file_name())
+        .map(|(crashes_dir, name)| crashes_dir.join(name))
+    else {
+        return;
+    };
+    if let Err(e) = fs::rename(&pending, &reported) {
+        log::error!("Failed to move minidump {}: {e}", pending.display());
+        return;
+    }
+    let sent = send(CrashReport {
+        minidump: reported.clone(),
+        crashed_at: report.crashed_at,
+    });
+    if !sent {
+        if let Err(e) = fs::rename(&reported, &pending) {
+            log::error!("Failed to move minidump {} back: {e}", reported.display());
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::thread;
+
+use base::id::WebViewId;
+use compositing_traits::ConstellationMsg;
+use crossbeam_channel::Sender;
+use ipc_channel::ipc::{self, IpcSender};
+use script_traits::{
+    webdriver_msg::{WebDriverJSValue, WebDriverScriptCommand},
+    WebDriverCommandMsg,
+};
+use versoview_messages::{DomSnapshot, ToControllerMessage};
+
+use crate::verso::send_to_constellation;
+
+/// Serializes the DOM of the page with the computed style and layout of its elements.
+const DOM_SNAPSHOT_SCRIPT: &str = include_str!("../resources/components/dom_snapshot.js");
+
+/// Capture the DOM of the webview and send it to the controller. It runs in a background thread
+/// since walking a large page takes a while and the script blocks until the page has run it.
+pub fn capture(
+    webview_id: WebViewId,
+    constellation_sender: Sender<ConstellationMsg>,
+    to_controller_sender: IpcSender<ToControllerMessage>,
+) {
+    let spawn_result = thread::Builder::new()
+        .name("DomSnapshot".to_owned())
+        .spawn(move || {
+            let snapshot = snapshot(webview_id, &constellation_sender);
+            if let Err(error) =
+                to_controller_sender.send(ToControllerMessage::GetDomSnapshotResponse(snapshot))
+            {
+                log::error!("Verso failed to send GetDomSnapshotResponse to controller: {error}");
+            }
+        });
+    if let Err(error) = spawn_result {
+        log::error!("Failed to spawn DOM snapshot thread: {error}");
+    }
+}
+
+/// Run the snapshot script in the webview and parse its output.
+fn snapshot(
+    webview_id: WebViewId,
+    constellation_sender: &Sender<ConstellationMsg>,
+) -> Result<DomSnapshot, String> {
+    let (result_sender, result_receiver) =
+        ipc::channel().map_err(|error| format!("Failed to create a channel: {error}"))?;
+    send_to_constellation(
+        constellation_sender,
+        ConstellationMsg::WebDriverCommand(WebDriverCommandMsg::ScriptCommand(
+            webview_id.0,
+            WebDriverScriptCommand::ExecuteScript(DOM_SNAPSHOT_SCRIPT.to_owned(), result_sender),
+        )),
+    );
+    let result = result_receiver
+        .recv()
+        .map_err(|_| "The webview is closed".to_owned())?;
+    match result {
+        Ok(WebDriverJSValue::String(output)) => serde_json::from_str(&output)
+            .map_err(|error| format!("Invalid output of the snapshot script: {error}")),
+        Ok(value) => Err(format!(
+            "Unexpected result of the snapshot script: {value:?}"
+        )),
+        Err(error) => Err(format!("Failed to run the snapshot script: {error:?}")),
+    }
+}
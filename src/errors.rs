@@ -17,4 +17,7 @@ pub enum Error {
     /// IPC errors.
     #[error(transparent)]
     IpcError(#[from] ipc_channel::ipc::IpcError),
+    /// Software rendering was requested, but the platform's GL drivers can't be switched to it.
+    #[error("Software rendering is only supported with Mesa GL drivers on Linux and BSD")]
+    SoftwareRenderingUnsupported,
 }
@@ -0,0 +1,34 @@
+use std::process::Command;
+
+use url::Url;
+
+/// Schemes Verso loads itself, links with other schemes like `mailto:` or `tel:` are handed to
+/// the system.
+const INTERNAL_SCHEMES: &[&str] = &[
+    "http",
+    "https",
+    "file",
+    "data",
+    "blob",
+    "about",
+    "javascript",
+    "verso",
+];
+
+/// Whether the URL has to be opened by another application since Servo can't load it.
+pub fn is_external(url: &Url) -> bool {
+    !INTERNAL_SCHEMES.contains(&url.scheme())
+}
+
+/// Open a URL with the system's default handler, like the default browser or mail client.
+pub fn open_externally(url: &Url) {
+    #[cfg(target_os = "macos")]
+    let result = Command::new("open").arg(url.as_str()).spawn();
+    #[cfg(target_os = "windows")]
+    let result = Command::new("explorer").arg(url.as_str()).spawn();
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let result = Command::new("xdg-open").arg(url.as_str()).spawn();
+    if let Err(error) = result {
+        log::error!("Failed to open {url} with the system's default handler: {error}");
+    }
+}
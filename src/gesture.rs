@@ -0,0 +1,125 @@
+use webrender_api::units::DevicePoint;
+use winit::event::{ElementState, MouseButton};
+
+/// Minimum length of a trail gesture in `DeviceIndependentPixel`.
+const GESTURE_MIN_SCREEN_PX: f32 = 50.0;
+
+/// Navigation triggered by a mouse gesture.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GestureAction {
+    /// Go back in history.
+    Back,
+    /// Go forward in history.
+    Forward,
+    /// Reload the page.
+    Reload,
+}
+
+/// What to do with a mouse button event after the gesture handler has seen it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GestureResult {
+    /// Not part of a gesture, forward it to the page as usual.
+    Pass,
+    /// Part of a gesture, don't forward it to the page.
+    Consumed,
+    /// A gesture has completed, perform the action and don't forward the event.
+    Action(GestureAction),
+    /// The right button was released without a gesture. The right button press held back at
+    /// this point has to be forwarded first, then the release can be forwarded as usual.
+    Replay(DevicePoint),
+}
+
+/// Recognizer of mouse gestures:
+/// - Trail gestures: hold the right button and move left to go back, right to go forward,
+///   or up or down to reload.
+/// - Rocker gestures: hold the right button and click the left one to go back, or hold the left
+///   button and click the right one to go forward.
+///
+/// The right button press is held back from the page until we know it isn't a gesture, so the
+/// context menu doesn't show up at the end of a gesture.
+#[derive(Debug, Default)]
+pub struct MouseGestureHandler {
+    /// Where the right button was pressed, if it's held down for a possible gesture.
+    right_down_at: Option<DevicePoint>,
+    /// Last cursor position while the right button is held down.
+    last_point: Option<DevicePoint>,
+    /// Whether the left button is held down and was forwarded to the page.
+    left_down: bool,
+    /// Whether the next release of the left button belongs to a rocker gesture.
+    suppress_left_release: bool,
+    /// Whether the next release of the right button belongs to a rocker gesture.
+    suppress_right_release: bool,
+}
+
+impl MouseGestureHandler {
+    /// Track the cursor while a trail gesture may be in progress.
+    pub fn on_mouse_move(&mut self, point: DevicePoint) {
+        if self.right_down_at.is_some() {
+            self.last_point = Some(point);
+        }
+    }
+
+    /// Handle a mouse button event. `scale_factor` converts device pixels to logical pixels.
+    pub fn on_mouse_input(
+        &mut self,
+        button: MouseButton,
+        state: ElementState,
+        point: DevicePoint,
+        scale_factor: f32,
+    ) -> GestureResult {
+        match (button, state) {
+            (MouseButton::Left, ElementState::Pressed) => {
+                if self.right_down_at.is_some() {
+                    self.suppress_left_release = true;
+                    self.suppress_right_release = true;
+                    self.right_down_at = None;
+                    self.last_point = None;
+                    GestureResult::Action(GestureAction::Back)
+                } else {
+                    self.left_down = true;
+                    GestureResult::Pass
+                }
+            }
+            (MouseButton::Left, ElementState::Released) => {
+                self.left_down = false;
+                if std::mem::take(&mut self.suppress_left_release) {
+                    GestureResult::Consumed
+                } else {
+                    GestureResult::Pass
+                }
+            }
+            (MouseButton::Right, ElementState::Pressed) => {
+                if self.left_down {
+                    self.suppress_right_release = true;
+                    GestureResult::Action(GestureAction::Forward)
+                } else {
+                    self.right_down_at = Some(point);
+                    self.last_point = Some(point);
+                    GestureResult::Consumed
+                }
+            }
+            (MouseButton::Right, ElementState::Released) => {
+                if std::mem::take(&mut self.suppress_right_release) {
+                    return GestureResult::Consumed;
+                }
+                let Some(start) = self.right_down_at.take() else {
+                    return GestureResult::Pass;
+                };
+                let end = self.last_point.take().unwrap_or(point);
+                let delta = (end - start) / scale_factor;
+                if delta.x.abs().max(delta.y.abs()) < GESTURE_MIN_SCREEN_PX {
+                    GestureResult::Replay(start)
+                } else if delta.x.abs() > delta.y.abs() {
+                    if delta.x < 0. {
+                        GestureResult::Action(GestureAction::Back)
+                    } else {
+                        GestureResult::Action(GestureAction::Forward)
+                    }
+                } else {
+                    GestureResult::Action(GestureAction::Reload)
+                }
+            }
+            _ => GestureResult::Pass,
+        }
+    }
+}
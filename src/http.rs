@@ -0,0 +1,378 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+use url::{Host, Url};
+
+/// Redirects followed at most before a request fails.
+const MAX_REDIRECTS: usize = 5;
+
+/// Largest status line and headers we read.
+const MAX_HEAD_SIZE: u64 = 64 * 1024;
+
+/// Options of a request.
+#[derive(Clone, Copy, Debug)]
+pub struct Options<'a> {
+    /// How long to wait for connecting and for each read and write.
+    pub timeout: Duration,
+    /// Largest body to accept, a larger one fails the request.
+    pub max_size: u64,
+    /// Sent as `Authorization: Bearer` to the origin of the URL, and dropped on redirects to
+    /// other origins.
+    pub bearer_token: Option<&'a str>,
+    /// Follow redirects, they're returned as responses otherwise.
+    pub follow_redirects: bool,
+}
+
+impl Options<'_> {
+    /// Options following redirects without authorization.
+    pub fn new(timeout: Duration, max_size: u64) -> Self {
+        Self {
+            timeout,
+            max_size,
+            bearer_token: None,
+            follow_redirects: true,
+        }
+    }
+}
+
+/// A response with its whole body.
+#[derive(Debug)]
+pub struct Response {
+    /// Status code
+    pub status: u16,
+    /// Headers with their names in lowercase
+    pub headers: Vec<(String, String)>,
+    /// Body
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// Get the first value of a header by its lowercase name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+/// Send a GET request and return the body of a successful response, following redirects. The
+/// request fails if the body is larger than `max_size` bytes.
+pub fn get(url: &Url, timeout: Duration, max_size: u64) -> io::Result<Vec<u8>> {
+    get_with(url, Options::new(timeout, max_size))
+}
+
+/// Like [`get`], with more options.
+pub fn get_with(url: &Url, options: Options) -> io::Result<Vec<u8>> {
+    let response = fetch(url, options)?;
+    if !(200..300).contains(&response.status) {
+        return Err(io::Error::other(format!(
+            "request didn't succeed, status {}",
+            response.status
+        )));
+    }
+    Ok(response.body)
+}
+
+/// Send a GET request and return the response whatever its status is.
+pub fn fetch(url: &Url, options: Options) -> io::Result<Response> {
+    let (head, body) = send_following_redirects(url, options)?;
+    let mut body_bytes = Vec::new();
+    read_limited(body, &mut body_bytes, options.max_size, head.content_length)?;
+    Ok(Response {
+        status: head.status,
+        headers: head.headers,
+        body: body_bytes,
+    })
+}
+
+/// Send a GET request and stream the body of a successful response into `writer`, following
+/// redirects. Returns the size of the body, the request fails if it's larger than `max_size`.
+pub fn download(url: &Url, options: Options, writer: &mut impl Write) -> io::Result<u64> {
+    let (head, body) = send_following_redirects(url, options)?;
+    if !(200..300).contains(&head.status) {
+        return Err(io::Error::other(format!(
+            "request didn't succeed, status {}",
+            head.status
+        )));
+    }
+    read_limited(body, writer, options.max_size, head.content_length)
+}
+
+/// Whether the URL points to this device.
+pub fn is_loopback(url: &Url) -> bool {
+    match url.host() {
+        Some(Host::Domain(domain)) => domain == "localhost" || domain.ends_with(".localhost"),
+        Some(Host::Ipv4(ip)) => ip.is_loopback(),
+        Some(Host::Ipv6(ip)) => ip.is_loopback(),
+        None => false,
+    }
+}
+
+/// Status line and headers of a response.
+struct Head {
+    status: u16,
+    headers: Vec<(String, String)>,
+    /// Size of the body if the response tells it.
+    content_length: Option<u64>,
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(header, _)| header == name)
+        .map(|(_, value)| value.as_str())
+}
+
+fn send_following_redirects(url: &Url, options: Options) -> io::Result<(Head, Box<dyn Read>)> {
+    let mut url = url.clone();
+    let origin = url.origin();
+    for _ in 0..=MAX_REDIRECTS {
+        // Credentials only go to the origin they're meant for.
+        let bearer_token = options.bearer_token.filter(|_| url.origin() == origin);
+        let (head, body) = send(&url, options.timeout, bearer_token)?;
+        let location = find_header(&head.headers, "location");
+        let redirect = matches!(head.status, 301 | 302 | 303 | 307 | 308);
+        let (true, true, Some(location)) = (options.follow_redirects, redirect, location) else {
+            return Ok((head, body));
+        };
+        let next = url
+            .join(location)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if url.scheme() == "https" && next.scheme() != "https" {
+            return Err(io::Error::other(format!(
+                "refusing to follow the redirect from {url} to insecure {next}"
+            )));
+        }
+        url = next;
+    }
+    Err(io::Error::other("too many redirects"))
+}
+
+/// Connect to the host of the URL, trying each of its addresses.
+fn connect(url: &Url, timeout: Duration) -> io::Result<TcpStream> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidInput, message);
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| invalid("URL has no port"))?;
+    let addresses: Vec<SocketAddr> = match url.host() {
+        Some(Host::Domain(domain)) => (domain, port).to_socket_addrs()?.collect(),
+        Some(Host::Ipv4(ip)) => vec![SocketAddr::new(IpAddr::V4(ip), port)],
+        Some(Host::Ipv6(ip)) => vec![SocketAddr::new(IpAddr::V6(ip), port)],
+        None => return Err(invalid("URL has no host")),
+    };
+    let mut last_error = invalid("host didn't resolve");
+    for address in addresses {
+        match TcpStream::connect_timeout(&address, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(error) => last_error = error,
+        }
+    }
+    Err(last_error)
+}
+
+/// Send a single request and read the head of the response.
+fn send(
+    url: &Url,
+    timeout: Duration,
+    bearer_token: Option<&str>,
+) -> io::Result<(Head, Box<dyn Read>)> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidInput, message);
+    let stream = connect(url, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    let path = &url[url::Position::BeforePath..url::Position::AfterQuery];
+    // The host keeps the brackets of IPv6 addresses, and the port only if it isn't the default.
+    let host = &url[url::Position::BeforeHost..url::Position::AfterPort];
+    let authorization = bearer_token
+        .map(|token| format!("Authorization: Bearer {token}\r\n"))
+        .unwrap_or_default();
+    // HTTP/1.0 so the body isn't chunked.
+    let request = format!(
+        "GET {path} HTTP/1.0\r\nHost: {host}\r\nAccept: */*\r\nCache-Control: no-cache\r\n\
+        {authorization}Connection: close\r\n\r\n"
+    );
+    let mut stream: Box<dyn ReadWrite> = match url.scheme() {
+        "https" => {
+            let root_store = rustls::RootCertStore {
+                roots: webpki_roots::TLS_SERVER_ROOTS.into(),
+            };
+            let config = rustls::ClientConfig::builder()
+                .with_root_certificates(root_store)
+                .with_no_client_auth();
+            let server_name = match url.host() {
+                Some(Host::Domain(domain)) => {
+                    rustls::pki_types::ServerName::try_from(domain.to_owned())
+                        .map_err(|_| invalid("invalid host"))?
+                }
+                Some(Host::Ipv4(ip)) => IpAddr::V4(ip).into(),
+                Some(Host::Ipv6(ip)) => IpAddr::V6(ip).into(),
+                None => return Err(invalid("URL has no host")),
+            };
+            let connection = rustls::ClientConnection::new(Arc::new(config), server_name)
+                .map_err(io::Error::other)?;
+            Box::new(rustls::StreamOwned::new(connection, stream))
+        }
+        "http" => Box::new(stream),
+        _ => return Err(invalid("URL must be http or https")),
+    };
+    stream.write_all(request.as_bytes())?;
+    stream.flush()?;
+
+    let mut reader = BufReader::new(stream);
+    let head = read_head(&mut reader)?;
+    Ok((head, Box::new(reader)))
+}
+
+/// A stream requests are written to and responses read from.
+trait ReadWrite: Read + Write {}
+
+impl<T: Read + Write> ReadWrite for T {}
+
+/// Read the status line and headers.
+fn read_head(reader: &mut impl BufRead) -> io::Result<Head> {
+    let malformed = || io::Error::new(io::ErrorKind::InvalidData, "malformed HTTP response");
+    let mut head = reader.take(MAX_HEAD_SIZE);
+    let mut line = String::new();
+    head.read_line(&mut line)?;
+    let status = line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(malformed)?;
+    let mut headers = Vec::new();
+    loop {
+        line.clear();
+        if head.read_line(&mut line)? == 0 {
+            return Err(malformed());
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_owned()));
+        }
+    }
+    let content_length = match status {
+        // These never have a body, whatever the headers say.
+        100..200 | 204 | 304 => Some(0),
+        _ => find_header(&headers, "content-length")
+            .map(|length| length.parse().map_err(|_| malformed()))
+            .transpose()?,
+    };
+    Ok(Head {
+        status,
+        headers,
+        content_length,
+    })
+}
+
+/// Copy the body to the writer, failing if it's larger than `max_size` or shorter than its
+/// `content_length`. Returns the size of the body.
+fn read_limited(
+    mut body: impl Read,
+    writer: &mut impl Write,
+    max_size: u64,
+    content_length: Option<u64>,
+) -> io::Result<u64> {
+    let too_large = || io::Error::other(format!("response is larger than {max_size} bytes"));
+    if content_length.is_some_and(|length| length > max_size) {
+        return Err(too_large());
+    }
+    let mut buffer = [0; 16 * 1024];
+    let mut size = 0;
+    while content_length != Some(size) {
+        let read = match body.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        };
+        // Don't read past the body if the server sends more.
+        let read = content_length.map_or(read, |length| read.min((length - size) as usize));
+        size += read as u64;
+        if size > max_size {
+            return Err(too_large());
+        }
+        writer.write_all(&buffer[..read])?;
+    }
+    if content_length.is_some_and(|length| length != size) {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "response body was truncated",
+        ));
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A body whose connection is closed without a TLS close_notify.
+    struct Unterminated<'a>(&'a [u8]);
+
+    impl Read for Unterminated<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            self.0.read(buf)
+        }
+    }
+
+    fn head(response: &str) -> io::Result<Head> {
+        read_head(&mut response.as_bytes())
+    }
+
+    fn read(body: impl Read, max_size: u64, content_length: Option<u64>) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        let size = read_limited(body, &mut bytes, max_size, content_length)?;
+        assert_eq!(size, bytes.len() as u64);
+        Ok(bytes)
+    }
+
+    #[test]
+    fn content_length() {
+        let ok = head("HTTP/1.1 200 OK\r\nContent-Length: 5\r\nLocation: /a\r\n\r\n").unwrap();
+        assert_eq!((ok.status, ok.content_length), (200, Some(5)));
+        assert_eq!(find_header(&ok.headers, "location"), Some("/a"));
+        assert_eq!(
+            head("HTTP/1.1 200 OK\r\n\r\n").unwrap().content_length,
+            None
+        );
+        let no_content = head("HTTP/1.1 204 No Content\r\nContent-Length: 5\r\n\r\n").unwrap();
+        assert_eq!(no_content.content_length, Some(0));
+        assert!(head("HTTP/1.1 200 OK\r\nContent-Length: five\r\n\r\n").is_err());
+        assert!(head("HTTP/1.1 200 OK\r\n").is_err());
+    }
+
+    #[test]
+    fn bodies_with_content_length() {
+        assert_eq!(read(&b"hello"[..], 10, Some(5)).unwrap(), b"hello");
+        // Bytes after the body are ignored.
+        assert_eq!(read(&b"hello world"[..], 10, Some(5)).unwrap(), b"hello");
+        assert_eq!(read(&b""[..], 10, Some(0)).unwrap(), b"");
+        assert!(read(&b"hel"[..], 10, Some(5)).is_err());
+        // A missing close_notify is fine once the whole body has arrived.
+        assert_eq!(read(Unterminated(b"hello"), 10, Some(5)).unwrap(), b"hello");
+        assert_eq!(read(Unterminated(b""), 10, Some(0)).unwrap(), b"");
+        assert!(read(Unterminated(b"hel"), 10, Some(5)).is_err());
+    }
+
+    #[test]
+    fn bodies_without_content_length() {
+        assert_eq!(read(&b"hello"[..], 10, None).unwrap(), b"hello");
+        assert_eq!(read(&b""[..], 10, None).unwrap(), b"");
+        // The body may have been cut off.
+        assert!(read(Unterminated(b"hello"), 10, None).is_err());
+    }
+
+    #[test]
+    fn size_limit() {
+        assert_eq!(read(&b"0123456789"[..], 10, None).unwrap(), b"0123456789");
+        assert!(read(&b"0123456789a"[..], 10, None).is_err());
+        assert!(read(&b""[..], 10, Some(11)).is_err());
+    }
+}
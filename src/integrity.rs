@@ -0,0 +1,141 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+/// Hash algorithm of an integrity metadata entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Algorithm {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl Algorithm {
+    fn digest(self, content: &[u8]) -> Vec<u8> {
+        match self {
+            Algorithm::Sha256 => Sha256::digest(content).to_vec(),
+            Algorithm::Sha384 => Sha384::digest(content).to_vec(),
+            Algorithm::Sha512 => Sha512::digest(content).to_vec(),
+        }
+    }
+}
+
+/// Expected hashes of the files served by the `verso://` protocol.
+///
+/// The manifest is a JSON object mapping paths relative to the resource directory to
+/// [Subresource Integrity](https://www.w3.org/TR/SRI/) metadata, like
+/// `{ "components/panel.html": "sha384-oqVuAfXRKap7fdgcCY5uykM6+R9GqQ8K/uxy9rx7HNQlGYl1kPzQho1wx4JwY8wC" }`.
+/// Once a manifest is in use, files which are missing from it or don't match their hash are
+/// refused, so an app bundle can't be altered after it was built.
+#[derive(Clone, Debug, Default)]
+pub struct IntegrityManifest {
+    hashes: HashMap<String, (Algorithm, Vec<u8>)>,
+}
+
+impl IntegrityManifest {
+    /// Read a manifest from a JSON file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let entries: HashMap<String, String> =
+            serde_json::from_str(&content).map_err(|e| e.to_string())?;
+        let hashes = entries
+            .into_iter()
+            .map(|(file, metadata)| {
+                let hash = parse_metadata(&metadata)
+                    .ok_or_else(|| format!("Invalid integrity metadata for {file}: {metadata}"))?;
+                Ok((file.trim_start_matches('/').to_owned(), hash))
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self { hashes })
+    }
+
+    /// Check the content of a file at this path relative to the resource directory.
+    pub fn verify(&self, file: &str, content: &[u8]) -> Result<(), String> {
+        let Some((algorithm, expected)) = self.hashes.get(file.trim_start_matches('/')) else {
+            return Err(format!("{file} isn't listed in the integrity manifest"));
+        };
+        if algorithm.digest(content) == *expected {
+            Ok(())
+        } else {
+            Err(format!("{file} doesn't match its {algorithm:?} hash"))
+        }
+    }
+}
+
+/// Parse `<algorithm>-<base64 hash>` integrity metadata.
+fn parse_metadata(metadata: &str) -> Option<(Algorithm, Vec<u8>)> {
+    let (algorithm, hash) = metadata.trim().split_once('-')?;
+    let algorithm = match algorithm {
+        "sha256" => Algorithm::Sha256,
+        "sha384" => Algorithm::Sha384,
+        "sha512" => Algorithm::Sha512,
+        _ => return None,
+    };
+    let hash = STANDARD.decode(hash).ok()?;
+    (hash.len() == algorithm.digest(&[]).len()).then_some((algorithm, hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PANEL: &[u8] = b"<html>panel</html>";
+
+    fn load(name: &str, entries: &[(&str, String)]) -> Result<IntegrityManifest, String> {
+        let path = std::env::temp_dir().join(format!(
+            "verso-integrity-{name}-{}.json",
+            std::process::id()
+        ));
+        let entries: HashMap<_, _> = entries.iter().cloned().collect();
+        fs::write(&path, serde_json::to_string(&entries).unwrap()).unwrap();
+        let manifest = IntegrityManifest::load(&path);
+        fs::remove_file(&path).unwrap();
+        manifest
+    }
+
+    fn sha384(content: &[u8]) -> String {
+        format!("sha384-{}", STANDARD.encode(Sha384::digest(content)))
+    }
+
+    #[test]
+    fn matching_files() {
+        let manifest = load(
+            "matching",
+            &[
+                ("components/panel.html", sha384(PANEL)),
+                ("/components/my page.html", sha384(b"")),
+            ],
+        )
+        .unwrap();
+        assert!(manifest.verify("components/panel.html", PANEL).is_ok());
+        assert!(manifest.verify("/components/panel.html", PANEL).is_ok());
+        assert!(manifest.verify("components/my page.html", b"").is_ok());
+    }
+
+    #[test]
+    fn mismatching_files() {
+        let manifest = load("mismatching", &[("components/panel.html", sha384(PANEL))]).unwrap();
+        assert!(manifest
+            .verify("components/panel.html", b"<html>altered</html>")
+            .is_err());
+        assert!(manifest.verify("components/other.html", PANEL).is_err());
+        // Nothing passes an empty manifest, like the one used when it fails to load.
+        assert!(IntegrityManifest::default()
+            .verify("components/panel.html", PANEL)
+            .is_err());
+    }
+
+    #[test]
+    fn missing_manifest() {
+        let path = std::env::temp_dir().join("verso-integrity-missing.json");
+        assert!(IntegrityManifest::load(&path).is_err());
+    }
+
+    #[test]
+    fn invalid_metadata() {
+        assert!(load("unknown", &[("a.html", "md5-AAAA".to_owned())]).is_err());
+        assert!(load("short", &[("a.html", "sha256-AAAA".to_owned())]).is_err());
+        assert!(load("base64", &[("a.html", "sha256-!".to_owned())]).is_err());
+    }
+}
@@ -0,0 +1,362 @@
+use std::{fs, path::Path, str::FromStr};
+
+use keyboard_types::{Code, Modifiers};
+use versoview_messages::{KeyAction, KeyBinding};
+
+/// Modifiers that take part in matching a shortcut, lock keys are ignored.
+const SHORTCUT_MODIFIERS: Modifiers = Modifiers::SHIFT
+    .union(Modifiers::CONTROL)
+    .union(Modifiers::ALT)
+    .union(Modifiers::META);
+
+/// Default shortcuts, `CmdOrCtrl` is Command on macOS and Control elsewhere.
+const DEFAULT_KEY_BINDINGS: &[(&str, KeyAction)] = &[
+    ("CmdOrCtrl+R", KeyAction::Reload),
+    ("F5", KeyAction::Reload),
+    #[cfg(target_os = "macos")]
+    ("Cmd+[", KeyAction::Back),
+    #[cfg(target_os = "macos")]
+    ("Cmd+]", KeyAction::Forward),
+    #[cfg(not(target_os = "macos"))]
+    ("Alt+Left", KeyAction::Back),
+    #[cfg(not(target_os = "macos"))]
+    ("Alt+Right", KeyAction::Forward),
+    #[cfg(target_os = "macos")]
+    ("Cmd+Shift+H", KeyAction::Home),
+    #[cfg(not(target_os = "macos"))]
+    ("Alt+Home", KeyAction::Home),
+    ("CmdOrCtrl+=", KeyAction::ZoomIn),
+    ("CmdOrCtrl+Shift+=", KeyAction::ZoomIn),
+    ("CmdOrCtrl+-", KeyAction::ZoomOut),
+    ("CmdOrCtrl+0", KeyAction::ResetZoom),
+    ("Shift+F12", KeyAction::ToggleStats),
+    ("CmdOrCtrl+Shift+F12", KeyAction::ToggleOverlay),
+    ("CmdOrCtrl+T", KeyAction::NewTab),
+    ("CmdOrCtrl+W", KeyAction::CloseTab),
+    ("CmdOrCtrl+N", KeyAction::NewWindow),
+    ("CmdOrCtrl+Q", KeyAction::Quit),
+];
+
+/// A key combination of a keyboard shortcut.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Accelerator {
+    /// Modifiers which have to be held down.
+    pub modifiers: Modifiers,
+    /// Physical key to press.
+    pub code: Code,
+}
+
+impl FromStr for Accelerator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = Modifiers::empty();
+        let (modifier_names, key) = match s.rsplit_once('+') {
+            // `CmdOrCtrl++` binds the plus key.
+            Some((rest, "")) if rest.ends_with('+') => (&rest[..rest.len() - 1], "+"),
+            Some((rest, key)) => (rest, key),
+            None => ("", s),
+        };
+        for name in modifier_names.split('+').filter(|name| !name.is_empty()) {
+            modifiers |= match name.to_ascii_lowercase().as_str() {
+                "cmdorctrl" | "commandorcontrol" => {
+                    if cfg!(target_os = "macos") {
+                        Modifiers::META
+                    } else {
+                        Modifiers::CONTROL
+                    }
+                }
+                "ctrl" | "control" => Modifiers::CONTROL,
+                "cmd" | "command" | "super" | "meta" => Modifiers::META,
+                "alt" | "option" => Modifiers::ALT,
+                "shift" => Modifiers::SHIFT,
+                _ => return Err(format!("Unknown modifier {name} in shortcut {s}")),
+            };
+        }
+        let code =
+            code_from_name(key).ok_or_else(|| format!("Unknown key {key} in shortcut {s}"))?;
+        Ok(Self { modifiers, code })
+    }
+}
+
+fn code_from_name(name: &str) -> Option<Code> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        let code = match c.to_ascii_uppercase() {
+            'A' => Code::KeyA,
+            'B' => Code::KeyB,
+            'C' => Code::KeyC,
+            'D' => Code::KeyD,
+            'E' => Code::KeyE,
+            'F' => Code::KeyF,
+            'G' => Code::KeyG,
+            'H' => Code::KeyH,
+            'I' => Code::KeyI,
+            'J' => Code::KeyJ,
+            'K' => Code::KeyK,
+            'L' => Code::KeyL,
+            'M' => Code::KeyM,
+            'N' => Code::KeyN,
+            'O' => Code::KeyO,
+            'P' => Code::KeyP,
+            'Q' => Code::KeyQ,
+            'R' => Code::KeyR,
+            'S' => Code::KeyS,
+            'T' => Code::KeyT,
+            'U' => Code::KeyU,
+            'V' => Code::KeyV,
+            'W' => Code::KeyW,
+            'X' => Code::KeyX,
+            'Y' => Code::KeyY,
+            'Z' => Code::KeyZ,
+            '0' => Code::Digit0,
+            '1' => Code::Digit1,
+            '2' => Code::Digit2,
+            '3' => Code::Digit3,
+            '4' => Code::Digit4,
+            '5' => Code::Digit5,
+            '6' => Code::Digit6,
+            '7' => Code::Digit7,
+            '8' => Code::Digit8,
+            '9' => Code::Digit9,
+            '=' | '+' => Code::Equal,
+            '-' => Code::Minus,
+            '[' => Code::BracketLeft,
+            ']' => Code::BracketRight,
+            ',' => Code::Comma,
+            '.' => Code::Period,
+            '/' => Code::Slash,
+            _ => return None,
+        };
+        return Some(code);
+    }
+
+    let code = match name.to_ascii_lowercase().as_str() {
+        "f1" => Code::F1,
+        "f2" => Code::F2,
+        "f3" => Code::F3,
+        "f4" => Code::F4,
+        "f5" => Code::F5,
+        "f6" => Code::F6,
+        "f7" => Code::F7,
+        "f8" => Code::F8,
+        "f9" => Code::F9,
+        "f10" => Code::F10,
+        "f11" => Code::F11,
+        "f12" => Code::F12,
+        "plus" | "equal" => Code::Equal,
+        "minus" => Code::Minus,
+        "left" => Code::ArrowLeft,
+        "right" => Code::ArrowRight,
+        "up" => Code::ArrowUp,
+        "down" => Code::ArrowDown,
+        "escape" | "esc" => Code::Escape,
+        "enter" | "return" => Code::Enter,
+        "tab" => Code::Tab,
+        "space" => Code::Space,
+        "backspace" => Code::Backspace,
+        "delete" => Code::Delete,
+        "home" => Code::Home,
+        "end" => Code::End,
+        "pageup" => Code::PageUp,
+        "pagedown" => Code::PageDown,
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// Keyboard shortcuts of the browser, handled before key events reach the page.
+#[derive(Clone, Debug)]
+pub struct KeyBindings {
+    /// Bindings with the accelerator as it was written, so it can be reported back.
+    bindings: Vec<(Accelerator, String, KeyAction)>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let mut key_bindings = Self { bindings: vec![] };
+        for (accelerator, action) in DEFAULT_KEY_BINDINGS {
+            key_bindings.bind(accelerator, Some(*action)).unwrap();
+        }
+        key_bindings
+    }
+}
+
+impl KeyBindings {
+    /// Default shortcuts overridden by the bindings in a JSON file, which is a list of
+    /// `{ "accelerator": "CmdOrCtrl+R", "action": "Reload" }`, with `null` action to remove one.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let overrides: Vec<KeyBinding> =
+            serde_json::from_str(&content).map_err(|e| e.to_string())?;
+        let mut key_bindings = Self::default();
+        if let Err(e) = key_bindings.apply(overrides) {
+            log::error!("Skipped invalid keybindings in {}: {e}", path.display());
+        }
+        Ok(key_bindings)
+    }
+
+    /// Add, replace or remove shortcuts. Invalid accelerators are skipped and reported together.
+    pub fn apply(&mut self, overrides: Vec<KeyBinding>) -> Result<(), String> {
+        let errors: Vec<String> = overrides
+            .into_iter()
+            .filter_map(|binding| self.bind(&binding.accelerator, binding.action).err())
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join(", "))
+        }
+    }
+
+    /// Bind an accelerator to an action, or unbind it if the action is `None`.
+    fn bind(&mut self, accelerator: &str, action: Option<KeyAction>) -> Result<(), String> {
+        let parsed = accelerator.parse::<Accelerator>()?;
+        self.bindings.retain(|(existing, _, _)| *existing != parsed);
+        if let Some(action) = action {
+            self.bindings.push((parsed, accelerator.to_owned(), action));
+        }
+        Ok(())
+    }
+
+    /// Find the action bound to a key press.
+    pub fn action(&self, modifiers: Modifiers, code: Code) -> Option<KeyAction> {
+        let modifiers = modifiers & SHORTCUT_MODIFIERS;
+        self.bindings
+            .iter()
+            .find(|(accelerator, _, _)| {
+                accelerator.modifiers == modifiers && accelerator.code == code
+            })
+            .map(|(_, _, action)| *action)
+    }
+
+    /// List all shortcuts.
+    pub fn to_list(&self) -> Vec<KeyBinding> {
+        self.bindings
+            .iter()
+            .map(|(_, accelerator, action)| KeyBinding {
+                accelerator: accelerator.clone(),
+                action: Some(*action),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `CmdOrCtrl` on this platform.
+    const CMD_OR_CTRL: Modifiers = if cfg!(target_os = "macos") {
+        Modifiers::META
+    } else {
+        Modifiers::CONTROL
+    };
+
+    fn parse(s: &str) -> Result<Accelerator, String> {
+        s.parse()
+    }
+
+    fn binding(accelerator: &str, action: Option<KeyAction>) -> KeyBinding {
+        KeyBinding {
+            accelerator: accelerator.to_owned(),
+            action,
+        }
+    }
+
+    #[test]
+    fn modifiers() {
+        assert_eq!(
+            parse("Ctrl+Alt+Shift+F5"),
+            Ok(Accelerator {
+                modifiers: Modifiers::CONTROL | Modifiers::ALT | Modifiers::SHIFT,
+                code: Code::F5,
+            })
+        );
+        assert_eq!(
+            parse("cmd+option+left"),
+            Ok(Accelerator {
+                modifiers: Modifiers::META | Modifiers::ALT,
+                code: Code::ArrowLeft,
+            })
+        );
+        assert_eq!(
+            parse("Escape"),
+            Ok(Accelerator {
+                modifiers: Modifiers::empty(),
+                code: Code::Escape,
+            })
+        );
+    }
+
+    #[test]
+    fn cmd_or_ctrl() {
+        assert_eq!(
+            parse("CmdOrCtrl+Shift+R"),
+            Ok(Accelerator {
+                modifiers: CMD_OR_CTRL | Modifiers::SHIFT,
+                code: Code::KeyR,
+            })
+        );
+        assert_eq!(parse("CommandOrControl+r"), parse("CmdOrCtrl+R"));
+        assert_eq!(
+            parse("CmdOrCtrl++"),
+            Ok(Accelerator {
+                modifiers: CMD_OR_CTRL,
+                code: Code::Equal,
+            })
+        );
+        assert_eq!(parse("CmdOrCtrl+Plus"), parse("CmdOrCtrl++"));
+    }
+
+    #[test]
+    fn invalid_accelerators() {
+        assert!(parse("").is_err());
+        assert!(parse("Ctrl+").is_err());
+        assert!(parse("Hyper+R").is_err());
+        assert!(parse("Ctrl+F13").is_err());
+        assert!(parse("Ctrl+RR").is_err());
+    }
+
+    #[test]
+    fn lock_keys_are_ignored() {
+        let key_bindings = KeyBindings::default();
+        assert_eq!(
+            key_bindings.action(CMD_OR_CTRL | Modifiers::CAPS_LOCK, Code::KeyR),
+            Some(KeyAction::Reload)
+        );
+        assert_eq!(
+            key_bindings.action(CMD_OR_CTRL | Modifiers::SHIFT, Code::KeyR),
+            None
+        );
+    }
+
+    #[test]
+    fn overrides() {
+        let mut key_bindings = KeyBindings::default();
+        let result = key_bindings.apply(vec![
+            binding("CmdOrCtrl+R", Some(KeyAction::NewTab)),
+            binding("F5", None),
+            binding("Hyper+X", Some(KeyAction::Quit)),
+            binding("Ctrl+Shift+N", Some(KeyAction::NewWindow)),
+        ]);
+        assert!(result.is_err_and(|e| e.contains("Hyper")));
+        assert_eq!(
+            key_bindings.action(CMD_OR_CTRL, Code::KeyR),
+            Some(KeyAction::NewTab)
+        );
+        assert_eq!(key_bindings.action(Modifiers::empty(), Code::F5), None);
+        assert_eq!(
+            key_bindings.action(Modifiers::CONTROL | Modifiers::SHIFT, Code::KeyN),
+            Some(KeyAction::NewWindow)
+        );
+        // A replaced binding is listed once, with the accelerator as it was written.
+        let reload: Vec<_> = key_bindings
+            .to_list()
+            .into_iter()
+            .filter(|binding| binding.accelerator == "CmdOrCtrl+R")
+            .collect();
+        assert_eq!(reload.len(), 1);
+        assert_eq!(reload[0].action, Some(KeyAction::NewTab));
+    }
+}
@@ -5,24 +5,77 @@
 
 #![deny(missing_docs)]
 
+/// System clipboard access.
+pub mod clipboard;
 /// Verso's compositor component to handle webrender.
 pub mod compositor;
 /// Utilities to read options and preferences.
 pub mod config;
+/// Network connectivity and captive portal checks.
+pub mod connectivity;
+/// Minidumps of native crashes, written by a crash monitor process.
+pub mod crash;
+/// Snapshots of the DOM, computed style and layout of pages for audit tools.
+pub mod dom_snapshot;
 /// Error and result types.
 pub mod errors;
+/// Links which other applications of the system open, like `mailto:`.
+pub mod external;
+/// Utilities to recognize mouse gestures.
+pub mod gesture;
+/// Minimal blocking HTTP client for background tasks.
+pub mod http;
+/// Integrity verification of the files served by the `verso://` protocol.
+pub mod integrity;
+/// Configurable keyboard shortcuts.
+pub mod keybinding;
 /// Utilities to handle keyboard inputs and states.
 pub mod keyboard;
+/// Logging to stderr and a log file, with a filter that can be changed while running.
+pub mod logging;
+/// Remote management of signage fleets by polling an HTTP endpoint.
+pub mod management;
+/// Memory reports of the `verso://memory` page and the controller.
+pub mod memory;
+/// URL patterns which pages can and can't navigate to.
+pub mod navigation_policy;
+/// The `verso://newtab` page with recent pages and bookmarks.
+pub mod newtab;
+/// Signage playlists rotating through pages.
+pub mod playlist;
+/// Blocking of popups pages open without user activation.
+pub mod popup_blocker;
 /// Verso's rendering context.
 pub mod rendering;
+/// JavaScript console of a webview for the controller.
+pub mod repl;
+/// Search engines and suggestions for URL bar input.
+pub mod search;
+/// Settings which the panel and the `verso://settings` page can change.
+pub mod settings;
+/// Per-site compatibility fixes applied when pages load.
+pub mod site_overrides;
+/// Timing of the phases of Verso's startup.
+pub mod startup;
+/// Compositor frame statistics for diagnostics.
+pub mod stats;
+/// Spans of Verso's event loop, compositor and IPC handling with the `tracing` crate.
+#[cfg(feature = "tracing")]
+pub mod telemetry;
 /// Utilities to handle touch inputs and states.
 pub mod touch;
+/// Chrome traces of Servo's time profiler.
+pub mod trace;
+/// Self-updates of standalone distributions with signed artifacts.
+pub mod updater;
 /// Main entry types and functions.
 pub mod verso;
 /// Web view types to handle web browsing contexts.
 pub mod webview;
 /// Verso's window types to handle Winit's window.
 pub mod window;
+/// Runner of web platform tests for `--wpt`.
+pub mod wpt;
 pub use errors::{Error, Result};
 /// Utilities to write tests.
 // pub mod test;
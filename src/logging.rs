@@ -0,0 +1,176 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{LazyLock, RwLock},
+};
+
+use compositing_traits::ConstellationMsg;
+use constellation::FromCompositorLogger;
+use crossbeam_channel::Sender;
+use env_logger::filter::{self, Filter};
+use log::{LevelFilter, Log, Metadata, Record};
+use serde_json::json;
+
+/// Name of the log file in the config directory.
+const LOG_FILE_NAME: &str = "verso.log";
+
+/// Size the log file grows to before it's rotated.
+const MAX_LOG_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Rotated log files kept next to the current one, as `verso.log.1` to `verso.log.3`.
+const MAX_ROTATED_LOG_FILES: usize = 3;
+
+/// Filter of the logs written to stderr and the log file. It starts from `RUST_LOG` and can be
+/// changed while running, so it's kept outside the logger installed in the `log` crate.
+static FILTER: LazyLock<RwLock<Filter>> =
+    LazyLock::new(|| RwLock::new(filter::Builder::from_env("RUST_LOG").build()));
+
+/// Where and how logs are written.
+#[derive(Debug, Clone, Default)]
+pub struct LogSettings {
+    /// Write logs to a file in this directory as well, rotated when it grows too large.
+    pub file_dir: Option<PathBuf>,
+    /// Write every log as a JSON object on a line of its own.
+    pub json: bool,
+}
+
+/// Verso's logger. Logs go to stderr, the log file if there's one, and warnings and errors are
+/// also reported to the constellation like Servo's own.
+struct Logger {
+    stderr: env_logger::Logger,
+    file: Option<env_logger::Logger>,
+    constellation: FromCompositorLogger,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        FILTER.read().unwrap().enabled(metadata) || self.constellation.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if FILTER.read().unwrap().matches(record) {
+            self.stderr.log(record);
+            if let Some(file) = &self.file {
+                file.log(record);
+            }
+        }
+        self.constellation.log(record);
+    }
+
+    fn flush(&self) {
+        self.stderr.flush();
+        if let Some(file) = &self.file {
+            file.flush();
+        }
+        self.constellation.flush();
+    }
+}
+
+/// Install Verso's logger.
+pub fn init(constellation_sender: Sender<ConstellationMsg>, settings: LogSettings) {
+    let constellation = FromCompositorLogger::new(constellation_sender);
+    let file = settings.file_dir.and_then(|dir| {
+        RotatingFile::open(dir.join(LOG_FILE_NAME))
+            .inspect_err(|e| eprintln!("Failed to open log file in {}: {e}", dir.display()))
+            .ok()
+    });
+    let logger = Logger {
+        stderr: builder(settings.json).build(),
+        file: file.map(|file| {
+            builder(settings.json)
+                .target(env_logger::Target::Pipe(Box::new(file)))
+                .write_style(env_logger::WriteStyle::Never)
+                .build()
+        }),
+        constellation,
+    };
+    let max_level = std::cmp::max(
+        FILTER.read().unwrap().filter(),
+        logger.constellation.filter(),
+    );
+    log::set_boxed_logger(Box::new(logger)).expect("Failed to set logger.");
+    log::set_max_level(max_level);
+}
+
+/// Change which logs are written, with the syntax of `RUST_LOG` like `info,net=debug`.
+pub fn set_filter(spec: &str) {
+    let filter = filter::Builder::new().parse(spec).build();
+    // Warnings and errors are still reported to the constellation whatever the filter is, like
+    // `FromCompositorLogger` does.
+    log::set_max_level(std::cmp::max(filter.filter(), LevelFilter::Warn));
+    *FILTER.write().unwrap() = filter;
+    log::info!("Log filter set to {spec}");
+}
+
+/// A logger writing every record, the filter is applied by [`Logger`] before.
+fn builder(json: bool) -> env_logger::Builder {
+    // Only the write style is read from the environment, `RUST_LOG` is parsed into [`FILTER`].
+    let mut builder =
+        env_logger::Builder::from_env(env_logger::Env::new().write_style("RUST_LOG_STYLE"));
+    builder.filter_level(LevelFilter::Trace);
+    if json {
+        builder.format(|buf, record| {
+            let line = json!({
+                "timestamp": buf.timestamp_millis().to_string(),
+                "level": record.level().as_str(),
+                "target": record.target(),
+                "message": record.args().to_string(),
+            });
+            writeln!(buf, "{line}")
+        });
+    }
+    builder
+}
+
+/// A log file that's moved aside to `verso.log.1` when it grows larger than
+/// [`MAX_LOG_FILE_SIZE`], shifting older files up to [`MAX_ROTATED_LOG_FILES`].
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf) -> io::Result<Self> {
+        let file = File::options().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path, file, size })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        for index in (1..MAX_ROTATED_LOG_FILES).rev() {
+            let from = rotated_path(&self.path, index);
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.path, index + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        *self = Self::open(self.path.clone())?;
+        Ok(())
+    }
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{index}"));
+    PathBuf::from(name)
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > MAX_LOG_FILE_SIZE {
+            // Keep writing to the current file if it can't be rotated, rather than losing logs.
+            if let Err(e) = self.rotate() {
+                eprintln!("Failed to rotate {}: {e}", self.path.display());
+            }
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
@@ -1,9 +1,12 @@
 // Prevent console window from appearing on Windows
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use versoview::rendering::{enable_software_rendering, SOFTWARE_RENDERING_FLAG};
 use versoview::verso::EventLoopProxyMessage;
 use versoview::{Result, Verso};
+use versoview_messages::ToVersoMessage;
 use winit::application::ApplicationHandler;
+use winit::event::StartCause;
 use winit::event_loop::{self, DeviceEvents};
 use winit::event_loop::{EventLoop, EventLoopProxy};
 
@@ -17,6 +20,16 @@ impl ApplicationHandler<EventLoopProxyMessage> for App {
         self.verso = Some(Verso::new(event_loop, self.proxy.clone()));
     }
 
+    fn new_events(&mut self, event_loop: &winit::event_loop::ActiveEventLoop, cause: StartCause) {
+        // The frame timer set while animating or the idle timer has fired, ask for the next
+        // frame so Verso checks its state.
+        if let StartCause::ResumeTimeReached { .. } = cause {
+            if let Some(v) = self.verso.as_mut() {
+                v.request_redraw(event_loop);
+            }
+        }
+    }
+
     fn window_event(
         &mut self,
         event_loop: &winit::event_loop::ActiveEventLoop,
@@ -39,7 +52,34 @@ impl ApplicationHandler<EventLoopProxyMessage> for App {
                     v.request_redraw(event_loop);
                 }
                 EventLoopProxyMessage::IpcMessage(message) => {
+                    let opens_webview =
+                        matches!(message, ToVersoMessage::NewWebViewRequestResponse(..));
                     v.handle_incoming_webview_message(message);
+                    // Windows for new webviews are created while handling Servo messages.
+                    if opens_webview {
+                        v.request_redraw(event_loop);
+                    }
+                }
+                EventLoopProxyMessage::ConnectivityChecked(state) => {
+                    v.handle_connectivity_state(state);
+                }
+                EventLoopProxyMessage::SearchSuggestions {
+                    window_id,
+                    query,
+                    suggestions,
+                } => {
+                    v.handle_search_suggestions(window_id, query, suggestions);
+                }
+                EventLoopProxyMessage::ManagementCommand(command) => {
+                    v.handle_management_command(command);
+                }
+                EventLoopProxyMessage::CollectMemoryReports(reports_chan) => {
+                    v.handle_memory_report_request(reports_chan);
+                }
+                EventLoopProxyMessage::UpdateReady(update) => {
+                    v.handle_update_ready(update);
+                    // Schedule the restart even if nothing else wakes Verso up.
+                    v.request_redraw(event_loop);
                 }
             }
         }
@@ -47,7 +87,18 @@ impl ApplicationHandler<EventLoopProxyMessage> for App {
 }
 
 fn main() -> Result<()> {
+    // Mesa reads this from the environment, which is only safe to change while there's a single
+    // thread, so it's done before anything else instead of once Verso parses the command line.
+    if std::env::args().any(|arg| arg == SOFTWARE_RENDERING_FLAG) {
+        enable_software_rendering()?;
+    }
+    // This process only writes minidumps of another Verso.
+    if versoview::crash::run_monitor() {
+        return Ok(());
+    }
     init_crypto();
+    #[cfg(feature = "tracing")]
+    versoview::telemetry::init();
 
     let event_loop = EventLoop::<EventLoopProxyMessage>::with_user_event().build()?;
     event_loop.listen_device_events(DeviceEvents::Never);
@@ -0,0 +1,106 @@
+use std::thread;
+use std::time::Duration;
+
+use serde::Deserialize;
+use url::Url;
+use winit::event_loop::EventLoopProxy;
+
+use crate::{http, settings::Settings, verso::EventLoopProxyMessage};
+
+/// How often the management endpoint is polled without `--management-interval`.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long to wait for connecting to and reading from the management endpoint.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest list of commands we read, settings can carry a fair amount of preferences.
+const MAX_RESPONSE_SIZE: u64 = 1024 * 1024;
+
+/// A command sent by a management endpoint, like
+/// `{ "command": "navigate", "url": "https://example.com" }`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "command", rename_all = "camelCase")]
+pub enum ManagementCommand {
+    /// Load a URL in the current tab of the first window.
+    Navigate {
+        /// URL to load
+        url: Url,
+    },
+    /// Reload the current tab of the first window.
+    Reload,
+    /// Change the settings of all windows and the Servo preferences.
+    UpdateSettings {
+        /// Settings to change, missing fields are left unchanged
+        settings: Settings,
+    },
+    /// Capture the window. Verso can't read the rendered frames back yet, so it's only reported.
+    Screenshot,
+}
+
+/// Poll a management endpoint for commands in a background thread, and send them to the event
+/// loop as [`EventLoopProxyMessage::ManagementCommand`]. This lets fleets of signage devices be
+/// managed without a controller process on each of them.
+///
+/// The endpoint answers a GET request with a JSON list of [`ManagementCommand`]s, or
+/// `204 No Content` when there's nothing to do. Commands aren't acknowledged, so the endpoint has
+/// to hand each of them out only once. Devices can tell themselves apart with the query of the URL.
+/// Requests carry `token` as a bearer token so the endpoint can refuse other clients.
+pub fn poll_commands(
+    endpoint: Url,
+    token: String,
+    interval: Duration,
+    proxy: EventLoopProxy<EventLoopProxyMessage>,
+) {
+    let spawn_result = thread::Builder::new()
+        .name("ManagementPoll".to_owned())
+        .spawn(move || loop {
+            let options = http::Options {
+                bearer_token: Some(&token),
+                ..http::Options::new(REQUEST_TIMEOUT, MAX_RESPONSE_SIZE)
+            };
+            match http::get_with(&endpoint, options) {
+                Ok(body) => {
+                    for command in parse_commands(&body) {
+                        log::info!("Received management command: {command:?}");
+                        if let Err(error) =
+                            proxy.send_event(EventLoopProxyMessage::ManagementCommand(command))
+                        {
+                            // The event loop is gone, so Verso is exiting.
+                            log::debug!("Failed to send management command to Verso: {error}");
+                            return;
+                        }
+                    }
+                }
+                Err(error) => log::warn!("Failed to poll management endpoint {endpoint}: {error}"),
+            }
+            thread::sleep(interval);
+        });
+    if let Err(error) = spawn_result {
+        log::error!("Failed to spawn management poll thread: {error}");
+    }
+}
+
+/// Get the commands of a response, skipping the ones which aren't understood so that a newer
+/// endpoint can still manage older devices.
+fn parse_commands(body: &[u8]) -> Vec<ManagementCommand> {
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Vec::new();
+    }
+    let commands: Vec<serde_json::Value> = match serde_json::from_slice(body) {
+        Ok(commands) => commands,
+        Err(error) => {
+            log::warn!("Invalid management response, expected a list of commands: {error}");
+            return Vec::new();
+        }
+    };
+    commands
+        .into_iter()
+        .filter_map(|command| {
+            serde_json::from_value(command.clone())
+                .inspect_err(|error| {
+                    log::warn!("Ignoring management command {command}: {error}");
+                })
+                .ok()
+        })
+        .collect()
+}
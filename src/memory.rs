@@ -0,0 +1,175 @@
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+use std::ffi::c_void;
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use ipc_channel::{
+    ipc::{self, IpcSender},
+    router::ROUTER,
+};
+use profile_traits::mem::{ProfilerChan, ProfilerMsg, ReportKind, ReporterRequest, ReportsChan};
+use versoview_messages::{MemoryComponent, MemoryReport, MemoryReportEntry, ToControllerMessage};
+use wr_malloc_size_of::VoidPtrToSizeFn;
+
+/// Placeholder of the memory report in the memory page.
+pub const MEMORY_REPORT_PLACEHOLDER: &str = "%MEMORY_REPORT%";
+
+/// How long reporters have to answer. A script thread busy running a page answers late, and its
+/// measurements are left out then.
+const REPORT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Reporters registered with the mem profiler by name. The profiler can only print its reports,
+/// so registrations are recorded on their way to it to ask the reporters directly. The memory page
+/// is served from the network threads, so they're shared with them here.
+static REPORTERS: LazyLock<Mutex<HashMap<String, IpcSender<ReporterRequest>>>> =
+    LazyLock::new(Default::default);
+
+/// Size of a heap allocation for WebRender to measure its memory with, `None` on platforms whose
+/// allocator can't tell.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub const USABLE_SIZE: Option<VoidPtrToSizeFn> = Some(usable_size);
+/// Size of a heap allocation for WebRender to measure its memory with, `None` on platforms whose
+/// allocator can't tell.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub const USABLE_SIZE: Option<VoidPtrToSizeFn> = None;
+
+#[cfg(target_os = "linux")]
+unsafe extern "C" fn usable_size(ptr: *const c_void) -> usize {
+    extern "C" {
+        fn malloc_usable_size(ptr: *const c_void) -> usize;
+    }
+    malloc_usable_size(ptr)
+}
+
+#[cfg(target_os = "macos")]
+unsafe extern "C" fn usable_size(ptr: *const c_void) -> usize {
+    extern "C" {
+        fn malloc_size(ptr: *const c_void) -> usize;
+    }
+    malloc_size(ptr)
+}
+
+/// Wrap the channel of the mem profiler to record the reporters registered with it.
+pub fn record_reporters(profiler: ProfilerChan) -> ProfilerChan {
+    let (sender, receiver) = ipc::channel().expect("ipc channel failure");
+    ROUTER.add_typed_route(
+        receiver,
+        Box::new(move |message| {
+            let message = match message {
+                Ok(message) => message,
+                Err(e) => {
+                    log::error!("Failed to receive mem profiler message: {e}");
+                    return;
+                }
+            };
+            match &message {
+                ProfilerMsg::RegisterReporter(name, reporter) => {
+                    REPORTERS
+                        .lock()
+                        .unwrap()
+                        .insert(name.clone(), reporter.0.clone());
+                }
+                ProfilerMsg::UnregisterReporter(name) => {
+                    REPORTERS.lock().unwrap().remove(name);
+                }
+                _ => {}
+            }
+            profiler.send(message);
+        }),
+    );
+    ProfilerChan(sender)
+}
+
+/// Ask every reporter for its measurements and sum them up by component. Blocks until every
+/// reporter has answered or [`REPORT_TIMEOUT`] has passed.
+pub fn collect() -> MemoryReport {
+    let reporters: Vec<_> = REPORTERS.lock().unwrap().values().cloned().collect();
+    let deadline = Instant::now() + REPORT_TIMEOUT;
+    // Ask all of them first so they measure at the same time.
+    let receivers: Vec<_> = reporters
+        .iter()
+        .filter_map(|reporter| {
+            let (sender, receiver) = ipc::channel().ok()?;
+            reporter
+                .send(ReporterRequest {
+                    reports_channel: ReportsChan(sender),
+                })
+                .ok()?;
+            Some(receiver)
+        })
+        .collect();
+
+    let mut report = MemoryReport::default();
+    for receiver in receivers {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        let Ok(measurements) = receiver.try_recv_timeout(timeout) else {
+            continue;
+        };
+        for measurement in measurements {
+            // Sizes like the resident memory of the process overlap with the explicit ones.
+            if matches!(measurement.kind, ReportKind::NonExplicitSize) {
+                continue;
+            }
+            let component = component(&measurement.path);
+            let size = measurement.size as u64;
+            match component {
+                MemoryComponent::JsHeap => report.js_heap += size,
+                MemoryComponent::Images => report.images += size,
+                MemoryComponent::Layout => report.layout += size,
+                MemoryComponent::WebRender => report.webrender += size,
+                MemoryComponent::Other => report.other += size,
+            }
+            report.entries.push(MemoryReportEntry {
+                path: measurement.path,
+                component,
+                size,
+            });
+        }
+    }
+    report.entries.sort_by(|a, b| b.size.cmp(&a.size));
+    report
+}
+
+/// The component a measurement belongs to by its path.
+fn component(path: &[String]) -> MemoryComponent {
+    let has = |predicate: fn(&str) -> bool| path.iter().any(|segment| predicate(segment));
+    if has(|segment| segment == "webrender") {
+        MemoryComponent::WebRender
+    } else if has(|segment| segment == "js") {
+        MemoryComponent::JsHeap
+    } else if has(|segment| segment.contains("image")) {
+        MemoryComponent::Images
+    } else if has(|segment| segment.starts_with("layout")) {
+        MemoryComponent::Layout
+    } else {
+        MemoryComponent::Other
+    }
+}
+
+/// Collect a memory report and send it to the controller. It runs in a background thread since
+/// the reporters of busy pages take a while to answer.
+pub fn send_report(to_controller_sender: IpcSender<ToControllerMessage>) {
+    let spawn_result = thread::Builder::new()
+        .name("MemoryReport".to_owned())
+        .spawn(move || {
+            if let Err(error) =
+                to_controller_sender.send(ToControllerMessage::GetMemoryReportResponse(collect()))
+            {
+                log::error!("Verso failed to send GetMemoryReportResponse to controller: {error}");
+            }
+        });
+    if let Err(error) = spawn_result {
+        log::error!("Failed to spawn memory report thread: {error}");
+    }
+}
+
+/// Get a memory report as a JavaScript object literal for the memory page.
+pub fn memory_report_data() -> String {
+    let data = serde_json::to_string(&collect()).unwrap_or_else(|_| "null".to_owned());
+    // Paths contain URLs, which can't close the script element the data is written into.
+    data.replace('<', "\\u003c")
+}
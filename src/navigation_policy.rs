@@ -0,0 +1,215 @@
+use std::path::Path;
+
+use url::Url;
+use versoview_messages::NavigationPolicy;
+
+/// Load a navigation policy from a JSON file like
+/// `{ "allow": ["https://*.example.com/*"], "deny": [], "openBlockedExternally": true }`.
+pub fn load_navigation_policy(path: &Path) -> Result<NavigationPolicy, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+/// Whether the policy lets pages navigate to the URL.
+pub fn is_allowed(policy: &NavigationPolicy, url: &Url) -> bool {
+    // Blocking the new tab page or `about:blank` would leave kiosks with nothing to show.
+    if matches!(url.scheme(), "about" | "verso") {
+        return true;
+    }
+    let allowed = policy.allow.is_empty()
+        || policy
+            .allow
+            .iter()
+            .any(|pattern| pattern_matches(pattern, url));
+    allowed
+        && !policy
+            .deny
+            .iter()
+            .any(|pattern| pattern_matches(pattern, url))
+}
+
+/// Match a URL against a pattern like `https://*.example.com/*`. The scheme, host and path are
+/// matched separately so a `*` can't reach from one into another:
+/// - the scheme is matched exactly, or `*` matches `http` and `https`;
+/// - the host is matched exactly, `*` matches any host, and a leading `*.` matches the domain and
+///   its subdomains. A port after the host has to match too;
+/// - the path, with the query, is matched with `*` matching any number of characters, a missing
+///   path matches every path.
+///
+/// Patterns of URLs without a host, like `mailto:*`, match the scheme and then the rest of the
+/// URL. Invalid patterns don't match anything.
+fn pattern_matches(pattern: &str, url: &Url) -> bool {
+    let Some((scheme, rest)) = pattern.split_once(':') else {
+        return false;
+    };
+    let scheme_matches = if scheme == "*" {
+        matches!(url.scheme(), "http" | "https")
+    } else {
+        scheme.eq_ignore_ascii_case(url.scheme())
+    };
+    if !scheme_matches {
+        return false;
+    }
+
+    let Some(rest) = rest.strip_prefix("//") else {
+        // Without a host, the rest is matched as it is, like `mailto:*@example.com`.
+        let url = &url[url::Position::AfterScheme..url::Position::AfterQuery];
+        return url
+            .strip_prefix(':')
+            .is_some_and(|url| glob_matches(rest, url));
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, "/*"),
+    };
+    host_matches(authority, url)
+        && glob_matches(
+            path,
+            &url[url::Position::BeforePath..url::Position::AfterQuery],
+        )
+}
+
+/// Match the host and port of a URL, see [`pattern_matches`].
+fn host_matches(pattern: &str, url: &Url) -> bool {
+    let (host_pattern, port) = match pattern.rsplit_once(':') {
+        // A colon inside brackets is part of an IPv6 address.
+        Some((host, port)) if !port.contains(']') => match port.parse::<u16>() {
+            Ok(port) => (host, Some(port)),
+            Err(_) => return false,
+        },
+        _ => (pattern, None),
+    };
+    if port.is_some() && port != url.port_or_known_default() {
+        return false;
+    }
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    let host = host.to_ascii_lowercase();
+    let host_pattern = host_pattern.to_ascii_lowercase();
+    if host_pattern == "*" {
+        return true;
+    }
+    match host_pattern.strip_prefix("*.") {
+        Some(domain) => {
+            !domain.is_empty()
+                && !domain.contains('*')
+                && (host == domain
+                    || host
+                        .strip_suffix(domain)
+                        .is_some_and(|subdomain| subdomain.ends_with('.')))
+        }
+        None => !host_pattern.contains('*') && host == host_pattern,
+    }
+}
+
+/// Match text against a pattern where `*` matches any number of characters.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // Splitting always yields at least one part.
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // No `*`, so the pattern is the whole text.
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, url: &str) -> bool {
+        pattern_matches(pattern, &Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn wildcard_subdomain() {
+        let pattern = "https://*.example.com/*";
+        assert!(matches(pattern, "https://example.com/"));
+        assert!(matches(pattern, "https://www.example.com/page?q=1"));
+        assert!(matches(pattern, "https://a.b.example.com/"));
+        assert!(!matches(pattern, "https://notexample.com/"));
+        assert!(!matches(pattern, "http://www.example.com/"));
+    }
+
+    #[test]
+    fn wildcard_does_not_cross_into_path() {
+        let pattern = "https://*.example.com/*";
+        assert!(!matches(pattern, "https://attacker.com/x.example.com/"));
+        assert!(!matches(pattern, "https://attacker.com/?.example.com/"));
+        assert!(!matches(pattern, "https://attacker.com/#.example.com/"));
+        assert!(!matches(pattern, "https://example.com.attacker.com/"));
+        assert!(!matches(pattern, "https://www.example.com@attacker.com/"));
+    }
+
+    #[test]
+    fn exact_host_and_path() {
+        assert!(matches(
+            "https://example.com/docs/*",
+            "https://example.com/docs/a"
+        ));
+        assert!(!matches(
+            "https://example.com/docs/*",
+            "https://example.com/admin"
+        ));
+        assert!(matches(
+            "https://example.com",
+            "https://example.com/any/path"
+        ));
+        assert!(!matches("https://example.com/", "https://example.com/page"));
+        assert!(!matches(
+            "https://*example.com/*",
+            "https://badexample.com/"
+        ));
+    }
+
+    #[test]
+    fn ports() {
+        assert!(matches("http://localhost:8000/*", "http://localhost:8000/"));
+        assert!(!matches(
+            "http://localhost:8000/*",
+            "http://localhost:9000/"
+        ));
+        assert!(matches("https://example.com:443/*", "https://example.com/"));
+        assert!(matches("http://[::1]:8000/*", "http://[::1]:8000/"));
+    }
+
+    #[test]
+    fn any_scheme_and_host_less_urls() {
+        assert!(matches("*://example.com/*", "http://example.com/"));
+        assert!(!matches("*://example.com/*", "ftp://example.com/"));
+        assert!(matches(
+            "mailto:*@example.com",
+            "mailto:someone@example.com"
+        ));
+        assert!(!matches(
+            "mailto:*@example.com",
+            "mailto:someone@attacker.com"
+        ));
+    }
+
+    #[test]
+    fn policy() {
+        let policy = NavigationPolicy {
+            allow: vec!["https://*.example.com/*".to_owned()],
+            deny: vec!["https://ads.example.com/*".to_owned()],
+            open_blocked_externally: false,
+        };
+        let allowed = |url: &str| is_allowed(&policy, &Url::parse(url).unwrap());
+        assert!(allowed("https://www.example.com/"));
+        assert!(!allowed("https://ads.example.com/banner"));
+        assert!(!allowed("https://attacker.com/x.example.com/"));
+        assert!(allowed("about:blank"));
+    }
+}
@@ -0,0 +1,73 @@
+use std::{
+    collections::VecDeque,
+    path::Path,
+    sync::{LazyLock, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// URL of the new tab page, also the home page if none is configured.
+pub const NEWTAB_URL: &str = "verso://newtab";
+
+/// Placeholder of the recent pages and bookmarks in the new tab page.
+pub const NEWTAB_DATA_PLACEHOLDER: &str = "%NEWTAB_DATA%";
+
+/// Most recently visited pages shown on the new tab page.
+const MAX_RECENT_PAGES: usize = 12;
+
+/// Pages visited in this session across all windows, the latest first. The new tab page is served
+/// from the network threads, so it's shared with them here.
+static RECENT_PAGES: LazyLock<Mutex<VecDeque<Page>>> = LazyLock::new(Default::default);
+
+/// A page shown on the new tab page.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Page {
+    /// URL of the page
+    pub url: url::Url,
+    /// Title of the page if it has one
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+/// Record a visit to a page for the new tab page, internal pages are skipped.
+pub fn record_visit(url: &url::Url, title: Option<String>) {
+    if !matches!(url.scheme(), "http" | "https" | "file") {
+        return;
+    }
+    let mut pages = RECENT_PAGES.lock().unwrap();
+    let previous = pages
+        .iter()
+        .position(|page| &page.url == url)
+        .and_then(|index| pages.remove(index));
+    // Navigations are reported before the title, so keep the one from a previous visit.
+    let title = title.or_else(|| previous.and_then(|page| page.title));
+    pages.push_front(Page {
+        url: url.clone(),
+        title,
+    });
+    pages.truncate(MAX_RECENT_PAGES);
+}
+
+/// Load bookmarks from a JSON file with a list of `{ "url", "title" }`.
+pub fn load_bookmarks(path: &Path) -> Result<Vec<Page>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+/// Get the recent pages and the bookmarks as a JavaScript object literal for the new tab page.
+pub fn newtab_data(bookmarks: &[Page]) -> String {
+    #[derive(Serialize)]
+    struct NewTabData<'a> {
+        recent: &'a VecDeque<Page>,
+        bookmarks: &'a [Page],
+    }
+
+    let recent = RECENT_PAGES.lock().unwrap();
+    let data = serde_json::to_string(&NewTabData {
+        recent: &recent,
+        bookmarks,
+    })
+    .unwrap_or_else(|_| "null".to_owned());
+    // Titles can't close the script element the data is written into.
+    data.replace('<', "\\u003c")
+}
@@ -0,0 +1,210 @@
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use base::id::WebViewId;
+use embedder_traits::{EmbedderMsg, LoadStatus};
+use serde::Deserialize;
+
+/// How long a page has to finish loading before it's loaded again.
+const LOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Most attempts to load a failing page before moving on to the next one.
+const MAX_RETRIES: u32 = 3;
+
+/// A page of a signage playlist.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PlaylistItem {
+    /// URL of the page
+    pub url: url::Url,
+    /// How long the page is shown in seconds
+    pub seconds: u64,
+}
+
+impl PlaylistItem {
+    fn dwell(&self) -> Duration {
+        // A page shown for no time would rotate on every frame.
+        Duration::from_secs(self.seconds.max(1))
+    }
+}
+
+/// Load the pages of a playlist from a JSON file with a list of `{ "url", "seconds" }`.
+pub fn load_playlist(path: &Path) -> Result<Vec<PlaylistItem>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+/// Pages which the main window rotates through for digital signage, with one page it's reloaded
+/// periodically. Servo doesn't report failed loads to the embedder, so a page counts as failed
+/// when it doesn't finish loading in time or its content crashes.
+#[derive(Debug)]
+pub struct Playlist {
+    items: Vec<PlaylistItem>,
+    /// Index of the page being shown.
+    index: usize,
+    /// When the page was last loaded.
+    loaded_at: Instant,
+    /// Whether the page has finished loading.
+    complete: bool,
+    /// Whether the content of the page has crashed.
+    crashed: bool,
+    /// Number of times the page has been loaded again after failing.
+    retries: u32,
+}
+
+impl Playlist {
+    /// Create a playlist starting at its first page, `None` if it's empty.
+    pub fn new(items: Vec<PlaylistItem>) -> Option<Self> {
+        if items.is_empty() {
+            return None;
+        }
+        Some(Self {
+            items,
+            index: 0,
+            loaded_at: Instant::now(),
+            complete: false,
+            crashed: false,
+            retries: 0,
+        })
+    }
+
+    /// URL of the page being shown.
+    pub fn current_url(&self) -> &url::Url {
+        &self.items[self.index].url
+    }
+
+    /// Follow the loading state of the page shown in `webview_id`.
+    pub fn handle_message(&mut self, webview_id: Option<WebViewId>, message: &EmbedderMsg) {
+        match message {
+            EmbedderMsg::NotifyLoadStatusChanged(id, LoadStatus::Complete)
+                if Some(*id) == webview_id =>
+            {
+                self.complete = true;
+                self.retries = 0;
+            }
+            EmbedderMsg::Panic(id, reason, _) if Some(*id) == webview_id => {
+                log::warn!("Playlist page {} has crashed: {reason}", self.current_url());
+                self.crashed = true;
+            }
+            _ => {}
+        }
+    }
+
+    /// When the page has to be changed or checked next.
+    pub fn deadline(&self) -> Instant {
+        let dwell = self.items[self.index].dwell();
+        if self.complete {
+            self.loaded_at + dwell
+        } else {
+            self.loaded_at + dwell.min(LOAD_TIMEOUT)
+        }
+    }
+
+    /// Get the URL to load if the page's time is up or it has failed.
+    pub fn poll(&mut self) -> Option<url::Url> {
+        let elapsed = self.loaded_at.elapsed();
+        let failed = self.crashed || (!self.complete && elapsed >= LOAD_TIMEOUT);
+        if failed && self.retries < MAX_RETRIES {
+            self.retries += 1;
+            log::warn!(
+                "Playlist page {} failed to load, trying again ({}/{MAX_RETRIES})",
+                self.current_url(),
+                self.retries
+            );
+        } else if failed || elapsed >= self.items[self.index].dwell() {
+            self.index = (self.index + 1) % self.items.len();
+            self.retries = 0;
+        } else {
+            return None;
+        }
+        self.loaded_at = Instant::now();
+        self.complete = false;
+        self.crashed = false;
+        Some(self.current_url().clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn playlist(seconds: &[u64]) -> Playlist {
+        let items = seconds
+            .iter()
+            .enumerate()
+            .map(|(i, &seconds)| PlaylistItem {
+                url: url::Url::parse(&format!("https://example.com/{i}")).unwrap(),
+                seconds,
+            })
+            .collect();
+        Playlist::new(items).unwrap()
+    }
+
+    /// Pretend the current page was loaded `elapsed` ago.
+    fn wait(playlist: &mut Playlist, elapsed: Duration) {
+        playlist.loaded_at = Instant::now() - elapsed;
+    }
+
+    #[test]
+    fn empty() {
+        assert!(Playlist::new(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn advances_after_dwell() {
+        let mut playlist = playlist(&[10, 20]);
+        playlist.complete = true;
+        assert_eq!(playlist.poll(), None);
+        wait(&mut playlist, Duration::from_secs(10));
+        assert_eq!(playlist.poll().unwrap().path(), "/1");
+        assert!(!playlist.complete);
+
+        playlist.complete = true;
+        wait(&mut playlist, Duration::from_secs(20));
+        assert_eq!(playlist.poll().unwrap().path(), "/0");
+    }
+
+    #[test]
+    fn single_page_reloads() {
+        let mut playlist = playlist(&[0]);
+        playlist.complete = true;
+        wait(&mut playlist, Duration::from_secs(1));
+        assert_eq!(playlist.poll().unwrap().path(), "/0");
+    }
+
+    #[test]
+    fn retries_timed_out_loads() {
+        let mut playlist = playlist(&[60, 60]);
+        for retry in 1..=MAX_RETRIES {
+            wait(&mut playlist, LOAD_TIMEOUT);
+            assert_eq!(playlist.poll().unwrap().path(), "/0");
+            assert_eq!(playlist.retries, retry);
+        }
+        // Moves on once the retries are used up.
+        wait(&mut playlist, LOAD_TIMEOUT);
+        assert_eq!(playlist.poll().unwrap().path(), "/1");
+        assert_eq!(playlist.retries, 0);
+    }
+
+    #[test]
+    fn retries_crashed_pages() {
+        let mut playlist = playlist(&[60, 60]);
+        playlist.complete = true;
+        playlist.crashed = true;
+        assert_eq!(playlist.poll().unwrap().path(), "/0");
+        assert_eq!(playlist.retries, 1);
+        assert!(!playlist.crashed);
+    }
+
+    #[test]
+    fn deadline() {
+        let mut playlist = playlist(&[60]);
+        assert_eq!(playlist.deadline(), playlist.loaded_at + LOAD_TIMEOUT);
+        playlist.complete = true;
+        assert_eq!(
+            playlist.deadline(),
+            playlist.loaded_at + Duration::from_secs(60)
+        );
+    }
+}
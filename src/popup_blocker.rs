@@ -0,0 +1,82 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::Deserialize;
+
+/// How long after the user clicks or types in a page it can open a popup, like the transient
+/// activation of other browsers.
+pub const USER_ACTIVATION_DURATION: Duration = Duration::from_secs(5);
+
+/// File in the config directory the exceptions are saved to.
+const EXCEPTIONS_FILE: &str = "popup-exceptions.json";
+
+/// Shows a blocked popup in the panel, called with the origin of the page or `null` to hide it.
+pub const POPUP_INDICATOR_SCRIPT: &str = include_str!("../resources/components/popup_indicator.js");
+
+/// What the user chose for a blocked popup in the panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PopupDecision {
+    /// Let the page open its next popup.
+    AllowOnce,
+    /// Let pages of this origin open popups from now on.
+    AllowSite,
+    /// Keep blocking and hide the indicator.
+    Dismiss,
+}
+
+/// Origins which can open popups without user activation. They're saved in the config directory
+/// if Verso has one, and only last for the session otherwise.
+#[derive(Debug, Clone, Default)]
+pub struct PopupExceptions {
+    origins: BTreeSet<String>,
+    path: Option<PathBuf>,
+}
+
+impl PopupExceptions {
+    /// Load the exceptions saved in the config directory.
+    pub fn load(config_dir: Option<&Path>) -> Self {
+        let Some(path) = config_dir.map(|dir| dir.join(EXCEPTIONS_FILE)) else {
+            return Self::default();
+        };
+        let origins = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                log::error!("Failed to parse popup exceptions {}: {e}", path.display());
+                BTreeSet::new()
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => BTreeSet::new(),
+            Err(e) => {
+                log::error!("Failed to read popup exceptions {}: {e}", path.display());
+                BTreeSet::new()
+            }
+        };
+        Self {
+            origins,
+            path: Some(path),
+        }
+    }
+
+    /// Whether pages of the origin, serialized like `https://example.com`, can open popups.
+    pub fn contains(&self, origin: &str) -> bool {
+        self.origins.contains(origin)
+    }
+
+    /// Let pages of the origin open popups and save the exceptions.
+    pub fn add(&mut self, origin: String) {
+        if !self.origins.insert(origin) {
+            return;
+        }
+        let Some(path) = &self.path else {
+            return;
+        };
+        let content = serde_json::to_string_pretty(&self.origins).unwrap();
+        if let Err(e) = fs::write(path, content) {
+            log::error!("Failed to save popup exceptions {}: {e}", path.display());
+        }
+    }
+}
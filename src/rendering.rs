@@ -1,20 +1,26 @@
 use std::ffi::CString;
 use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 use euclid::default::Size2D;
 use gleam::gl;
 use glutin::{
     config::{Config, GetGlConfig, GlConfig},
-    context::{ContextApi, ContextAttributesBuilder, PossiblyCurrentContext, Version},
-    display::GetGlDisplay,
+    context::{
+        AsRawContext, ContextApi, ContextAttributesBuilder, PossiblyCurrentContext, RawContext,
+        Version,
+    },
+    display::{AsRawDisplay, GetGlDisplay, RawDisplay},
     prelude::{GlContext, GlDisplay, NotCurrentGlContext, PossiblyCurrentGlContext},
     surface::{
-        GlSurface, ResizeableSurface, Surface, SurfaceTypeTrait, SwapInterval, WindowSurface,
+        GlSurface, Rect, ResizeableSurface, Surface, SurfaceTypeTrait, SwapInterval, WindowSurface,
     },
 };
-use glutin_winit::GlWindow;
+use glutin_winit::{ApiPreference, GlWindow};
+use media::{GlApi, GlContext as MediaGlContext, NativeDisplay};
 use raw_window_handle::HasWindowHandle;
+use webrender_api::units::DeviceIntRect;
 use winit::window::Window;
 
 /// A Verso rendering context, which holds all of the information needed
@@ -50,30 +56,20 @@ impl RenderingContext {
         let not_current_gl_context = unsafe {
             gl_display
                 .create_context(gl_config, &context_attributes)
-                .unwrap_or_else(|_| {
-                    gl_display
-                        .create_context(gl_config, &fallback_context_attributes)
-                        .unwrap_or_else(|_| {
-                            gl_display
-                                .create_context(gl_config, &legacy_context_attributes)
-                                .expect("failed to create context")
-                        })
-                })
+                .or_else(|_| gl_display.create_context(gl_config, &fallback_context_attributes))
+                .or_else(|_| gl_display.create_context(gl_config, &legacy_context_attributes))?
         };
 
         // Create surface
-        let attrs = window
-            .build_surface_attributes(Default::default())
-            .expect("Failed to build surface attributes");
+        let attrs = window.build_surface_attributes(Default::default())?;
         let surface = unsafe {
             gl_config
                 .display()
-                .create_window_surface(gl_config, &attrs)
-                .unwrap()
+                .create_window_surface(gl_config, &attrs)?
         };
 
         // Make it current.
-        let context = not_current_gl_context.make_current(&surface).unwrap();
+        let context = not_current_gl_context.make_current(&surface)?;
 
         // Try setting vsync.
         if let Err(res) =
@@ -151,6 +147,186 @@ impl RenderingContext {
         surface.swap_buffers(&self.context)?;
         Ok(())
     }
+
+    /// Present only the damaged region of the surface. Falls back to a full present if there's
+    /// no damage information or the platform doesn't support partial updates.
+    pub fn present_with_damage(
+        &self,
+        surface: &Surface<impl SurfaceTypeTrait>,
+        damage: &[DeviceIntRect],
+    ) -> Result<(), crate::errors::Error> {
+        if damage.is_empty() {
+            return self.present(surface);
+        }
+        self.context.make_current(surface)?;
+        // Damage rects are in device space with a top-left origin, but EGL expects bottom-left.
+        let height = surface.height().unwrap_or_default() as i32;
+        let rects: Vec<Rect> = damage
+            .iter()
+            .map(|rect| Rect::new(rect.min.x, height - rect.max.y, rect.width(), rect.height()))
+            .collect();
+        if surface
+            .swap_buffers_with_damage(&self.context, &rects)
+            .is_err()
+        {
+            surface.swap_buffers(&self.context)?;
+        }
+        Ok(())
+    }
+
+    /// The raw GL context, which media players share to upload video frames as GL textures.
+    pub fn media_gl_context(&self) -> MediaGlContext {
+        #[allow(unreachable_patterns)]
+        match self.context.raw_context() {
+            #[cfg(any(target_os = "linux", target_os = "windows"))]
+            RawContext::Egl(context) => MediaGlContext::Egl(context as usize),
+            #[cfg(target_os = "linux")]
+            RawContext::Glx(context) => MediaGlContext::Glx(context as usize),
+            _ => MediaGlContext::Unknown,
+        }
+    }
+
+    /// The native display the GL context was created on, in the form media players expect.
+    pub fn media_native_display(&self) -> NativeDisplay {
+        #[allow(unreachable_patterns)]
+        match self.context.display().raw_display() {
+            #[cfg(any(target_os = "linux", target_os = "windows"))]
+            RawDisplay::Egl(display) => NativeDisplay::Egl(display as usize),
+            // The raw GLX display is the Xlib display.
+            #[cfg(target_os = "linux")]
+            RawDisplay::Glx(display) => NativeDisplay::X11(display as usize),
+            _ => NativeDisplay::Unknown,
+        }
+    }
+
+    /// The GL API of the context, in the form media players expect.
+    pub fn media_gl_api(&self) -> GlApi {
+        // These queries only exist since OpenGL 3.0 and GLES 3.0, older contexts leave them 0.
+        let mut major = [0];
+        let mut minor = [0];
+        unsafe {
+            self.gl.get_integer_v(gl::MAJOR_VERSION, &mut major);
+            self.gl.get_integer_v(gl::MINOR_VERSION, &mut minor);
+        }
+        match self.gl.get_type() {
+            gl::GlType::Gl if (major[0], minor[0]) >= (3, 2) => GlApi::OpenGL3,
+            gl::GlType::Gl => GlApi::OpenGL,
+            // A GLES 2.0 context can't report its version this way, but we never create GLES 1.
+            gl::GlType::Gles => GlApi::Gles2,
+        }
+    }
+
+    /// The age of the surface's back buffer in frames, or 0 if its content is undefined.
+    /// WebRender uses it to decide how much of the previous frame it can reuse.
+    pub fn buffer_age(&self, surface: &Surface<impl SurfaceTypeTrait>) -> usize {
+        surface.buffer_age() as usize
+    }
+}
+
+/// Which GL platform API to create the display with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GlBackend {
+    /// The platform's native API (WGL, GLX or CGL), falling back to EGL if it's unavailable.
+    #[default]
+    Native,
+    /// EGL, which is ANGLE on Windows. Useful on machines with broken OpenGL drivers.
+    /// On Windows it needs [`ANGLE_LIBRARIES`] next to Verso or on `PATH`.
+    Egl,
+}
+
+/// DLLs of ANGLE, which provides EGL on Windows.
+pub const ANGLE_LIBRARIES: [&str; 2] = ["libEGL.dll", "libGLESv2.dll"];
+
+impl GlBackend {
+    pub(crate) fn api_preference(self) -> ApiPreference {
+        match self {
+            GlBackend::Native => ApiPreference::FallbackEgl,
+            GlBackend::Egl => ApiPreference::PreferEgl,
+        }
+    }
+
+    /// Why a context can't be created with this backend, checked before trying it.
+    pub(crate) fn unavailable_reason(self) -> Option<String> {
+        match self {
+            GlBackend::Egl if cfg!(windows) => missing_angle_library()
+                .map(|library| format!("ANGLE's {library} isn't next to Verso or on PATH")),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for GlBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "native" => Ok(GlBackend::Native),
+            "egl" => Ok(GlBackend::Egl),
+            "angle" if !cfg!(windows) => Err(String::from(
+                "ANGLE is only used on Windows, other platforms render with egl directly",
+            )),
+            "angle" => match missing_angle_library() {
+                Some(library) => Err(format!("ANGLE's {library} isn't next to Verso or on PATH")),
+                None => Ok(GlBackend::Egl),
+            },
+            _ => Err(format!("Unknown GL backend {s}, expected native or egl")),
+        }
+    }
+}
+
+/// Find the first of [`ANGLE_LIBRARIES`] which isn't in a directory Windows loads DLLs from, the
+/// executable's directory or `PATH`.
+fn missing_angle_library() -> Option<&'static str> {
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let dirs: Vec<PathBuf> = exe_dir
+        .into_iter()
+        .chain(std::env::split_paths(&path))
+        .collect();
+    ANGLE_LIBRARIES
+        .into_iter()
+        .find(|library| !dirs.iter().any(|dir| dir.join(library).is_file()))
+}
+
+/// Settings to pick the GL platform used to create the rendering context.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RenderingSettings {
+    /// GL platform API.
+    pub backend: GlBackend,
+}
+
+impl RenderingSettings {
+    /// The settings to try next if a context couldn't be created with these settings.
+    /// On Windows native OpenGL falls back to ANGLE.
+    pub(crate) fn fallback(self) -> Option<Self> {
+        if cfg!(windows) && self.backend == GlBackend::Native {
+            Some(Self {
+                backend: GlBackend::Egl,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// Command line flag which makes Verso rasterize in software, see [`enable_software_rendering`].
+pub const SOFTWARE_RENDERING_FLAG: &str = "--software-rendering";
+
+/// Make the GL driver rasterize in software for the whole process. Only Mesa (llvmpipe/softpipe),
+/// which is what VMs and headless CI usually have, can be switched to it. It reads an environment
+/// variable, so this has to be called in `main` before any other thread is spawned.
+pub fn enable_software_rendering() -> crate::Result<()> {
+    #[cfg(linux)]
+    {
+        std::env::set_var("LIBGL_ALWAYS_SOFTWARE", "1");
+        Ok(())
+    }
+    #[cfg(not(linux))]
+    {
+        Err(crate::errors::Error::SoftwareRenderingUnsupported)
+    }
 }
 
 /// Find the config with the maximum number of samples, so our triangle will be
@@ -0,0 +1,160 @@
+use std::{thread, time::Duration};
+
+use base::id::WebViewId;
+use compositing_traits::ConstellationMsg;
+use crossbeam_channel::{unbounded, Receiver, RecvTimeoutError, Sender};
+use ipc_channel::ipc::{self, IpcSender};
+use script_traits::{
+    webdriver_msg::{WebDriverJSValue, WebDriverScriptCommand},
+    WebDriverCommandMsg,
+};
+use serde::Deserialize;
+use versoview_messages::{ConsoleMessage, ToControllerMessage};
+
+use crate::verso::send_to_constellation;
+
+/// How often the messages logged by the page are sent to the controller.
+const CONSOLE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Hooks the console of the page, prepended to every script the REPL runs.
+const REPL_SCRIPT: &str = include_str!("../resources/components/repl.js");
+
+/// Result of evaluating code in the page, as serialized by the REPL script.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum Evaluation {
+    Value(String),
+    Error(String),
+}
+
+/// A JavaScript console of a webview which the controller evaluates code in. It runs in a
+/// background thread since scripts block until the page has run them, and closes once dropped.
+pub struct Repl {
+    sender: Sender<String>,
+}
+
+impl Repl {
+    /// Open a console in the webview, results and console messages are sent straight to the
+    /// controller.
+    pub fn open(
+        webview_id: WebViewId,
+        constellation_sender: Sender<ConstellationMsg>,
+        to_controller_sender: IpcSender<ToControllerMessage>,
+    ) -> Option<Self> {
+        let (sender, receiver) = unbounded();
+        let spawn_result = thread::Builder::new()
+            .name("Repl".to_owned())
+            .spawn(move || {
+                run(
+                    webview_id,
+                    receiver,
+                    &constellation_sender,
+                    &to_controller_sender,
+                )
+            });
+        if let Err(error) = spawn_result {
+            log::error!("Failed to spawn REPL thread: {error}");
+            return None;
+        }
+        Some(Self { sender })
+    }
+
+    /// Evaluate code in the page, `false` if the console has closed since its webview is gone.
+    pub fn evaluate(&self, code: String) -> bool {
+        self.sender.send(code).is_ok()
+    }
+}
+
+/// Evaluate the code sent to the console and forward console messages until the console is
+/// dropped or its webview is gone.
+fn run(
+    webview_id: WebViewId,
+    receiver: Receiver<String>,
+    constellation_sender: &Sender<ConstellationMsg>,
+    to_controller_sender: &IpcSender<ToControllerMessage>,
+) {
+    let send = |message| {
+        if let Err(error) = to_controller_sender.send(message) {
+            log::error!("Verso failed to send REPL output to controller: {error}");
+        }
+    };
+    loop {
+        let code = match receiver.recv_timeout(CONSOLE_POLL_INTERVAL) {
+            Ok(code) => Some(code),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+        let result = match code {
+            Some(code) => {
+                let script = format!(
+                    "{REPL_SCRIPT}\nwindow.__versoRepl.evaluate({})",
+                    serde_json::to_string(&code).unwrap()
+                );
+                match run_script(constellation_sender, webview_id, script) {
+                    Some(output) => Some(output.and_then(parse_evaluation)),
+                    None => {
+                        log::debug!("Closing the REPL of webview {webview_id}, it's gone");
+                        send(ToControllerMessage::EvaluateInReplResponse(Err(
+                            "The webview of the console is closed".to_owned(),
+                        )));
+                        return;
+                    }
+                }
+            }
+            None => None,
+        };
+
+        // Messages logged by the code come before its result.
+        let script = format!("{REPL_SCRIPT}\nwindow.__versoRepl.takeMessages()");
+        let messages = run_script(constellation_sender, webview_id, script);
+        if let Some(Ok(messages)) = &messages {
+            match serde_json::from_str::<Vec<ConsoleMessage>>(messages) {
+                Ok(messages) => {
+                    for message in messages {
+                        send(ToControllerMessage::OnReplConsoleMessage(message));
+                    }
+                }
+                Err(error) => log::warn!("Invalid console messages from the REPL script: {error}"),
+            }
+        }
+        if let Some(result) = result {
+            send(ToControllerMessage::EvaluateInReplResponse(result));
+        }
+        if messages.is_none() {
+            log::debug!("Closing the REPL of webview {webview_id}, it's gone");
+            return;
+        }
+    }
+}
+
+/// Get the value or the error of evaluated code from the output of the REPL script.
+fn parse_evaluation(output: String) -> Result<String, String> {
+    match serde_json::from_str(&output) {
+        Ok(Evaluation::Value(value)) => Ok(value),
+        Ok(Evaluation::Error(error)) => Err(error),
+        Err(error) => Err(format!("Invalid output of the REPL script: {error}")),
+    }
+}
+
+/// Run a script in the webview and get the string it evaluates to, `None` if the webview is gone.
+fn run_script(
+    constellation_sender: &Sender<ConstellationMsg>,
+    webview_id: WebViewId,
+    script: String,
+) -> Option<Result<String, String>> {
+    let (result_sender, result_receiver) = ipc::channel().ok()?;
+    send_to_constellation(
+        constellation_sender,
+        ConstellationMsg::WebDriverCommand(WebDriverCommandMsg::ScriptCommand(
+            webview_id.0,
+            WebDriverScriptCommand::ExecuteScript(script, result_sender),
+        )),
+    );
+    match result_receiver.recv().ok()? {
+        Ok(WebDriverJSValue::String(output)) => Some(Ok(output)),
+        Ok(value) => Some(Err(format!(
+            "Unexpected result of the REPL script: {value:?}"
+        ))),
+        Err(error) => Some(Err(format!("Failed to run the REPL script: {error:?}"))),
+    }
+}
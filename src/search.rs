@@ -0,0 +1,402 @@
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{unbounded, Receiver, RecvTimeoutError, Sender};
+use url::Url;
+use winit::{event_loop::EventLoopProxy, window::WindowId};
+
+use crate::{http, verso::EventLoopProxyMessage};
+
+/// Placeholder of the search terms in search and suggestion URL templates.
+pub const SEARCH_TERMS_PLACEHOLDER: &str = "%s";
+
+/// How long to wait for connecting to and reading from a suggestions endpoint.
+const SUGGESTIONS_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Largest suggestions response we read.
+const MAX_RESPONSE_SIZE: u64 = 64 * 1024;
+
+/// Most suggestions sent to the panel for a query.
+const MAX_SUGGESTIONS: usize = 8;
+
+/// How long the URL bar input has to stay the same before suggestions are fetched for it.
+const SUGGESTIONS_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// A search engine the URL bar sends queries to when the input isn't a URL.
+#[derive(Clone, Debug)]
+pub struct SearchEngine {
+    /// Name to select the engine with `--search-engine`.
+    pub name: String,
+    /// Typing this word and a space before a query searches with this engine, like `w rust`.
+    pub keyword: Option<String>,
+    /// URL of the results page, `%s` is replaced with the query.
+    pub template: String,
+    /// URL returning OpenSearch suggestions as JSON, `%s` is replaced with the query.
+    pub suggestions: Option<String>,
+}
+
+impl SearchEngine {
+    fn builtin(name: &str, keyword: &str, template: &str, suggestions: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            keyword: Some(keyword.to_owned()),
+            template: template.to_owned(),
+            suggestions: Some(suggestions.to_owned()),
+        }
+    }
+
+    /// Create an engine from a URL template, it has to contain `%s`.
+    pub fn custom(name: &str, keyword: Option<&str>, template: &str) -> Result<Self, String> {
+        if !template.contains(SEARCH_TERMS_PLACEHOLDER) {
+            return Err(format!(
+                "Search template {template} doesn't contain {SEARCH_TERMS_PLACEHOLDER}"
+            ));
+        }
+        // Check the template with some terms since `%s` may be in the host.
+        fill_template(template, "verso")
+            .ok_or_else(|| format!("Invalid search template {template}"))?;
+        Ok(Self {
+            name: name.to_owned(),
+            keyword: keyword.map(str::to_owned),
+            template: template.to_owned(),
+            suggestions: None,
+        })
+    }
+
+    /// Get the URL of the results page for the query.
+    pub fn search_url(&self, query: &str) -> Option<Url> {
+        fill_template(&self.template, query)
+    }
+
+    /// Get the URL of the suggestions for the query, `None` if the engine doesn't have any.
+    pub fn suggestions_url(&self, query: &str) -> Option<Url> {
+        fill_template(self.suggestions.as_ref()?, query)
+    }
+}
+
+/// Search engines known to the URL bar and the one used by default.
+#[derive(Clone, Debug)]
+pub struct SearchEngines {
+    engines: Vec<SearchEngine>,
+    default: usize,
+}
+
+impl Default for SearchEngines {
+    fn default() -> Self {
+        Self {
+            engines: vec![
+                SearchEngine::builtin(
+                    "DuckDuckGo",
+                    "ddg",
+                    "https://duckduckgo.com/?q=%s",
+                    "https://duckduckgo.com/ac/?type=list&q=%s",
+                ),
+                SearchEngine::builtin(
+                    "Google",
+                    "g",
+                    "https://www.google.com/search?q=%s",
+                    "https://suggestqueries.google.com/complete/search?client=firefox&q=%s",
+                ),
+                SearchEngine::builtin(
+                    "Bing",
+                    "b",
+                    "https://www.bing.com/search?q=%s",
+                    "https://api.bing.com/osjson.aspx?query=%s",
+                ),
+                SearchEngine::builtin(
+                    "Wikipedia",
+                    "w",
+                    "https://en.wikipedia.org/wiki/Special:Search?search=%s",
+                    "https://en.wikipedia.org/w/api.php?action=opensearch&search=%s",
+                ),
+            ],
+            default: 0,
+        }
+    }
+}
+
+impl SearchEngines {
+    /// Add an engine, replacing the keyword of engines which already use it.
+    pub fn add(&mut self, engine: SearchEngine) {
+        if let Some(keyword) = &engine.keyword {
+            for other in &mut self.engines {
+                if other.keyword.as_ref() == Some(keyword) {
+                    other.keyword = None;
+                }
+            }
+        }
+        self.engines.push(engine);
+    }
+
+    /// Use an engine by default, given by its name or keyword, or a new one from a URL template.
+    pub fn set_default(&mut self, engine: &str) -> Result<(), String> {
+        let index = self.engines.iter().position(|known| {
+            known.name.eq_ignore_ascii_case(engine) || known.keyword.as_deref() == Some(engine)
+        });
+        self.default = match index {
+            Some(index) => index,
+            None => {
+                self.add(SearchEngine::custom("Custom", None, engine)?);
+                self.engines.len() - 1
+            }
+        };
+        Ok(())
+    }
+
+    /// The engine used without a keyword.
+    pub fn default_engine(&self) -> &SearchEngine {
+        &self.engines[self.default]
+    }
+
+    /// Get the engine and query of URL bar input which starts with a keyword.
+    fn keyword_search<'a>(&self, input: &'a str) -> Option<(&SearchEngine, &'a str)> {
+        let (keyword, query) = input.split_once(char::is_whitespace)?;
+        let query = query.trim();
+        if query.is_empty() {
+            return None;
+        }
+        let engine = self
+            .engines
+            .iter()
+            .find(|engine| engine.keyword.as_deref() == Some(keyword))?;
+        Some((engine, query))
+    }
+
+    /// Get the URL to load for URL bar input, either the URL it contains or a search.
+    pub fn resolve(&self, input: &str) -> Option<Url> {
+        let input = input.trim();
+        if input.is_empty() {
+            return None;
+        }
+        if let Some((engine, query)) = self.keyword_search(input) {
+            return engine.search_url(query);
+        }
+        parse_url_input(input).or_else(|| self.default_engine().search_url(input))
+    }
+
+    /// Get the URL of the suggestions for URL bar input, `None` if it's a URL or the engine
+    /// doesn't have suggestions.
+    pub fn suggestions_url(&self, input: &str) -> Option<Url> {
+        let input = input.trim();
+        if input.is_empty() {
+            return None;
+        }
+        if let Some((engine, query)) = self.keyword_search(input) {
+            return engine.suggestions_url(query);
+        }
+        if parse_url_input(input).is_some() {
+            return None;
+        }
+        self.default_engine().suggestions_url(input)
+    }
+}
+
+/// Replace `%s` in the template with the percent encoded query.
+fn fill_template(template: &str, query: &str) -> Option<Url> {
+    let query: String = url::form_urlencoded::byte_serialize(query.as_bytes()).collect();
+    Url::parse(&template.replace(SEARCH_TERMS_PLACEHOLDER, &query)).ok()
+}
+
+/// Parse URL bar input as a URL, `https` is assumed if it has a host but no scheme.
+/// Input with spaces or a single word other than `localhost` is a search instead.
+fn parse_url_input(input: &str) -> Option<Url> {
+    if input.contains(char::is_whitespace) {
+        return None;
+    }
+    if let Ok(url) = Url::parse(input) {
+        // Something like `localhost:8080` parses as a URL with the `localhost` scheme and a path
+        // starting with the port. Other URLs without a host, like `mailto:` ones, are kept.
+        let starts_with_port = url
+            .path()
+            .split('/')
+            .next()
+            .is_some_and(|port| !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()));
+        if url.has_host() || !starts_with_port {
+            return Some(url);
+        }
+    }
+    let url = Url::parse(&format!("https://{input}")).ok()?;
+    let host = url.host_str()?;
+    let is_host = host.contains('.')
+        || host.starts_with('[')
+        || host.eq_ignore_ascii_case("localhost")
+        || url.port().is_some();
+    is_host.then_some(url)
+}
+
+/// A request for the suggestions of URL bar input.
+struct SuggestionsRequest {
+    url: Url,
+    window_id: WindowId,
+    query: String,
+}
+
+/// Fetches search suggestions in a background thread, and sends them back to the event loop as
+/// [`EventLoopProxyMessage::SearchSuggestions`]. Only the last request made while the user is
+/// typing is fetched.
+pub struct SuggestionsFetcher {
+    sender: Sender<SuggestionsRequest>,
+}
+
+impl SuggestionsFetcher {
+    /// Start the thread fetching suggestions, it exits once the fetcher is dropped.
+    pub fn new(proxy: EventLoopProxy<EventLoopProxyMessage>) -> Option<Self> {
+        let (sender, receiver) = unbounded();
+        let spawn_result = thread::Builder::new()
+            .name("SearchSuggestions".to_owned())
+            .spawn(move || run_fetcher(receiver, proxy));
+        if let Err(error) = spawn_result {
+            log::error!("Failed to spawn search suggestions thread: {error}");
+            return None;
+        }
+        Some(Self { sender })
+    }
+
+    /// Fetch the suggestions of a query from `url` for a window, unless another request comes
+    /// in shortly after.
+    pub fn fetch(&self, url: Url, window_id: WindowId, query: String) {
+        let _ = self.sender.send(SuggestionsRequest {
+            url,
+            window_id,
+            query,
+        });
+    }
+}
+
+/// Fetch the last of the requests which come in quick succession until the fetcher is dropped.
+fn run_fetcher(
+    receiver: Receiver<SuggestionsRequest>,
+    proxy: EventLoopProxy<EventLoopProxyMessage>,
+) {
+    while let Ok(mut request) = receiver.recv() {
+        loop {
+            match receiver.recv_timeout(SUGGESTIONS_DEBOUNCE) {
+                Ok(newer) => request = newer,
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+        let SuggestionsRequest {
+            url,
+            window_id,
+            query,
+        } = request;
+        let suggestions = match http::get(&url, SUGGESTIONS_TIMEOUT, MAX_RESPONSE_SIZE)
+            .and_then(|body| parse_suggestions(&body))
+        {
+            Ok(suggestions) => suggestions,
+            Err(error) => {
+                log::debug!("Failed to fetch search suggestions from {url}: {error}");
+                continue;
+            }
+        };
+        if let Err(error) = proxy.send_event(EventLoopProxyMessage::SearchSuggestions {
+            window_id,
+            query,
+            suggestions,
+        }) {
+            log::error!("Failed to send search suggestions to Verso: {error}");
+            return;
+        }
+    }
+}
+
+/// Get the suggestions of an OpenSearch suggestions response, `["query", ["suggestion", ...]]`.
+fn parse_suggestions(body: &[u8]) -> std::io::Result<Vec<String>> {
+    let response: serde_json::Value = serde_json::from_slice(body)?;
+    let suggestions = response
+        .get(1)
+        .and_then(serde_json::Value::as_array)
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "not an OpenSearch suggestions response",
+            )
+        })?;
+    Ok(suggestions
+        .iter()
+        .filter_map(|suggestion| suggestion.as_str().map(str::to_owned))
+        .take(MAX_SUGGESTIONS)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_template_encodes_query() {
+        let url = fill_template("https://example.com/?q=%s", "rust & verso").unwrap();
+        assert_eq!(url.as_str(), "https://example.com/?q=rust+%26+verso");
+        assert!(fill_template("not a url %s", "rust").is_none());
+    }
+
+    #[test]
+    fn parse_url_input_with_and_without_scheme() {
+        let parse = |input| parse_url_input(input).map(String::from);
+        assert_eq!(
+            parse("example.com").as_deref(),
+            Some("https://example.com/")
+        );
+        assert_eq!(
+            parse("localhost:8080").as_deref(),
+            Some("https://localhost:8080/")
+        );
+        assert_eq!(
+            parse("example.com:8080/path").as_deref(),
+            Some("https://example.com:8080/path")
+        );
+        assert_eq!(parse("[::1]").as_deref(), Some("https://[::1]/"));
+        assert_eq!(
+            parse("http://example.com").as_deref(),
+            Some("http://example.com/")
+        );
+        assert_eq!(parse("about:blank").as_deref(), Some("about:blank"));
+        assert_eq!(
+            parse("mailto:someone@example.com").as_deref(),
+            Some("mailto:someone@example.com")
+        );
+        assert_eq!(parse("tel:+123456").as_deref(), Some("tel:+123456"));
+        assert_eq!(parse("rust"), None);
+        assert_eq!(parse("rust verso"), None);
+    }
+
+    #[test]
+    fn resolve_searches_and_keywords() {
+        let engines = SearchEngines::default();
+        let resolve = |input| engines.resolve(input).map(String::from);
+        assert_eq!(resolve("  ").as_deref(), None);
+        assert_eq!(
+            resolve(" example.com ").as_deref(),
+            Some("https://example.com/")
+        );
+        assert_eq!(
+            resolve("rust verso").as_deref(),
+            Some("https://duckduckgo.com/?q=rust+verso")
+        );
+        assert_eq!(
+            resolve("w rust").as_deref(),
+            Some("https://en.wikipedia.org/wiki/Special:Search?search=rust")
+        );
+        // A keyword without a query is searched for itself.
+        assert_eq!(resolve("w").as_deref(), Some("https://duckduckgo.com/?q=w"));
+    }
+
+    #[test]
+    fn custom_default_engine() {
+        let mut engines = SearchEngines::default();
+        engines.set_default("g").unwrap();
+        assert_eq!(
+            engines.resolve("rust").map(String::from).as_deref(),
+            Some("https://www.google.com/search?q=rust")
+        );
+        engines
+            .set_default("https://search.example/?terms=%s")
+            .unwrap();
+        assert_eq!(
+            engines.resolve("rust").map(String::from).as_deref(),
+            Some("https://search.example/?terms=rust")
+        );
+        assert!(engines.set_default("https://search.example/").is_err());
+    }
+}
@@ -0,0 +1,112 @@
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use servo_config::prefs::{self, Preferences};
+
+/// Secret which the `verso://settings` page sends along with its prompts. Other pages can't read
+/// it from there, so they can't change settings with prompts of their own.
+pub static SETTINGS_TOKEN: LazyLock<String> =
+    LazyLock::new(|| uuid::Uuid::new_v4().simple().to_string());
+
+/// Placeholder of [`SETTINGS_TOKEN`] in the settings page.
+pub const SETTINGS_TOKEN_PLACEHOLDER: &str = "%SETTINGS_TOKEN%";
+
+/// File in the config directory the saved settings are written to.
+const SAVED_SETTINGS_FILE: &str = "settings.json";
+
+/// Prefix of the prompts of the `verso://settings` page, followed by the token, and a colon and
+/// the [`Settings`] to change if it isn't only reading them.
+pub const SETTINGS_PROMPT_PREFIX: &str = "VERSO_SETTINGS:";
+
+/// Settings which the panel and the settings page can read and change, missing fields are left
+/// unchanged.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Settings {
+    /// Upgrade navigations to `http` URLs to `https`, for all windows. It's saved like
+    /// [`SavedSettings`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub https_only: Option<bool>,
+    /// Navigate with mouse gestures, for the window
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mouse_gestures: Option<bool>,
+    /// Servo preferences by name, for the whole process. Most of them are read when a page or
+    /// thread starts, so they only apply to pages loaded afterwards.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefs: Option<Map<String, Value>>,
+}
+
+/// Settings of Verso which are saved in the config directory if Verso has one, and only last for
+/// the session otherwise.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SavedSettings {
+    /// Whether HTTPS-only mode is on.
+    pub https_only: bool,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl SavedSettings {
+    /// Load the settings saved in the config directory.
+    pub fn load(config_dir: Option<&Path>) -> Self {
+        let Some(path) = config_dir.map(|dir| dir.join(SAVED_SETTINGS_FILE)) else {
+            return Self::default();
+        };
+        let settings = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                log::error!("Failed to parse saved settings {}: {e}", path.display());
+                Self::default()
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                log::error!("Failed to read saved settings {}: {e}", path.display());
+                Self::default()
+            }
+        };
+        Self {
+            path: Some(path),
+            ..settings
+        }
+    }
+
+    /// Write the settings to the config directory.
+    pub fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let content = serde_json::to_string_pretty(self).unwrap();
+        if let Err(e) = fs::write(path, content) {
+            log::error!("Failed to save settings {}: {e}", path.display());
+        }
+    }
+}
+
+/// Get all Servo preferences by name.
+pub fn servo_prefs() -> Map<String, Value> {
+    match serde_json::to_value(prefs::get().clone()) {
+        Ok(Value::Object(prefs)) => prefs,
+        _ => Map::new(),
+    }
+}
+
+/// Change some Servo preferences, the others are kept.
+pub fn set_servo_prefs(changes: Map<String, Value>) -> Result<(), String> {
+    let mut prefs = servo_prefs();
+    for (name, value) in changes {
+        if !prefs.contains_key(&name) {
+            return Err(format!("Unknown preference {name}"));
+        }
+        prefs.insert(name, value);
+    }
+    let prefs: Preferences =
+        serde_json::from_value(Value::Object(prefs)).map_err(|e| e.to_string())?;
+    prefs::set(prefs);
+    Ok(())
+}
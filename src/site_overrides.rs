@@ -0,0 +1,63 @@
+use std::path::Path;
+
+use serde::Deserialize;
+
+/// Compatibility fixes for a site which doesn't work well with Servo, like Firefox's
+/// interventions. They're applied with a script when a page of the site starts loading.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SiteOverride {
+    /// Domains the override applies to, their subdomains included
+    pub domains: Vec<String>,
+    /// User agent the pages see in `navigator.userAgent`. The `User-Agent` header of the
+    /// requests can't be changed per site, so this only helps with sniffing in scripts.
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// Script patching the pages
+    #[serde(default)]
+    pub script: Option<String>,
+    /// Why the site needs the override, for the people maintaining the list
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Load site overrides from a JSON file with a list of `{ "domains", "userAgent", "script" }`.
+pub fn load_site_overrides(path: &Path) -> Result<Vec<SiteOverride>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+/// Get the script applying the overrides of the page's domain, `None` if there are none.
+pub fn site_overrides_script(overrides: &[SiteOverride]) -> Option<String> {
+    if overrides.is_empty() {
+        return None;
+    }
+    let mut script = String::from(
+        "(() => {\n\
+         const host = location.hostname;\n\
+         const matches = (domains) =>\n\
+         domains.some((domain) => host === domain || host.endsWith('.' + domain));\n",
+    );
+    for site in overrides {
+        // Serialized strings are valid JavaScript literals.
+        let domains = serde_json::to_string(&site.domains).ok()?;
+        script.push_str(&format!("if (matches({domains})) {{\n"));
+        if let Some(user_agent) = &site.user_agent {
+            let user_agent = serde_json::to_string(user_agent).ok()?;
+            script.push_str(&format!(
+                "Object.defineProperty(navigator, 'userAgent', {{ get: () => {user_agent} }});\n"
+            ));
+        }
+        if let Some(patch) = &site.script {
+            // Compiled on its own so a broken patch doesn't keep the others from running.
+            let patch = serde_json::to_string(patch).ok()?;
+            script.push_str(&format!(
+                "try {{\nnew Function({patch})();\n}} catch (error) {{\n\
+                 console.error('Verso site override failed for', {domains}, error);\n}}\n"
+            ));
+        }
+        script.push_str("}\n");
+    }
+    script.push_str("})();\n");
+    Some(script)
+}
@@ -0,0 +1,58 @@
+use std::time::Instant;
+
+use versoview_messages::{StartupPhase, StartupReport};
+
+/// Times the phases of Verso's startup one after another.
+#[derive(Debug)]
+pub struct StartupProfiler {
+    /// When startup began.
+    started_at: Instant,
+    /// When the last phase ended, which is when the next one begins.
+    last: Instant,
+    phases: Vec<StartupPhase>,
+}
+
+impl StartupProfiler {
+    /// Start timing the first phase.
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            started_at: now,
+            last: now,
+            phases: Vec::new(),
+        }
+    }
+
+    /// When startup began.
+    pub fn started_at(&self) -> Instant {
+        self.started_at
+    }
+
+    /// Record that a phase ended now, it began when the previous one ended.
+    pub fn phase(&mut self, name: &str) {
+        let now = Instant::now();
+        let duration = now - self.last;
+        log::debug!("Startup phase {name} took {duration:?}");
+        self.phases.push(StartupPhase {
+            name: name.to_owned(),
+            duration,
+        });
+        self.last = now;
+    }
+
+    /// Finish timing and get the report of every phase.
+    pub fn finish(self) -> StartupReport {
+        let total = self.last - self.started_at;
+        log::info!("Verso started in {total:?}");
+        StartupReport {
+            phases: self.phases,
+            total,
+        }
+    }
+}
+
+impl Default for StartupProfiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
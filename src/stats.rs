@@ -0,0 +1,70 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// How far back frames are counted.
+const FRAME_WINDOW: Duration = Duration::from_secs(1);
+
+/// Composite timings of a window over the last second.
+#[derive(Debug, Default)]
+pub struct FrameStats {
+    /// When each frame was composited and how long it took.
+    frames: VecDeque<(Instant, Duration)>,
+}
+
+impl FrameStats {
+    /// Record a composited frame.
+    pub fn record(&mut self, duration: Duration) {
+        let now = Instant::now();
+        self.frames.push_back((now, duration));
+        while self
+            .frames
+            .front()
+            .is_some_and(|(time, _)| now.duration_since(*time) > FRAME_WINDOW)
+        {
+            self.frames.pop_front();
+        }
+    }
+
+    /// Number of frames composited in the last second.
+    pub fn fps(&self) -> usize {
+        let now = Instant::now();
+        self.frames
+            .iter()
+            .filter(|(time, _)| now.duration_since(*time) <= FRAME_WINDOW)
+            .count()
+    }
+
+    /// Average time spent compositing a frame in the last second.
+    pub fn frame_time(&self) -> Option<Duration> {
+        let now = Instant::now();
+        let (count, total) = self
+            .frames
+            .iter()
+            .filter(|(time, _)| now.duration_since(*time) <= FRAME_WINDOW)
+            .fold((0, Duration::ZERO), |(count, total), (_, duration)| {
+                (count + 1, total + *duration)
+            });
+        (count > 0).then(|| total / count)
+    }
+}
+
+/// Resident memory of the process in bytes, `None` if the platform isn't supported.
+pub fn resident_memory() -> Option<u64> {
+    #[cfg(linux)]
+    {
+        let status = std::fs::read_to_string("/proc/self/status").ok()?;
+        let kilobytes = status
+            .lines()
+            .find_map(|line| line.strip_prefix("VmRSS:"))?
+            .trim()
+            .strip_suffix("kB")?
+            .trim()
+            .parse::<u64>()
+            .ok()?;
+        Some(kilobytes * 1024)
+    }
+    #[cfg(not(linux))]
+    None
+}
@@ -14,8 +14,12 @@ pub struct Tab {
     webview: WebView,
     /// History
     history: TabHistory,
+    /// Page titles of the visited URLs
+    titles: HashMap<ServoUrl, String>,
     /// Prompt
     prompt: Option<PromptDialog>,
+    /// How far the current page load is from 0 to 1, `None` if the tab isn't loading
+    load_progress: Option<f32>,
 }
 
 impl Tab {
@@ -28,7 +32,9 @@ impl Tab {
                 list: Vec::new(),
                 current_idx: 0,
             },
+            titles: HashMap::new(),
             prompt: None,
+            load_progress: None,
         }
     }
 
@@ -57,6 +63,32 @@ impl Tab {
         self.history = TabHistory { list, current_idx };
     }
 
+    /// Get the page title of a visited URL.
+    pub fn title(&self, url: &ServoUrl) -> Option<&str> {
+        self.titles.get(url).map(String::as_str)
+    }
+
+    /// Set the page title of the current history entry.
+    pub fn set_title(&mut self, title: Option<String>) {
+        let Some(url) = self.history.list.get(self.history.current_idx) else {
+            return;
+        };
+        match title {
+            Some(title) => self.titles.insert(url.clone(), title),
+            None => self.titles.remove(url),
+        };
+    }
+
+    /// Get how far the current page load is, `None` if the tab isn't loading.
+    pub fn load_progress(&self) -> Option<f32> {
+        self.load_progress
+    }
+
+    /// Set how far the current page load is.
+    pub fn set_load_progress(&mut self, progress: Option<f32>) {
+        self.load_progress = progress;
+    }
+
     /// Get tab prompt dialog.
     pub fn prompt(&self) -> Option<&PromptDialog> {
         self.prompt.as_ref()
@@ -193,6 +225,20 @@ impl TabManager {
         };
     }
 
+    /// Set the page title of the tab's current history entry.
+    pub fn set_title(&mut self, tab_id: WebViewId, title: Option<String>) {
+        if let Some(tab) = self.tab_map.get_mut(&tab_id) {
+            tab.set_title(title);
+        };
+    }
+
+    /// Set how far the tab's current page load is.
+    pub fn set_load_progress(&mut self, tab_id: WebViewId, progress: Option<f32>) {
+        if let Some(tab) = self.tab_map.get_mut(&tab_id) {
+            tab.set_load_progress(progress);
+        };
+    }
+
     /* Prompt */
 
     /// Get prompt dialog by tab id.
@@ -240,6 +286,14 @@ impl TabManager {
             None
         }
     }
+    /// Get the tab webview ID a prompt belongs to.
+    pub fn prompt_tab_id(&self, prompt_id: WebViewId) -> Option<WebViewId> {
+        self.prompt_tab_map.get(&prompt_id).copied()
+    }
+    /// Get all prompt webview IDs.
+    pub fn prompt_ids(&self) -> Vec<WebViewId> {
+        self.prompt_tab_map.keys().copied().collect()
+    }
     /// Check if there is a prompt dialog by prompt webview ID.
     pub fn has_prompt(&self, prompt_id: WebViewId) -> bool {
         self.prompt_tab_map.contains_key(&prompt_id)
@@ -0,0 +1,79 @@
+use tracing_subscriber::{
+    fmt::{self, format::FmtSpan},
+    layer::SubscriberExt,
+    util::SubscriberInitExt,
+    EnvFilter,
+};
+
+/// Environment variable with the filter of the spans to record, with the syntax of `RUST_LOG`
+/// like `versoview=trace`. Nothing is recorded when it isn't set.
+const FILTER_ENV: &str = "VERSO_TRACING";
+
+/// Record the spans of Verso's event loop, compositor and IPC handling. They're exported with
+/// OTLP when `OTEL_EXPORTER_OTLP_ENDPOINT` is set and the `tracing-otlp` feature is enabled, so
+/// embedders can see them next to their own traces, and printed to stderr when they closed
+/// otherwise.
+pub fn init() {
+    let Ok(filter) = EnvFilter::try_from_env(FILTER_ENV) else {
+        return;
+    };
+    #[cfg(feature = "tracing-otlp")]
+    let otlp = otlp::layer();
+    #[cfg(not(feature = "tracing-otlp"))]
+    let otlp: Option<tracing_subscriber::layer::Identity> = None;
+
+    let stderr = otlp.is_none().then(|| {
+        fmt::layer()
+            .with_writer(std::io::stderr)
+            .with_span_events(FmtSpan::CLOSE)
+    });
+    let result = tracing_subscriber::registry()
+        .with(filter)
+        .with(otlp)
+        .with(stderr)
+        .try_init();
+    if let Err(e) = result {
+        log::error!("Failed to set the tracing subscriber: {e}");
+    }
+}
+
+/// Export the spans which haven't been yet before exiting.
+pub fn shutdown() {
+    #[cfg(feature = "tracing-otlp")]
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+#[cfg(feature = "tracing-otlp")]
+mod otlp {
+    use opentelemetry::{trace::TracerProvider as _, KeyValue};
+    use opentelemetry_otlp::SpanExporter;
+    use opentelemetry_sdk::{runtime, trace::TracerProvider, Resource};
+    use tracing_opentelemetry::OpenTelemetryLayer;
+    use tracing_subscriber::registry::LookupSpan;
+
+    /// Name Verso's spans are reported under.
+    const SERVICE_NAME: &str = "verso";
+
+    /// A layer exporting spans to the OTLP endpoint in `OTEL_EXPORTER_OTLP_ENDPOINT`, `None` if
+    /// it isn't set.
+    pub fn layer<S>() -> Option<OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>>
+    where
+        S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+    {
+        std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT")?;
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .build()
+            .inspect_err(|e| log::error!("Failed to create the OTLP exporter: {e}"))
+            .ok()?;
+        // Spans are exported in batches from a thread of their own, so the event loop never
+        // waits for the collector.
+        let provider = TracerProvider::builder()
+            .with_batch_exporter(exporter, runtime::TokioCurrentThread)
+            .with_resource(Resource::new([KeyValue::new("service.name", SERVICE_NAME)]))
+            .build();
+        let tracer = provider.tracer(SERVICE_NAME);
+        opentelemetry::global::set_tracer_provider(provider);
+        Some(tracing_opentelemetry::layer().with_tracer(tracer))
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::{Duration, Instant};
+
 use embedder_traits::TouchId;
 use euclid::{Point2D, Scale, Vector2D};
 use log::warn;
@@ -9,12 +11,25 @@ use self::TouchState::*;
 /// Minimum number of `DeviceIndependentPixel` to begin touch scrolling.
 const TOUCH_PAN_MIN_SCREEN_PX: f32 = 20.0;
 
+/// Minimum speed in `DevicePixel` per second a pan has to be released at to start a fling.
+const FLING_MIN_SPEED: f32 = 300.0;
+
+/// Weight of the latest movement in the smoothed pan velocity.
+const VELOCITY_SMOOTHING: f32 = 0.6;
+
+/// How long a pan can rest before it's released for the release to still start a fling.
+const FLING_MAX_REST: Duration = Duration::from_millis(50);
+
 /// Handler of touch inputs and states.
 pub struct TouchHandler {
     /// State of the touch handler
     pub state: TouchState,
     /// Cerrent active touch points.
     pub active_touch_points: Vec<TouchPoint>,
+    /// Smoothed velocity of the current pan in `DevicePixel` per second.
+    velocity: Vector2D<f32, DevicePixel>,
+    /// Time of the last pan movement.
+    last_move_time: Option<Instant>,
 }
 
 /// The point of touch input
@@ -34,8 +49,6 @@ impl TouchPoint {
 }
 
 /// The states of the touch input state machine.
-///
-/// TODO: Add support for "flinging" (scrolling inertia)
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum TouchState {
     /// Not tracking any touch point
@@ -65,6 +78,8 @@ pub enum TouchAction {
     Scroll(Vector2D<f32, DevicePixel>),
     /// Zoom by a magnification factor and scroll by the provided offset.
     Zoom(f32, Vector2D<f32, DevicePixel>),
+    /// Keep scrolling with the provided velocity in `DevicePixel` per second after the pan ended.
+    Fling(Vector2D<f32, DevicePixel>),
     /// Send a JavaScript event to content.
     DispatchEvent,
     /// Don't do anything.
@@ -76,6 +91,8 @@ impl Default for TouchHandler {
         Self {
             state: Nothing,
             active_touch_points: Vec::new(),
+            velocity: Vector2D::zero(),
+            last_move_time: None,
         }
     }
 }
@@ -119,6 +136,8 @@ impl TouchHandler {
                     || delta.y.abs() > TOUCH_PAN_MIN_SCREEN_PX
                 {
                     self.state = Panning;
+                    self.velocity = Vector2D::zero();
+                    self.last_move_time = Some(Instant::now());
                     TouchAction::Scroll(delta)
                 } else {
                     TouchAction::NoAction
@@ -126,6 +145,7 @@ impl TouchHandler {
             }
             Panning => {
                 let delta = point - old_point;
+                self.track_velocity(delta);
                 TouchAction::Scroll(delta)
             }
             DefaultPrevented => TouchAction::DispatchEvent,
@@ -169,10 +189,21 @@ impl TouchHandler {
                 self.state = Nothing;
                 TouchAction::Click
             }
-            Nothing | Panning => {
+            Panning => {
                 self.state = Nothing;
-                TouchAction::NoAction
+                let velocity = std::mem::replace(&mut self.velocity, Vector2D::zero());
+                // The finger stopped before it was lifted, the last movement doesn't count.
+                let resting = self
+                    .last_move_time
+                    .take()
+                    .is_none_or(|time| time.elapsed() > FLING_MAX_REST);
+                if !resting && velocity.length() > FLING_MIN_SPEED {
+                    TouchAction::Fling(velocity)
+                } else {
+                    TouchAction::NoAction
+                }
             }
+            Nothing => TouchAction::NoAction,
             Pinching => {
                 self.state = Panning;
                 TouchAction::NoAction
@@ -227,6 +258,17 @@ impl TouchHandler {
         }
     }
 
+    /// Update the smoothed pan velocity with the latest movement.
+    fn track_velocity(&mut self, delta: Vector2D<f32, DevicePixel>) {
+        let now = Instant::now();
+        if let Some(last_move_time) = self.last_move_time.replace(now) {
+            let elapsed = (now - last_move_time).as_secs_f32();
+            if elapsed > 0. {
+                self.velocity = self.velocity.lerp(delta / elapsed, VELOCITY_SMOOTHING);
+            }
+        }
+    }
+
     fn touch_count(&self) -> usize {
         self.active_touch_points.len()
     }
@@ -0,0 +1,141 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{LazyLock, Mutex},
+};
+
+use base::cross_process_instant::CrossProcessInstant;
+use ipc_channel::{ipc, router::ROUTER};
+use profile_traits::time::{ProfilerChan, ProfilerMsg};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Events a trace keeps at most, later ones are dropped so a forgotten trace can't use up the
+/// memory.
+const MAX_TRACE_EVENTS: usize = 1_000_000;
+
+/// The trace being recorded. Servo reports timings to the time profiler whether it prints them or
+/// not, and they're recorded on their way to it from the router thread.
+static TRACE: LazyLock<Mutex<Option<Trace>>> = LazyLock::new(Default::default);
+
+/// Timings of Servo's time profiler in the Chrome `trace_event` format.
+struct Trace {
+    /// When the trace started, timestamps are relative to it.
+    start: CrossProcessInstant,
+    /// Track of every page, pages are shown as threads so each one gets a row of its own.
+    tracks: HashMap<String, usize>,
+    events: Vec<TraceEvent>,
+}
+
+/// A complete event of the `trace_event` format.
+#[derive(Serialize)]
+struct TraceEvent {
+    name: String,
+    cat: &'static str,
+    ph: &'static str,
+    /// Start in microseconds
+    ts: f64,
+    /// Duration in microseconds
+    dur: f64,
+    pid: u32,
+    tid: usize,
+}
+
+/// Wrap the channel of the time profiler to record the timings sent to it while a trace is
+/// running.
+pub fn record_traces(profiler: ProfilerChan) -> ProfilerChan {
+    let (sender, receiver) = ipc::channel().expect("ipc channel failure");
+    ROUTER.add_typed_route(
+        receiver,
+        Box::new(move |message| {
+            let message = match message {
+                Ok(message) => message,
+                Err(e) => {
+                    log::error!("Failed to receive time profiler message: {e}");
+                    return;
+                }
+            };
+            if let ProfilerMsg::Time((category, metadata), (start, end)) = &message {
+                if let Some(trace) = TRACE.lock().unwrap().as_mut() {
+                    let page = metadata.as_ref().map(|metadata| metadata.url.as_str());
+                    trace.add(format!("{category:?}"), page, *start, *end);
+                }
+            }
+            profiler.send(message);
+        }),
+    );
+    ProfilerChan(sender)
+}
+
+impl Trace {
+    fn add(
+        &mut self,
+        name: String,
+        page: Option<&str>,
+        start: CrossProcessInstant,
+        end: CrossProcessInstant,
+    ) {
+        if self.events.len() >= MAX_TRACE_EVENTS || start < self.start {
+            return;
+        }
+        // Timings without a page, like the compositor's, go on the first track.
+        let track_count = self.tracks.len();
+        let tid = match page {
+            Some(page) => *self
+                .tracks
+                .entry(page.to_owned())
+                .or_insert(track_count + 1),
+            None => 0,
+        };
+        self.events.push(TraceEvent {
+            name,
+            cat: "servo",
+            ph: "X",
+            ts: (start - self.start).whole_nanoseconds() as f64 / 1000.,
+            dur: (end - start).whole_nanoseconds() as f64 / 1000.,
+            pid: std::process::id(),
+            tid,
+        });
+    }
+
+    /// The trace as a JSON object, with the names of the tracks as metadata events.
+    fn to_json(&self) -> Value {
+        let pid = std::process::id();
+        let mut events: Vec<Value> = vec![json!({
+            "name": "thread_name", "ph": "M", "pid": pid, "tid": 0,
+            "args": { "name": "Verso" },
+        })];
+        events.extend(self.tracks.iter().map(|(page, tid)| {
+            json!({
+                "name": "thread_name", "ph": "M", "pid": pid, "tid": tid,
+                "args": { "name": page },
+            })
+        }));
+        events.extend(
+            self.events
+                .iter()
+                .filter_map(|event| serde_json::to_value(event).ok()),
+        );
+        json!({ "traceEvents": events, "displayTimeUnit": "ms" })
+    }
+}
+
+/// Start recording a trace, a trace already running starts over.
+pub fn start() {
+    TRACE.lock().unwrap().replace(Trace {
+        start: CrossProcessInstant::now(),
+        tracks: HashMap::new(),
+        events: Vec::new(),
+    });
+}
+
+/// Stop recording and write the trace to a JSON file, which Perfetto and `chrome://tracing` open.
+pub fn stop(path: &Path) -> Result<(), String> {
+    let trace = TRACE
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or_else(|| "No trace is running".to_owned())?;
+    let json = serde_json::to_vec(&trace.to_json()).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
@@ -0,0 +1,286 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use minisign_verify::{PublicKey, Signature};
+use serde::Deserialize;
+use url::Url;
+use winit::event_loop::EventLoopProxy;
+
+use crate::{http, verso::EventLoopProxyMessage};
+
+/// Environment variable pointing a restarted Verso to the session file to restore.
+pub const RESTORE_SESSION_VAR: &str = "VERSO_RESTORE_SESSION";
+
+/// How long there has to be no user input before Verso restarts into an update.
+pub const RESTART_IDLE_TIME: Duration = Duration::from_secs(60);
+
+/// How often the update feed is checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// How long to wait for connecting to and reading from the update server.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Largest update feed we read.
+const MAX_FEED_SIZE: u64 = 64 * 1024;
+
+/// Largest artifact we download.
+const MAX_ARTIFACT_SIZE: u64 = 512 * 1024 * 1024;
+
+/// The latest release announced by an update feed, like
+/// `{ "version": "0.1.0", "platforms": { "linux-x86_64": { "url", "signature" } } }`.
+#[derive(Debug, Deserialize)]
+struct UpdateFeed {
+    version: String,
+    platforms: HashMap<String, Artifact>,
+}
+
+/// The executable of a release for a platform.
+#[derive(Debug, Deserialize)]
+struct Artifact {
+    /// Where to download the executable from
+    url: Url,
+    /// Minisign signature of the executable, with a trusted comment naming the version and
+    /// platform like `version:0.1.0 platform:linux-x86_64`
+    signature: String,
+}
+
+/// Check the update feed in a background thread every few hours. A newer release is downloaded
+/// and verified against the public key, written next to the running executable, and reported to
+/// the event loop as [`EventLoopProxyMessage::UpdateReady`].
+///
+/// Artifacts are the Verso executable itself, so this is meant for standalone distributions
+/// which can write to their own directory.
+pub fn check_for_updates(
+    feed: Url,
+    public_key: PublicKey,
+    proxy: EventLoopProxy<EventLoopProxyMessage>,
+) {
+    let spawn_result = thread::Builder::new()
+        .name("Updater".to_owned())
+        .spawn(move || loop {
+            match download_update(&feed, &public_key) {
+                Ok(Some(update)) => {
+                    log::info!("Update downloaded to {}", update.display());
+                    if let Err(error) = proxy.send_event(EventLoopProxyMessage::UpdateReady(update))
+                    {
+                        log::error!("Failed to send the update to Verso: {error}");
+                    }
+                    return;
+                }
+                Ok(None) => log::debug!("Verso is up to date"),
+                Err(error) => log::warn!("Failed to check for updates at {feed}: {error}"),
+            }
+            thread::sleep(CHECK_INTERVAL);
+        });
+    if let Err(error) = spawn_result {
+        log::error!("Failed to spawn updater thread: {error}");
+    }
+}
+
+/// Download and verify a newer release if the feed has one for this platform.
+fn download_update(feed: &Url, public_key: &PublicKey) -> Result<Option<PathBuf>, String> {
+    let body = http::get(feed, REQUEST_TIMEOUT, MAX_FEED_SIZE).map_err(|e| e.to_string())?;
+    let feed: UpdateFeed = serde_json::from_slice(&body).map_err(|e| e.to_string())?;
+    let current = env!("CARGO_PKG_VERSION");
+    if !is_newer(&feed.version, current) {
+        return Ok(None);
+    }
+    let platform = format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH);
+    let Some(artifact) = feed.platforms.get(&platform) else {
+        log::info!("Verso {} is available but not for {platform}", feed.version);
+        return Ok(None);
+    };
+    if artifact.url.scheme() != "https" && !http::is_loopback(&artifact.url) {
+        return Err(format!("Artifact {} must be https", artifact.url));
+    }
+    // The feed isn't signed, so the signed trusted comment is what ties the artifact to the
+    // version. Otherwise an older signed artifact could be served as a newer version.
+    let signature = Signature::decode(&artifact.signature).map_err(|e| e.to_string())?;
+    if !trusted_comment_matches(signature.trusted_comment(), &feed.version, &platform) {
+        return Err(format!(
+            "Signature of Verso {} is for {:?} instead",
+            feed.version,
+            signature.trusted_comment()
+        ));
+    }
+
+    // Next to the executable so it can be renamed over it.
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let partial = exe.with_extension("update.part");
+    let path = exe.with_extension("update");
+    log::info!("Downloading Verso {} from {}", feed.version, artifact.url);
+    let result = download_artifact(&artifact.url, &partial).and_then(|()| {
+        verify_file(&partial, public_key, &signature)
+            .map_err(|e| format!("Signature of Verso {} doesn't match: {e}", feed.version))
+    });
+    if let Err(error) = result {
+        let _ = fs::remove_file(&partial);
+        return Err(error);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&partial, fs::Permissions::from_mode(0o755))
+            .map_err(|e| e.to_string())?;
+    }
+    fs::rename(&partial, &path).map_err(|e| e.to_string())?;
+    Ok(Some(path))
+}
+
+/// Stream an artifact into a file instead of holding it in memory.
+fn download_artifact(url: &Url, path: &Path) -> Result<(), String> {
+    let mut file = BufWriter::new(File::create(path).map_err(|e| e.to_string())?);
+    http::download(
+        url,
+        http::Options::new(REQUEST_TIMEOUT, MAX_ARTIFACT_SIZE),
+        &mut file,
+    )
+    .map_err(|e| e.to_string())?;
+    file.flush().map_err(|e| e.to_string())
+}
+
+/// Verify the signature of a downloaded file, reading it in chunks.
+fn verify_file(path: &Path, public_key: &PublicKey, signature: &Signature) -> Result<(), String> {
+    let mut verifier = public_key
+        .verify_stream(signature)
+        .map_err(|e| e.to_string())?;
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        verifier.update(&buffer[..read]);
+    }
+    verifier.finalize().map_err(|e| e.to_string())
+}
+
+/// Whether a trusted comment like `version:0.1.0 platform:linux-x86_64` names the version and
+/// platform of the artifact. Other fields, like the `timestamp` minisign adds, are ignored.
+fn trusted_comment_matches(comment: &str, version: &str, platform: &str) -> bool {
+    let field = |name: &str| {
+        comment
+            .split_whitespace()
+            .find_map(|field| field.strip_prefix(name)?.strip_prefix(':'))
+    };
+    field("version") == Some(version) && field("platform") == Some(platform)
+}
+
+/// Whether a dotted version is newer than the current one, pre-release suffixes are ignored.
+fn is_newer(version: &str, current: &str) -> bool {
+    fn parse(version: &str) -> Vec<u64> {
+        version
+            .trim_start_matches('v')
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    }
+    parse(version) > parse(current)
+}
+
+/// Replace the running executable with a downloaded update. The previous executable is kept
+/// with the `old` extension, since Windows can't remove it while it's running.
+pub fn install(update: &Path) -> std::io::Result<()> {
+    let exe = std::env::current_exe()?;
+    let old = exe.with_extension("old");
+    let _ = fs::remove_file(&old);
+    fs::rename(&exe, &old)?;
+    if let Err(error) = fs::rename(update, &exe) {
+        let _ = fs::rename(&old, &exe);
+        return Err(error);
+    }
+    Ok(())
+}
+
+/// Save the URLs of the tabs to a file for the restarted Verso to open again. The temporary
+/// directory may be shared with other users, so the file gets a random name, is only readable by
+/// the user, and is never an existing file or symlink.
+pub fn save_session(urls: &[Url]) -> Result<PathBuf, String> {
+    let path = std::env::temp_dir().join(format!("verso-session-{}.json", uuid::Uuid::new_v4()));
+    let content = serde_json::to_string(urls).map_err(|e| e.to_string())?;
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(&path)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// Get the URLs of the session saved before restarting into an update, the current tab first.
+/// The session file is removed, so it's only restored once.
+pub fn take_restored_session() -> Vec<Url> {
+    let Some(path) = std::env::var_os(RESTORE_SESSION_VAR) else {
+        return Vec::new();
+    };
+    let content = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+    content
+        .map_err(|e| e.to_string())
+        .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()))
+        .unwrap_or_else(|error| {
+            log::error!("Failed to restore the session: {error}");
+            Vec::new()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trusted_comment() {
+        let comment = "timestamp:1700000000 version:0.2.0 platform:linux-x86_64";
+        assert!(trusted_comment_matches(comment, "0.2.0", "linux-x86_64"));
+        assert!(!trusted_comment_matches(comment, "0.3.0", "linux-x86_64"));
+        assert!(!trusted_comment_matches(comment, "0.2.0", "windows-x86_64"));
+        assert!(!trusted_comment_matches(
+            "timestamp:1700000000 file:verso",
+            "0.2.0",
+            "linux-x86_64"
+        ));
+        assert!(!trusted_comment_matches(
+            "versions:0.2.0 platform:linux-x86_64",
+            "0.2.0",
+            "linux-x86_64"
+        ));
+    }
+
+    #[test]
+    fn private_session_file() {
+        let urls = vec![Url::parse("https://example.com/").unwrap()];
+        let path = save_session(&urls).unwrap();
+        let other = save_session(&urls).unwrap();
+        assert_ne!(path, other);
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<Url>>(&content).unwrap(), urls);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        fs::remove_file(path).unwrap();
+        fs::remove_file(other).unwrap();
+    }
+
+    #[test]
+    fn newer_versions() {
+        assert!(is_newer("0.2.0", "0.1.9"));
+        assert!(is_newer("v1.0.0", "0.9.0"));
+        assert!(!is_newer("0.1.0", "0.1.0"));
+        assert!(!is_newer("0.1.0-beta", "0.1.0"));
+    }
+}
@@ -1,54 +1,162 @@
 use std::{
     borrow::Cow,
-    collections::HashMap,
-    sync::{atomic::Ordering, Arc},
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
 
-use arboard::Clipboard;
 use base::id::{PipelineNamespace, PipelineNamespaceId, TopLevelBrowsingContextId, WebViewId};
 use bluetooth::BluetoothThreadFactory;
 use bluetooth_traits::BluetoothRequest;
 use canvas::canvas_paint_thread::CanvasPaintThread;
 use compositing_traits::{CompositorMsg, CompositorProxy, CompositorReceiver, ConstellationMsg};
-use constellation::{Constellation, FromCompositorLogger, InitialConstellationState};
+use constellation::{Constellation, InitialConstellationState};
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use devtools;
 use embedder_traits::{
-    AllowOrDeny, EmbedderMsg, EmbedderProxy, EventLoopWaker, HttpBodyData, WebResourceResponse,
-    WebResourceResponseMsg,
+    AllowOrDeny, EmbedderMsg, EmbedderProxy, EventLoopWaker, HttpBodyData, ImeEvent, InputEvent,
+    InputMethodType, LoadStatus, TraversalDirection, WebResourceResponse, WebResourceResponseMsg,
 };
 use euclid::Scale;
 use fonts::SystemFontService;
 use ipc_channel::ipc::{self, IpcSender};
 use ipc_channel::router::ROUTER;
+use keyboard_types::{CompositionEvent, CompositionState, Key, KeyState, KeyboardEvent};
 use layout_thread_2020;
-use log::{Log, Metadata, Record};
-use media::{GlApi, GlContext, NativeDisplay, WindowGLContext};
+use media::{GLPlayerThreads, GlContext, WindowGLContext};
 use net::resource_thread;
+use net_traits::pub_domains::reg_host;
 use profile;
+use profile_traits::mem::{ProfilerMsg, Reporter, ReporterRequest, ReportsChan};
 use script::{self, JSEngineSetup};
 use script_traits::WindowSizeData;
 use servo_config::{opts, pref};
 use servo_url::ServoUrl;
 use style;
-use versoview_messages::{ToControllerMessage, ToVersoMessage};
+use versoview_messages::{
+    ConnectivityState, CrashReport, HistoryEntry, HistoryList, IdleState, LifecycleEvent,
+    LoadProgress, LoadStage, NavigationPolicy, NewWebViewPolicy, PageInfo, StartupReport,
+    TextInputChange, TextInputEvent, TextInputState, TextInputType, ToControllerMessage,
+    ToVersoMessage, VersoWebviewId, VersoWindowId,
+};
 use webgpu;
-use webrender::{create_webrender_instance, ShaderPrecacheFlags, WebRenderOptions};
+use webrender::{
+    create_webrender_instance, CompositorConfig, ShaderPrecacheFlags, WebRenderOptions,
+};
 use webrender_api::*;
 use webrender_traits::*;
 use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
     event::WindowEvent,
     event_loop::{ActiveEventLoop, ControlFlow, EventLoopProxy},
-    window::WindowId,
+    window::{Window as WinitWindow, WindowAttributes, WindowId, WindowLevel},
 };
 
 use crate::{
+    clipboard::Clipboard,
     compositor::{IOCompositor, InitialCompositorState, ShutdownState},
     config::Config,
+    connectivity,
+    crash::{self, CrashReporter},
+    dom_snapshot,
+    keybinding::KeyBindings,
+    logging::{self, LogSettings},
+    management::{self, ManagementCommand},
+    memory,
+    playlist::Playlist,
+    popup_blocker::PopupExceptions,
+    repl::Repl,
+    search::{SearchEngines, SuggestionsFetcher},
+    settings::SavedSettings,
+    site_overrides::site_overrides_script,
+    startup::StartupProfiler,
+    trace, updater,
     webview::execute_script,
-    window::Window,
+    window::{winit_theme, NewWebViewRequests, Window},
+    wpt::{WptRunner, WptStep, WPT_HOOK_SCRIPT},
 };
 
+/// How often webviews which don't belong to any window are looked for.
+const WEBVIEW_AUDIT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long a shutdown which wasn't given a timeout can take before Verso exits anyway.
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Set for a Verso started by [`restart`], which waits for the previous process to release its
+/// ports first.
+const RESTARTED_VAR: &str = "VERSO_RESTARTED";
+
+/// How long a restarted Verso waits for the ports of the previous process.
+const RESTART_PORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long Verso has to run before it restarts when the constellation is gone, so a
+/// constellation which dies on start doesn't restart Verso over and over.
+const CONSTELLATION_RESTART_MIN_UPTIME: Duration = Duration::from_secs(30);
+
+/// Set when a message couldn't be sent to the constellation since its thread is gone.
+static CONSTELLATION_LOST: AtomicBool = AtomicBool::new(false);
+
+/// Ids of windows and webviews exposed to the controller. Unlike winit's window ids and Servo's
+/// webview ids, they are plain numbers and never reused within a process.
+#[derive(Default)]
+struct PublicIds {
+    last_window_id: u64,
+    last_webview_id: u64,
+    windows: HashMap<WindowId, VersoWindowId>,
+    webviews: HashMap<WebViewId, VersoWebviewId>,
+}
+
+impl PublicIds {
+    /// Get the public id of a window, assigning one if it doesn't have one yet.
+    fn window(&mut self, id: WindowId) -> VersoWindowId {
+        *self.windows.entry(id).or_insert_with(|| {
+            self.last_window_id += 1;
+            VersoWindowId(self.last_window_id)
+        })
+    }
+
+    /// Get the public id of a webview, assigning one if it doesn't have one yet.
+    fn webview(&mut self, id: WebViewId) -> VersoWebviewId {
+        *self.webviews.entry(id).or_insert_with(|| {
+            self.last_webview_id += 1;
+            VersoWebviewId(self.last_webview_id)
+        })
+    }
+
+    /// Get the webview with a public id, `None` if it's closed or its id was never exposed.
+    fn find_webview(&self, public_id: VersoWebviewId) -> Option<WebViewId> {
+        self.webviews
+            .iter()
+            .find_map(|(id, public)| (*public == public_id).then_some(*id))
+    }
+
+    /// Get the window with a public id, `None` if it's closed or its id was never exposed.
+    fn find_window(&self, public_id: VersoWindowId) -> Option<WindowId> {
+        self.windows
+            .iter()
+            .find_map(|(id, public)| (*public == public_id).then_some(*id))
+    }
+
+    /// Forget a closed window and return its public id.
+    fn remove_window(&mut self, id: WindowId) -> VersoWindowId {
+        let public_id = self.window(id);
+        self.windows.remove(&id);
+        public_id
+    }
+
+    /// Forget a closed webview and return its public id.
+    fn remove_webview(&mut self, id: WebViewId) -> VersoWebviewId {
+        let public_id = self.webview(id);
+        self.webviews.remove(&id);
+        public_id
+    }
+}
+
 /// Main entry point of Verso browser.
 pub struct Verso {
     windows: HashMap<WindowId, (Window, DocumentId)>,
@@ -60,8 +168,105 @@ pub struct Verso {
     /// and deinitialization of the JS Engine. Multiprocess Servo instances have their
     /// own instance that exists in the content process instead.
     _js_engine_setup: Option<JSEngineSetup>,
-    /// FIXME: It's None on wayland in Flatpak. Find a way to support this.
+    /// System clipboard, `None` if it couldn't be accessed.
     clipboard: Option<Clipboard>,
+    /// Port and connection token of the devtools server once it has started.
+    devtools_server: Option<(u16, String)>,
+    /// Attributes for windows opened after the initial one, they must match its transparency
+    /// since all windows share the same GL config.
+    new_window_attributes: WindowAttributes,
+    /// Whether windows recognize mouse gestures.
+    mouse_gestures: bool,
+    /// Page drawn as the browser UI of new windows instead of the built-in panel, and its height.
+    chrome: Option<(ServoUrl, f64)>,
+    /// Settings saved in the config directory, like whether windows upgrade navigations to
+    /// `https`.
+    saved_settings: SavedSettings,
+    /// URL patterns which pages can and can't navigate to, for new windows.
+    navigation_policy: NavigationPolicy,
+    /// Where pages open new webviews.
+    new_webview_policy: NewWebViewPolicy,
+    /// Whether windows block popups opened without user activation.
+    block_popups: bool,
+    /// Origins which can open popups without user activation, shared by all windows.
+    popup_exceptions: PopupExceptions,
+    /// Script run when a page of any window starts loading.
+    init_script: Option<String>,
+    /// Search engines for URL bar input which isn't a URL.
+    search_engines: SearchEngines,
+    /// Page loaded by the Home action and by new windows.
+    homepage: ServoUrl,
+    /// Fetches search suggestions for URL bar input, `None` if the panel can't.
+    suggestions_fetcher: Option<SuggestionsFetcher>,
+    /// Event loop proxy for background tasks to report back to Verso.
+    event_loop_proxy: EventLoopProxy<EventLoopProxyMessage>,
+    /// URL of the captive portal probe, connectivity checks are disabled if it's `None`.
+    captive_portal_probe: Option<url::Url>,
+    /// Whether to open a detected captive portal in a new tab.
+    open_captive_portal: bool,
+    /// Result of the last connectivity check.
+    connectivity: Option<ConnectivityState>,
+    /// Keyboard shortcuts handled before key events reach the page.
+    keybindings: KeyBindings,
+    /// When the webviews were last checked for leaks.
+    last_webview_audit: Instant,
+    /// Webviews which didn't belong to any window in the last audit.
+    detached_webviews: HashSet<WebViewId>,
+    /// Whether the controller listens to lifecycle events.
+    lifecycle_listener: bool,
+    /// Whether the controller listens to changes of the current pages.
+    page_listener: bool,
+    /// Whether the controller listens to the loading progress of tabs.
+    load_progress_listener: bool,
+    /// Whether the controller listens on text fields gaining and losing focus.
+    text_input_listener: bool,
+    /// Whether the controller listens on connectivity changes.
+    connectivity_listener: bool,
+    /// Pages of any window waiting for the controller to decide where their new webviews open,
+    /// `None` if it doesn't listen.
+    new_webview_requests: Option<NewWebViewRequests>,
+    /// Windows reported in the last lifecycle update.
+    known_windows: HashSet<WindowId>,
+    /// Ids of windows and webviews exposed to the controller.
+    public_ids: PublicIds,
+    /// Restart with a fresh session after this long without user input.
+    idle_reset: Option<Duration>,
+    /// When the user last interacted with any window.
+    last_input: Instant,
+    /// Whether the user interacted with any window since Verso started, there's nothing to reset
+    /// otherwise.
+    input_since_start: bool,
+    /// Report to the controller when there's no user input for this long.
+    idle_timeout: Option<Duration>,
+    /// Whether the controller has been told that the user is idle.
+    idle: bool,
+    /// When Verso started.
+    started_at: Instant,
+    /// Whether Verso starts again after shutting down.
+    restart_on_exit: bool,
+    /// Session file for Verso to restore after restarting.
+    restart_session: Option<PathBuf>,
+    /// Downloaded update to restart into once the user is idle.
+    pending_update: Option<PathBuf>,
+    /// When Verso exits even if the constellation hasn't finished shutting down.
+    shutdown_deadline: Option<Instant>,
+    /// Pages the main window rotates through for digital signage.
+    playlist: Option<Playlist>,
+    /// Web platform tests the main window runs for `--wpt`.
+    wpt: Option<WptRunner>,
+    /// The window created at startup, the playlist and web platform tests stay in it whichever
+    /// windows are opened later.
+    main_window: WindowId,
+    /// JavaScript console of a webview opened by the controller.
+    repl: Option<Repl>,
+    /// Where to write the trace recorded since launch for `--profiler-chrome-trace`.
+    chrome_trace: Option<PathBuf>,
+    /// How long each phase of startup took.
+    startup_report: StartupReport,
+    /// Crash handler sending minidumps of crashes to the crash monitor.
+    _crash_reporter: Option<CrashReporter>,
+    /// Crashes of previous runs found at startup, reported when the controller listens.
+    crash_reports: Vec<CrashReport>,
 }
 
 impl Verso {
@@ -81,8 +286,25 @@ impl Verso {
     /// - Canvas: Enabled
     /// - Constellation: Enabled
     /// - Image Cache: Enabled
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, level = "trace"))]
     pub fn new(evl: &ActiveEventLoop, proxy: EventLoopProxy<EventLoopProxyMessage>) -> Self {
+        let mut startup = StartupProfiler::new();
         let config = Config::new();
+        let (crash_reporter, crash_reports) =
+            match (&config.args.config_dir, config.args.crash_reports) {
+                (Some(config_dir), true) => {
+                    let reports = crash::pending_reports(config_dir);
+                    let reporter = CrashReporter::start(config_dir)
+                        .inspect_err(|e| log::error!("Failed to start crash reporting: {e}"))
+                        .ok();
+                    (reporter, reports)
+                }
+                (None, true) => {
+                    log::warn!("Crash reporting needs --config-dir to write minidumps to");
+                    (None, Vec::new())
+                }
+                _ => (None, Vec::new()),
+            };
         let to_controller_sender = if let Some(ipc_channel) = &config.args.ipc_channel {
             let sender =
                 IpcSender::<ToControllerMessage>::connect(ipc_channel.to_string()).unwrap();
@@ -109,27 +331,125 @@ impl Verso {
             None
         };
 
+        startup.phase("config");
+
         // Initialize configurations and Verso window
         let protocols = config.create_protocols();
-        let initial_url = config.args.url.clone();
+        let wpt = WptRunner::new(config.args.wpt_tests.clone(), config.args.wpt_timeout);
+        // Tests decide which page is shown, the playlist would navigate away from them.
+        let playlist = Playlist::new(config.args.playlist.clone()).filter(|_| wpt.is_none());
+        let mut restored_urls = updater::take_restored_session();
+        let initial_url = match (&wpt, &playlist) {
+            (Some(wpt), _) => {
+                if config.args.url.is_some() || !config.args.playlist.is_empty() {
+                    log::warn!("Ignoring url and playlist, the first web platform test is loaded");
+                }
+                restored_urls.clear();
+                Some(wpt.current_url().clone())
+            }
+            (None, Some(playlist)) => {
+                if config.args.url.is_some() {
+                    log::warn!("Ignoring url, the playlist decides which page is shown");
+                }
+                Some(playlist.current_url().clone())
+            }
+            // The current tab of the session comes first.
+            (None, None) if !restored_urls.is_empty() => Some(restored_urls.remove(0)),
+            (None, None) => config.args.url.clone(),
+        };
+        let homepage = ServoUrl::from_url(config.homepage.clone());
         let with_panel = !config.args.no_panel;
+        let chrome = config
+            .args
+            .chrome
+            .as_ref()
+            .and_then(|chrome| match chrome.to_url() {
+                Ok(url) => Some((ServoUrl::from_url(url), config.args.chrome_height)),
+                Err(e) => {
+                    log::error!("Failed to load the chrome page, using the built-in panel: {e}");
+                    None
+                }
+            });
         let window_settings = config.args.window_attributes.clone();
+        let transparent = window_settings.transparent;
+        let new_window_attributes = WinitWindow::default_attributes()
+            .with_transparent(transparent)
+            .with_decorations(window_settings.decorations)
+            .with_theme(window_settings.preferred_theme);
         let user_agent: Cow<'static, str> = config
             .args
             .user_agent
             .clone()
             .unwrap_or_else(|| default_user_agent_string().to_string())
             .into();
-        let init_script = config.args.init_script.clone();
+        // Site overrides run first, so the init script sees the patched pages.
+        let init_script = match (
+            site_overrides_script(&config.args.site_overrides),
+            config.args.init_script.clone(),
+        ) {
+            (Some(overrides), Some(script)) => Some(format!("{overrides}\n{script}")),
+            (overrides, script) => overrides.or(script),
+        };
+        let init_script = match (init_script, &wpt) {
+            (Some(script), Some(_)) => Some(format!("{script}\n{WPT_HOOK_SCRIPT}")),
+            (None, Some(_)) => Some(WPT_HOOK_SCRIPT.to_owned()),
+            (script, None) => script,
+        };
         let zoom_level = config.args.zoom_level;
+        let rendering_settings = config.args.rendering_settings;
+        let mouse_gestures = config.args.mouse_gestures;
+        let mut saved_settings = SavedSettings::load(config.args.config_dir.as_deref());
+        // The command line turns HTTPS-only mode on for this session without saving it.
+        saved_settings.https_only |= config.args.https_only;
+        let navigation_policy = config.args.navigation_policy.clone();
+        let new_webview_policy = config.args.new_webview_policy;
+        let block_popups = config.args.block_popups;
+        let search_engines = config.args.search_engines.clone();
+        let suggestions_fetcher = config
+            .args
+            .search_suggestions
+            .then(|| SuggestionsFetcher::new(proxy.clone()))
+            .flatten();
+        let captive_portal_probe = config.args.captive_portal_probe.clone();
+        let open_captive_portal = config.args.open_captive_portal;
+        let event_loop_proxy = proxy.clone();
+        let keybindings = config.args.keybindings.clone();
+        let idle_reset = config.args.idle_reset;
+        let chrome_trace = config.args.profiler_chrome_trace.clone();
+        let log_settings = LogSettings {
+            file_dir: config
+                .args
+                .log_file
+                .then(|| config.args.config_dir.clone())
+                .flatten(),
+            json: config.args.log_json,
+        };
+        if let Some((endpoint, token)) = &config.args.management {
+            management::poll_commands(
+                endpoint.clone(),
+                token.clone(),
+                config.args.management_interval,
+                proxy.clone(),
+            );
+        }
+        if let Some((feed, public_key)) = &config.args.update {
+            match minisign_verify::PublicKey::from_base64(public_key) {
+                Ok(public_key) => {
+                    updater::check_for_updates(feed.clone(), public_key, proxy.clone())
+                }
+                Err(e) => log::error!("Invalid update public key: {e}"),
+            }
+        }
 
         config.init();
         // Reserving a namespace to create TopLevelBrowsingContextId.
         PipelineNamespace::install(PipelineNamespaceId(0));
-        let (mut window, rendering_context) = Window::new(evl, window_settings);
+        let (mut window, rendering_context) = Window::new(evl, window_settings, rendering_settings);
+        startup.phase("window");
 
         let event_loop_waker = Box::new(Waker(proxy));
         let opts = opts::get();
+        let popup_exceptions = PopupExceptions::load(opts.config_dir.as_deref());
 
         // Set Stylo flags
         style::context::DEFAULT_DISABLE_STYLE_SHARING_CACHE
@@ -149,11 +469,16 @@ impl Verso {
         let webrender_gl = rendering_context.gl.clone();
 
         // Create profiler threads
-        let time_profiler_sender = profile::time::Profiler::create(
+        let time_profiler_sender = trace::record_traces(profile::time::Profiler::create(
             &opts.time_profiling,
             opts.time_profiler_trace_path.clone(),
-        );
-        let mem_profiler_sender = profile::mem::Profiler::create(opts.mem_profiler_period);
+        ));
+        if chrome_trace.is_some() {
+            trace::start();
+        }
+        let mem_profiler_sender =
+            memory::record_reporters(profile::mem::Profiler::create(opts.mem_profiler_period));
+        startup.phase("profilers");
 
         // Create compositor and embedder channels
         let (compositor_sender, compositor_receiver) = {
@@ -189,6 +514,14 @@ impl Verso {
             )
         };
 
+        // The previous process may still be exiting, and Servo gives up if it can't listen.
+        if std::env::var_os(RESTARTED_VAR).is_some() {
+            let devtools_port = pref!(devtools_server_enabled)
+                .then(|| pref!(devtools_server_port) as u16)
+                .filter(|port| *port != 0);
+            wait_for_ports(devtools_port.into_iter().chain(opts.webdriver_port));
+        }
+
         // Create dev tools thread
         let devtools_sender = if pref!(devtools_server_enabled) {
             Some(devtools::start_server(
@@ -205,7 +538,11 @@ impl Verso {
             debug_flags.set(DebugFlags::PROFILER_DBG, opts.debug.webrender_stats);
 
             let render_notifier = Box::new(RenderNotifier::new(compositor_sender.clone()));
-            let clear_color = ColorF::new(0., 0., 0., 0.);
+            let clear_color = if transparent {
+                ColorF::new(0., 0., 0., 0.)
+            } else {
+                ColorF::WHITE
+            };
             create_webrender_instance(
                 webrender_gl.clone(),
                 render_notifier,
@@ -226,6 +563,14 @@ impl Verso {
                     enable_subpixel_aa: pref!(gfx_subpixel_text_antialiasing_enabled),
                     allow_texture_swizzling: pref!(gfx_texture_swizzling_enabled),
                     clear_color,
+                    // Let WebRender track the dirty region of each frame, so we only need to
+                    // repaint and present the damaged area when the surface supports it.
+                    compositor_config: CompositorConfig::Draw {
+                        max_partial_present_rects: 1,
+                        draw_previous_partial_present_regions: false,
+                        partial_present: None,
+                    },
+                    size_of_op: memory::USABLE_SIZE,
                     ..Default::default()
                 },
                 None,
@@ -233,8 +578,33 @@ impl Verso {
             .expect("Unable to initialize webrender!")
         };
         let webrender_api = webrender_api_sender.create_api();
+
+        // WebRender lives on this thread, so its reports are collected by the event loop.
+        if memory::USABLE_SIZE.is_some() {
+            let (reporter_sender, reporter_receiver) = ipc::channel().expect("ipc channel failure");
+            let proxy = event_loop_proxy.clone();
+            ROUTER.add_typed_route(
+                reporter_receiver,
+                Box::new(move |request| match request {
+                    Ok(ReporterRequest { reports_channel }) => {
+                        if let Err(e) = proxy.send_event(
+                            EventLoopProxyMessage::CollectMemoryReports(reports_channel),
+                        ) {
+                            log::error!("Failed to send memory report request to Verso: {e}");
+                        }
+                    }
+                    Err(e) => log::error!("Failed to receive memory report request: {e}"),
+                }),
+            );
+            mem_profiler_sender.send(ProfilerMsg::RegisterReporter(
+                "webrender".to_owned(),
+                Reporter(reporter_sender),
+            ));
+        }
+
         let webrender_document =
             webrender_api.add_document_with_id(window.size(), u64::from(window.id()) as u32);
+        startup.phase("webrender");
 
         // Initialize js engine if it's single process mode
         let js_engine_setup = if !opts.multiprocess {
@@ -242,6 +612,7 @@ impl Verso {
         } else {
             None
         };
+        startup.phase("js_engine");
 
         let (external_image_handlers, external_images) = WebrenderExternalImageHandlers::new();
         let mut external_image_handlers = Box::new(external_image_handlers);
@@ -273,12 +644,25 @@ impl Verso {
             WebrenderImageHandlerType::WebGPU,
         );
 
-        // TODO enable gl media player
+        // Set webrender external image handler for GL media player frames. The dummy media
+        // backend doesn't render any video yet, but backends which do can upload frames to the
+        // shared GL context and hand them to WebRender without copying them.
+        let gl_context = rendering_context.media_gl_context();
+        let glplayer_threads = match gl_context {
+            GlContext::Unknown => None,
+            _ => {
+                let (glplayer_threads, image_handler) =
+                    GLPlayerThreads::new(external_images.clone());
+                external_image_handlers
+                    .set_handler(image_handler, WebrenderImageHandlerType::Media);
+                Some(glplayer_threads)
+            }
+        };
         let glplayer_context = WindowGLContext {
-            gl_context: GlContext::Unknown,
-            gl_api: GlApi::None,
-            native_display: NativeDisplay::Unknown,
-            glplayer_chan: None,
+            gl_context,
+            gl_api: rendering_context.media_gl_api(),
+            native_display: rendering_context.media_native_display(),
+            glplayer_chan: glplayer_threads.as_ref().map(GLPlayerThreads::pipeline),
         };
 
         webrender.set_external_image_handler(external_image_handlers);
@@ -300,12 +684,14 @@ impl Verso {
                 opts.ignore_certificate_errors,
                 Arc::new(protocols),
             );
+        startup.phase("resource_threads");
 
         // Create font cache thread
         let system_font_service = Arc::new(
             SystemFontService::spawn(compositor_sender.cross_process_compositor_api.clone())
                 .to_proxy(),
         );
+        startup.phase("font_service");
 
         // Create canvas thread
         let (canvas_create_sender, canvas_ipc_sender) = CanvasPaintThread::start(
@@ -313,6 +699,7 @@ impl Verso {
             system_font_service.clone(),
             public_resource_threads.clone(),
         );
+        startup.phase("canvas");
 
         // Create layout factory
         let layout_factory = Arc::new(layout_thread_2020::LayoutFactoryImpl());
@@ -330,7 +717,7 @@ impl Verso {
             webrender_api_sender,
             webxr_registry: None,
             webgl_threads: None,
-            glplayer_threads: None,
+            glplayer_threads,
             player_context: glplayer_context,
             user_agent,
             webrender_external_images: external_images,
@@ -356,6 +743,7 @@ impl Verso {
                 canvas_create_sender,
                 canvas_ipc_sender,
             );
+        startup.phase("constellation");
 
         // Create webdriver thread
         if let Some(port) = opts.webdriver_port {
@@ -383,21 +771,47 @@ impl Verso {
             opts.exit_after_load,
             opts.debug.convert_mouse_to_touch,
         );
+        startup.phase("compositor");
 
         if let Some(zoom_level) = zoom_level {
             compositor.on_zoom_window_event(zoom_level, &window);
         }
 
+        window.https_only = saved_settings.https_only;
+        window.navigation_policy = navigation_policy.clone();
+        window.new_webview_policy = new_webview_policy;
+        window.block_popups = block_popups;
+        window.popup_exceptions = popup_exceptions.clone();
+        window.search_engines = search_engines.clone();
+        window.homepage = homepage.clone();
+        // Start with the home page instead of a URL the policy blocks.
+        let initial_url = initial_url.filter(|url| {
+            let url = ServoUrl::from_url(url.clone());
+            let allowed = window.allows_navigation(&url);
+            if !allowed {
+                window.open_blocked_externally(&constellation_sender, None, &url);
+            }
+            allowed
+        });
         if with_panel {
-            window.create_panel(&constellation_sender, initial_url);
-        } else if let Some(initial_url) = initial_url {
-            window.create_tab(&constellation_sender, initial_url.into());
+            window.create_panel(&constellation_sender, initial_url, chrome.clone());
+            if let Some(panel) = &mut window.panel {
+                panel.restored_urls = restored_urls.into_iter().map(ServoUrl::from_url).collect();
+            }
+        } else {
+            let initial_url = initial_url.map_or_else(|| homepage.clone(), ServoUrl::from_url);
+            window.create_tab(&constellation_sender, initial_url);
         }
 
-        window.set_init_script(init_script);
+        window.set_init_script(init_script.clone());
+        window.set_mouse_gestures(mouse_gestures);
+        startup.phase("first_webview");
 
+        let clipboard = Clipboard::new(&window.window);
+        let main_window = window.id();
         let mut windows = HashMap::new();
         windows.insert(window.id(), (window, webrender_document));
+        let known_windows = windows.keys().copied().collect();
 
         // Create Verso instance
         let verso = Verso {
@@ -407,15 +821,63 @@ impl Verso {
             to_controller_sender,
             embedder_receiver,
             _js_engine_setup: js_engine_setup,
-            clipboard: Clipboard::new().ok(),
+            clipboard,
+            devtools_server: None,
+            new_window_attributes,
+            mouse_gestures,
+            chrome,
+            saved_settings,
+            navigation_policy,
+            new_webview_policy,
+            block_popups,
+            popup_exceptions,
+            init_script,
+            search_engines,
+            suggestions_fetcher,
+            homepage,
+            event_loop_proxy,
+            captive_portal_probe,
+            open_captive_portal,
+            connectivity: None,
+            keybindings,
+            last_webview_audit: Instant::now(),
+            detached_webviews: HashSet::new(),
+            lifecycle_listener: false,
+            page_listener: false,
+            load_progress_listener: false,
+            text_input_listener: false,
+            connectivity_listener: false,
+            new_webview_requests: None,
+            known_windows,
+            public_ids: PublicIds::default(),
+            idle_reset,
+            last_input: Instant::now(),
+            input_since_start: false,
+            idle_timeout: None,
+            idle: false,
+            started_at: startup.started_at(),
+            restart_on_exit: false,
+            restart_session: None,
+            pending_update: None,
+            shutdown_deadline: None,
+            playlist,
+            wpt,
+            main_window,
+            repl: None,
+            chrome_trace,
+            startup_report: startup.finish(),
+            _crash_reporter: crash_reporter,
+            crash_reports,
         };
 
-        verso.setup_logging();
+        verso.setup_logging(log_settings);
+        verso.check_connectivity();
         verso
     }
 
     /// Handle Winit window events. The strategy to handle event are different between platforms
     /// because the order of events might be different.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, level = "trace"))]
     pub fn handle_window_event(
         &mut self,
         event_loop: &ActiveEventLoop,
@@ -452,6 +914,15 @@ impl Verso {
     fn handle_winit_window_event(&mut self, window_id: WindowId, event: WindowEvent) -> bool {
         log::trace!("Verso is handling Winit event: {event:?}");
 
+        if is_user_input(&event) {
+            self.last_input = Instant::now();
+            self.input_since_start = true;
+            if self.idle {
+                self.idle = false;
+                Self::send_idle_state(&self.to_controller_sender, IdleState::Active);
+            }
+        }
+
         let Some(compositor) = &mut self.compositor else {
             return false;
         };
@@ -473,10 +944,25 @@ impl Verso {
                     }
                 }
             }
-            // self.windows.remove(&window_id);
-            compositor.maybe_start_shutting_down();
+            if self.windows.len() > 1 {
+                // The compositor removes the window once its webviews are closed.
+                window.close(&self.constellation_sender);
+            } else {
+                self.request_shutdown(DEFAULT_SHUTDOWN_TIMEOUT);
+            }
         } else {
-            window.handle_winit_window_event(&self.constellation_sender, compositor, &event);
+            window.handle_winit_window_event(
+                &self.constellation_sender,
+                compositor,
+                &self.keybindings,
+                &event,
+            );
+            if matches!(
+                event,
+                WindowEvent::Resized(_) | WindowEvent::Moved(_) | WindowEvent::Occluded(_)
+            ) {
+                window.notify_window_state_changed(&self.to_controller_sender);
+            }
             return window.resizing;
         }
 
@@ -484,6 +970,7 @@ impl Verso {
     }
 
     /// Handle message came from Servo.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, level = "trace"))]
     pub fn handle_servo_messages(&mut self, evl: &ActiveEventLoop) {
         if self.compositor.is_none() {
             log::error!("Verso shouldn't be handling messages after compositor has shut down");
@@ -492,6 +979,8 @@ impl Verso {
         let compositor = self.compositor.as_mut().unwrap();
 
         let mut shutdown = false;
+        // Shutting down needs all of Verso, so it's requested once the compositor isn't borrowed.
+        let mut shutdown_requested = false;
 
         // Handle Compositor's messages first
         log::trace!("Verso is handling Compositor messages");
@@ -510,23 +999,51 @@ impl Verso {
 
         match compositor.shutdown_state {
             ShutdownState::NotShuttingDown => {
+                let mut new_windows = 0;
+                let mut opened_webviews = Vec::new();
+                let mut popup_exceptions_changed = false;
+                let main_tab = self.main_tab();
                 for msg in messages {
+                    if let Some(playlist) = &mut self.playlist {
+                        playlist.handle_message(main_tab, &msg);
+                    }
+                    if let Some(wpt) = &mut self.wpt {
+                        // Results reported by the hook script aren't real prompts.
+                        if wpt.handle_message(main_tab, &msg) {
+                            continue;
+                        }
+                    }
+                    let event = Self::lifecycle_event(&self.windows, &mut self.public_ids, &msg);
+                    if let Some(event) = event.filter(|_| self.lifecycle_listener) {
+                        Self::send_lifecycle_event(&self.to_controller_sender, event);
+                    }
+                    if self.load_progress_listener {
+                        if let Some(progress) =
+                            Self::load_progress(&self.windows, &mut self.public_ids, &msg)
+                        {
+                            Self::send_load_progress(&self.to_controller_sender, progress);
+                        }
+                    }
+                    if self.text_input_listener {
+                        if let Some(change) =
+                            Self::text_input_change(&self.windows, &mut self.public_ids, &msg)
+                        {
+                            Self::send_text_input_change(&self.to_controller_sender, change);
+                        }
+                    }
                     if let Some(webview_id) = Self::get_embedder_message_webview_id(&msg) {
-                        for (window, document) in self.windows.values_mut() {
+                        for (window, _) in self.windows.values_mut() {
                             if window.has_webview(*webview_id) {
                                 if window.handle_servo_message(
                                     *webview_id,
                                     msg,
                                     &self.constellation_sender,
                                     &self.to_controller_sender,
+                                    &mut self.new_webview_requests,
                                     self.clipboard.as_mut(),
                                     compositor,
                                 ) {
-                                    let mut window = Window::new_with_compositor(evl, compositor);
-                                    window.create_panel(&self.constellation_sender, None);
-                                    let webrender_document = *document;
-                                    self.windows
-                                        .insert(window.id(), (window, webrender_document));
+                                    new_windows += 1;
                                 }
                                 break;
                             }
@@ -546,17 +1063,222 @@ impl Verso {
                                     log::error!("Failed to send RequestDevtoolsConnection response back: {err}");
                                 }
                             }
+                            EmbedderMsg::OnDevtoolsStarted(port, token) => match port {
+                                Ok(port) => {
+                                    log::info!("Devtools server is listening on port {port}");
+                                    self.devtools_server = Some((port, token));
+                                    Self::send_devtools_started(
+                                        &self.to_controller_sender,
+                                        &self.devtools_server,
+                                    );
+                                }
+                                Err(()) => log::error!("Failed to start devtools server"),
+                            },
                             e => {
                                 log::trace!("Verso Window isn't supporting handling this message yet: {e:?}")
                             }
                         }
                     }
                 }
+
+                for (window, _) in self.windows.values_mut() {
+                    // Navigating or switching tabs may have changed the current page.
+                    window.notify_security_state_changed(&self.to_controller_sender);
+                    let window_id = window.id();
+                    if let Some(page) = window.update_current_page().filter(|_| self.page_listener)
+                    {
+                        let info = Self::page_info(&mut self.public_ids, window_id, page);
+                        Self::send_page_changed(&self.to_controller_sender, info);
+                    }
+                    Self::send_blocked_navigations(&self.to_controller_sender, window);
+                    window.update_stats_overlay(compositor);
+                    // Windows requested by keyboard shortcuts.
+                    if std::mem::take(&mut window.new_window_requested) {
+                        new_windows += 1;
+                    }
+                    // Windows requested by pages for their new webviews.
+                    opened_webviews.append(&mut window.opened_window_requests);
+                    for origin in window.allowed_popup_origins.drain(..) {
+                        self.popup_exceptions.add(origin);
+                        popup_exceptions_changed = true;
+                    }
+                    if let Some(query) = window.suggestions_requested.take() {
+                        let url = window.search_engines.suggestions_url(&query);
+                        if let (Some(url), Some(fetcher)) = (url, &self.suggestions_fetcher) {
+                            fetcher.fetch(url, window.id(), query);
+                        }
+                    }
+                }
+                if popup_exceptions_changed {
+                    for (window, _) in self.windows.values_mut() {
+                        window.popup_exceptions = self.popup_exceptions.clone();
+                    }
+                }
+                Self::sync_https_only(&mut self.windows, &mut self.saved_settings);
+                // All windows have to share the same document for now. The WebRender renderer
+                // only draws a single document, and image and font keys from content are scoped
+                // to this API's namespace, so a second document or renderer couldn't display the
+                // same pipelines.
+                let webrender_document =
+                    self.windows.values().next().map(|(_, document)| *document);
+                if let Some(webrender_document) = webrender_document {
+                    let requests = (0..new_windows)
+                        .map(|_| None)
+                        .chain(opened_webviews.into_iter().map(Some));
+                    for response_sender in requests {
+                        let mut window = Window::new_with_compositor(
+                            evl,
+                            self.new_window_attributes.clone(),
+                            compositor,
+                        );
+                        window.set_mouse_gestures(self.mouse_gestures);
+                        window.https_only = self.saved_settings.https_only;
+                        window.navigation_policy = self.navigation_policy.clone();
+                        window.new_webview_policy = self.new_webview_policy;
+                        window.block_popups = self.block_popups;
+                        window.popup_exceptions = self.popup_exceptions.clone();
+                        window.set_init_script(self.init_script.clone());
+                        window.search_engines = self.search_engines.clone();
+                        window.homepage = self.homepage.clone();
+                        if let Some(response_sender) = response_sender {
+                            // Added before the panel so it's shown instead of the home page.
+                            let webview_id = WebViewId::new();
+                            window.add_tab(&self.constellation_sender, webview_id);
+                            if let Err(error) = response_sender.send(Some(webview_id)) {
+                                log::error!(
+                                    "Verso failed to respond to AllowOpeningWebView: {error}"
+                                );
+                            }
+                        }
+                        window.create_panel(&self.constellation_sender, None, self.chrome.clone());
+                        self.windows
+                            .insert(window.id(), (window, webrender_document));
+                    }
+                }
+
+                for window_id in self.windows.keys() {
+                    if !self.known_windows.contains(window_id) && self.lifecycle_listener {
+                        Self::send_lifecycle_event(
+                            &self.to_controller_sender,
+                            LifecycleEvent::WindowCreated {
+                                window_id: self.public_ids.window(*window_id),
+                            },
+                        );
+                    }
+                }
+                for window_id in &self.known_windows {
+                    if !self.windows.contains_key(window_id) {
+                        let window_id = self.public_ids.remove_window(*window_id);
+                        if self.lifecycle_listener {
+                            Self::send_lifecycle_event(
+                                &self.to_controller_sender,
+                                LifecycleEvent::WindowDestroyed { window_id },
+                            );
+                        }
+                    }
+                }
+                self.known_windows = self.windows.keys().copied().collect();
+
+                if self.last_webview_audit.elapsed() >= WEBVIEW_AUDIT_INTERVAL {
+                    self.last_webview_audit = Instant::now();
+                    // A webview which is detached in two audits in a row is leaked, e.g. when a
+                    // window failed to take it. One-off detachments are webviews in the middle of
+                    // being created or closed.
+                    let detached: HashSet<WebViewId> = compositor
+                        .detached_webviews(&self.windows)
+                        .into_iter()
+                        .collect();
+                    for webview_id in detached.intersection(&self.detached_webviews) {
+                        log::warn!("Closing webview {webview_id} which isn't in any window");
+                        send_to_constellation(
+                            &self.constellation_sender,
+                            ConstellationMsg::CloseWebView(*webview_id),
+                        );
+                    }
+                    self.detached_webviews = detached
+                        .difference(&self.detached_webviews)
+                        .copied()
+                        .collect();
+                }
+
+                if !self.idle
+                    && self
+                        .idle_timeout
+                        .is_some_and(|timeout| self.last_input.elapsed() >= timeout)
+                {
+                    self.idle = true;
+                    Self::send_idle_state(&self.to_controller_sender, IdleState::Idle);
+                }
+
+                if let (Some(playlist), Some(tab_id)) = (&mut self.playlist, main_tab) {
+                    if let Some(url) = playlist.poll() {
+                        send_to_constellation(
+                            &self.constellation_sender,
+                            ConstellationMsg::LoadUrl(tab_id, ServoUrl::from_url(url)),
+                        );
+                    }
+                }
+
+                if let (Some(wpt), Some(tab_id)) = (&mut self.wpt, main_tab) {
+                    match wpt.poll() {
+                        Some(WptStep::Load(url)) => send_to_constellation(
+                            &self.constellation_sender,
+                            ConstellationMsg::LoadUrl(tab_id, ServoUrl::from_url(url)),
+                        ),
+                        Some(WptStep::Finished(report)) => {
+                            println!("{}", serde_json::to_string(&report).unwrap());
+                            shutdown_requested = true;
+                        }
+                        None => {}
+                    }
+                }
+
+                if self.input_since_start
+                    && self
+                        .idle_reset
+                        .is_some_and(|timeout| self.last_input.elapsed() >= timeout)
+                {
+                    log::info!("Verso has been idle for too long, restarting with a fresh session");
+                    self.restart_on_exit = true;
+                    shutdown_requested = true;
+                }
+
+                // Restart into a downloaded update while nobody is using Verso.
+                let idle = self.last_input.elapsed() >= updater::RESTART_IDLE_TIME;
+                if let Some(update) = self.pending_update.take_if(|_| idle) {
+                    match updater::install(&update) {
+                        Ok(()) => {
+                            log::info!("Update installed, restarting Verso");
+                            self.restart_session =
+                                updater::save_session(&Self::session_urls(&self.windows))
+                                    .inspect_err(|e| log::error!("Failed to save the session: {e}"))
+                                    .ok();
+                            self.restart_on_exit = true;
+                            shutdown_requested = true;
+                        }
+                        Err(e) => log::error!("Failed to install the update: {e}"),
+                    }
+                }
+
+                if CONSTELLATION_LOST.load(Ordering::Relaxed) {
+                    self.recover_from_lost_constellation();
+                }
             }
             ShutdownState::FinishedShuttingDown => {
                 log::error!("Verso shouldn't be handling messages after compositor has shut down");
             }
-            ShutdownState::ShuttingDown => {}
+            ShutdownState::ShuttingDown => {
+                if self
+                    .shutdown_deadline
+                    .is_some_and(|deadline| Instant::now() >= deadline)
+                {
+                    log::warn!("The constellation didn't shut down in time, exiting anyway");
+                    if self.restart_on_exit {
+                        restart(self.restart_session.as_deref());
+                    }
+                    std::process::exit(1);
+                }
+            }
         }
 
         if compositor.shutdown_state != ShutdownState::FinishedShuttingDown {
@@ -567,10 +1289,12 @@ impl Verso {
         }
 
         // Check if Verso need to start shutting down.
-        if self.windows.is_empty() {
-            self.compositor
-                .as_mut()
-                .map(IOCompositor::maybe_start_shutting_down);
+        let quit_requested = self
+            .windows
+            .values_mut()
+            .any(|(window, _)| std::mem::take(&mut window.quit_requested));
+        if self.windows.is_empty() || quit_requested || shutdown_requested {
+            self.request_shutdown(DEFAULT_SHUTDOWN_TIMEOUT);
         }
 
         // Check compositor status and set control flow.
@@ -579,11 +1303,33 @@ impl Verso {
             if let Some(mut compositor) = self.compositor.take() {
                 IOCompositor::deinit(&mut compositor)
             }
+            if let Some(path) = &self.chrome_trace {
+                if let Err(e) = trace::stop(path) {
+                    log::error!("Failed to write the profiler trace: {e}");
+                }
+            }
+            #[cfg(feature = "tracing")]
+            crate::telemetry::shutdown();
+            if self.restart_on_exit {
+                restart(self.restart_session.as_deref());
+            }
             evl.exit();
-        } else if self.is_animating() {
-            evl.set_control_flow(ControlFlow::Poll);
         } else {
-            evl.set_control_flow(ControlFlow::Wait);
+            // Wake up when the user becomes idle or the playlist moves on, even without any
+            // event.
+            let timer_deadline = self.timer_deadline();
+            if self.is_animating() {
+                // Wake up at the next display refresh instead of polling, in case no new frame
+                // arrives to drive the animation forward.
+                let next_frame = Instant::now() + self.frame_interval();
+                evl.set_control_flow(ControlFlow::WaitUntil(
+                    timer_deadline.map_or(next_frame, |deadline| deadline.min(next_frame)),
+                ));
+            } else if let Some(timer_deadline) = timer_deadline {
+                evl.set_control_flow(ControlFlow::WaitUntil(timer_deadline));
+            } else {
+                evl.set_control_flow(ControlFlow::Wait);
+            }
         }
     }
 
@@ -640,14 +1386,37 @@ impl Verso {
         }
     }
 
+    /// Shut down gracefully: close every webview and let the constellation shut down, which
+    /// saves the browsing data of Servo's resource threads. Verso exits anyway if the constellation
+    /// doesn't acknowledge within `timeout`.
+    pub fn request_shutdown(&mut self, timeout: Duration) {
+        let Some(compositor) = &mut self.compositor else {
+            return;
+        };
+        if compositor.shutdown_state != ShutdownState::NotShuttingDown {
+            return;
+        }
+        log::info!("Verso is shutting down");
+        for (window, _) in self.windows.values_mut() {
+            // Paused pages can't shut down.
+            window.resume_all_webviews();
+            for webview_id in window.webview_ids() {
+                send_to_constellation(
+                    &self.constellation_sender,
+                    ConstellationMsg::CloseWebView(webview_id),
+                );
+            }
+        }
+        self.shutdown_deadline = Some(Instant::now() + timeout);
+        compositor.maybe_start_shutting_down();
+    }
+
     /// Handle message came from webview controller.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, level = "trace"))]
     pub fn handle_incoming_webview_message(&mut self, message: ToVersoMessage) {
         match message {
-            ToVersoMessage::Exit => {
-                if let Some(compositor) = &mut self.compositor {
-                    compositor.maybe_start_shutting_down();
-                }
-            }
+            ToVersoMessage::Exit => self.request_shutdown(DEFAULT_SHUTDOWN_TIMEOUT),
+            ToVersoMessage::Shutdown(timeout) => self.request_shutdown(timeout),
             ToVersoMessage::ListenToOnCloseRequested => {
                 if let Some(window) = self.first_window_mut() {
                     window.event_listeners.on_close_requested = true;
@@ -655,9 +1424,19 @@ impl Verso {
             }
             ToVersoMessage::NavigateTo(to_url) => {
                 if let Some(webview_id) = self.first_webview_id() {
+                    let mut url = ServoUrl::from_url(to_url);
+                    // Borrow the window alone so the controller sender stays available.
+                    if let Some((window, _)) = self.windows.values_mut().next() {
+                        url = window.upgrade_insecure_url(url);
+                        if !window.allows_navigation(&url) {
+                            window.open_blocked_externally(&self.constellation_sender, None, &url);
+                            Self::send_blocked_navigations(&self.to_controller_sender, window);
+                            return;
+                        }
+                    }
                     send_to_constellation(
                         &self.constellation_sender,
-                        ConstellationMsg::LoadUrl(webview_id, ServoUrl::from_url(to_url)),
+                        ConstellationMsg::LoadUrl(webview_id, url),
                     );
                 }
             }
@@ -675,8 +1454,43 @@ impl Verso {
                     ),
                 );
             }
+            ToVersoMessage::ListenToNewWebViewRequests => {
+                self.new_webview_requests.get_or_insert_with(HashMap::new);
+            }
+            ToVersoMessage::NewWebViewRequestResponse(id, policy) => {
+                let Some((opener_id, response_sender)) = self
+                    .new_webview_requests
+                    .as_mut()
+                    .and_then(|requests| requests.remove(&id))
+                else {
+                    return;
+                };
+                // The opener may have moved to another window or closed in the meantime.
+                match self
+                    .windows
+                    .values_mut()
+                    .find(|(window, _)| window.has_webview(opener_id))
+                {
+                    Some((window, _)) => window.open_new_webview(
+                        &self.constellation_sender,
+                        opener_id,
+                        policy,
+                        response_sender,
+                    ),
+                    None => {
+                        if let Err(error) = response_sender.send(None) {
+                            log::error!("Verso failed to respond to AllowOpeningWebView: {error}");
+                        }
+                    }
+                }
+            }
             ToVersoMessage::ExecuteScript(js) => {
                 if let Some(webview_id) = self.first_webview_id() {
+                    // Verso would wait on the script until the page is resumed.
+                    if self.is_paused(webview_id) {
+                        log::error!("Verso can't run scripts in a paused webview");
+                        return;
+                    }
                     let _ = execute_script(&self.constellation_sender, &webview_id, js);
                 }
             }
@@ -751,6 +1565,32 @@ impl Verso {
                     window.window.set_visible(visible);
                 }
             }
+            ToVersoMessage::SetAlwaysOnTop(always_on_top) => {
+                if let Some(window) = self.first_window() {
+                    window.window.set_window_level(if always_on_top {
+                        WindowLevel::AlwaysOnTop
+                    } else {
+                        WindowLevel::Normal
+                    });
+                }
+            }
+            ToVersoMessage::SetTitle(title) => {
+                if let Some(window) = self.first_window() {
+                    window.window.set_title(&title);
+                }
+            }
+            ToVersoMessage::SetColorScheme(color_scheme) => {
+                let theme = color_scheme.map(winit_theme);
+                self.new_window_attributes.preferred_theme = theme;
+                for (window, _) in self.windows.values() {
+                    window.window.set_theme(theme);
+                }
+            }
+            ToVersoMessage::ListenToWindowStateChanged => {
+                if let Some(window) = self.first_window_mut() {
+                    window.event_listeners.on_window_state_changed = Some(None);
+                }
+            }
             ToVersoMessage::StartDragging => {
                 if let Some(window) = self.first_window() {
                     let _ = window.window.drag_window();
@@ -854,10 +1694,813 @@ impl Verso {
                     }
                 }
             }
+            ToVersoMessage::GetHistoryList => {
+                let tab = self
+                    .first_window()
+                    .and_then(|window| window.tab_manager.current_tab());
+                // The controller waits for a response, so it gets an empty list without a tab.
+                let history_list = match tab {
+                    Some(tab) => HistoryList {
+                        entries: tab
+                            .history()
+                            .list
+                            .iter()
+                            .map(|url| HistoryEntry {
+                                url: url.as_url().clone(),
+                                title: tab.title(url).map(str::to_owned),
+                            })
+                            .collect(),
+                        current_index: tab.history().current_idx,
+                    },
+                    None => {
+                        log::warn!("Verso has no tab to get the history list of");
+                        HistoryList {
+                            entries: Vec::new(),
+                            current_index: 0,
+                        }
+                    }
+                };
+                if let Err(error) = self
+                    .to_controller_sender
+                    .as_ref()
+                    .unwrap()
+                    .send(ToControllerMessage::GetHistoryListResponse(history_list))
+                {
+                    log::error!(
+                        "Verso failed to send GetHistoryListResponse to controller: {error}"
+                    )
+                }
+            }
+            ToVersoMessage::GoToHistoryIndex(index) => {
+                let Some(tab) = self
+                    .first_window()
+                    .and_then(|window| window.tab_manager.current_tab())
+                else {
+                    log::error!("Verso has no tab to go to history index {index} in");
+                    return;
+                };
+                let current_idx = tab.history().current_idx;
+                let direction = if index >= tab.history().list.len() {
+                    log::error!("History index {index} is out of bounds");
+                    return;
+                } else if index < current_idx {
+                    TraversalDirection::Back(current_idx - index)
+                } else if index > current_idx {
+                    TraversalDirection::Forward(index - current_idx)
+                } else {
+                    return;
+                };
+                send_to_constellation(
+                    &self.constellation_sender,
+                    ConstellationMsg::TraverseHistory(tab.id(), direction),
+                );
+            }
+            ToVersoMessage::SetDragRegions(regions) => {
+                if let Some(window) = self.first_window_mut() {
+                    window.drag_regions = regions;
+                }
+            }
+            ToVersoMessage::CheckConnectivity => {
+                self.check_connectivity();
+            }
+            ToVersoMessage::GetSecurityInfo => {
+                if let Some(window) = self.first_window() {
+                    if let Err(error) = self.to_controller_sender.as_ref().unwrap().send(
+                        ToControllerMessage::GetSecurityInfoResponse(window.security_info()),
+                    ) {
+                        log::error!(
+                            "Verso failed to send GetSecurityInfoResponse to controller: {error}"
+                        )
+                    }
+                }
+            }
+            ToVersoMessage::ListenToSecurityStateChanged => {
+                if let Some(window) = self.first_window_mut() {
+                    window.event_listeners.on_security_state_changed = Some(None);
+                }
+            }
+            ToVersoMessage::ListenToLifecycleEvents => {
+                if !self.lifecycle_listener {
+                    self.lifecycle_listener = true;
+                    // Report what exists already, the controller can't know the initial ids.
+                    for (window, _) in self.windows.values() {
+                        Self::send_lifecycle_event(
+                            &self.to_controller_sender,
+                            LifecycleEvent::WindowCreated {
+                                window_id: self.public_ids.window(window.id()),
+                            },
+                        );
+                        for webview_id in window.webview_ids() {
+                            let event = Self::webview_created_event(
+                                window,
+                                &mut self.public_ids,
+                                webview_id,
+                            );
+                            if let Some(event) = event {
+                                Self::send_lifecycle_event(&self.to_controller_sender, event);
+                            }
+                        }
+                    }
+                }
+            }
+            ToVersoMessage::ListenToPageChanged => {
+                if !self.page_listener {
+                    self.page_listener = true;
+                    // Report the pages shown already, later ones are reported when they change.
+                    for (window, _) in self.windows.values() {
+                        if let Some(page) = window.current_page() {
+                            let info = Self::page_info(&mut self.public_ids, window.id(), page);
+                            Self::send_page_changed(&self.to_controller_sender, info);
+                        }
+                    }
+                }
+            }
+            ToVersoMessage::ListenToLoadProgress => {
+                self.load_progress_listener = true;
+            }
+            ToVersoMessage::ListenToTextInput => {
+                self.text_input_listener = true;
+            }
+            ToVersoMessage::TextInput(event) => self.handle_text_input(event),
+            ToVersoMessage::ListenToCrashReports => {
+                let Some(to_controller_sender) = &self.to_controller_sender else {
+                    return;
+                };
+                // Each crash is only reported once.
+                for report in std::mem::take(&mut self.crash_reports) {
+                    crash::send_report(report, |report| {
+                        to_controller_sender
+                            .send(ToControllerMessage::OnCrashReported(report))
+                            .inspect_err(|error| {
+                                log::error!(
+                                    "Verso failed to send OnCrashReported to controller: {error}"
+                                )
+                            })
+                            .is_ok()
+                    });
+                }
+            }
+            ToVersoMessage::ListenToConnectivity => {
+                self.connectivity_listener = true;
+                // Checks may have finished before the controller started listening.
+                if let Some(state) = self.connectivity.clone() {
+                    self.send_connectivity_state(state);
+                }
+            }
+            ToVersoMessage::GetPipelineDiagnostics => {
+                if let Some(compositor) = &self.compositor {
+                    if let Err(error) = self.to_controller_sender.as_ref().unwrap().send(
+                        ToControllerMessage::GetPipelineDiagnosticsResponse(
+                            compositor.pipeline_diagnostics(&self.windows, |webview_id| {
+                                self.public_ids.webview(webview_id)
+                            }),
+                        ),
+                    ) {
+                        log::error!(
+                            "Verso failed to send GetPipelineDiagnosticsResponse to controller: {error}"
+                        )
+                    }
+                }
+            }
+            ToVersoMessage::GetKeyBindings => {
+                if let Some(to_controller_sender) = &self.to_controller_sender {
+                    if let Err(error) = to_controller_sender.send(
+                        ToControllerMessage::GetKeyBindingsResponse(self.keybindings.to_list()),
+                    ) {
+                        log::error!(
+                            "Verso failed to send GetKeyBindingsResponse to controller: {error}"
+                        )
+                    }
+                }
+            }
+            ToVersoMessage::SetKeyBindings(bindings) => {
+                if let Err(error) = self.keybindings.apply(bindings) {
+                    log::error!("Skipped invalid keybindings from controller: {error}");
+                }
+            }
+            ToVersoMessage::ListenToIdleStateChanged(timeout) => {
+                self.idle_timeout = Some(timeout);
+                self.idle = false;
+            }
+            ToVersoMessage::OpenRepl(webview_id) => {
+                let webview_id = match webview_id {
+                    Some(webview_id) => self.public_ids.find_webview(webview_id),
+                    None => self.first_webview_id(),
+                };
+                let Some(webview_id) = webview_id else {
+                    log::error!("Verso failed to open a console, the webview doesn't exist");
+                    return;
+                };
+                if let Some(to_controller_sender) = &self.to_controller_sender {
+                    self.repl = Repl::open(
+                        webview_id,
+                        self.constellation_sender.clone(),
+                        to_controller_sender.clone(),
+                    );
+                }
+            }
+            ToVersoMessage::EvaluateInRepl(code) => {
+                if !self.repl.as_ref().is_some_and(|repl| repl.evaluate(code)) {
+                    self.repl = None;
+                    if let Err(error) = self.to_controller_sender.as_ref().unwrap().send(
+                        ToControllerMessage::EvaluateInReplResponse(Err(
+                            "No console is open".to_owned()
+                        )),
+                    ) {
+                        log::error!(
+                            "Verso failed to send EvaluateInReplResponse to controller: {error}"
+                        )
+                    }
+                }
+            }
+            ToVersoMessage::CloseRepl => {
+                self.repl = None;
+            }
+            ToVersoMessage::PausePipeline(webview_id) => {
+                let Some(webview_id) = self.public_ids.find_webview(webview_id) else {
+                    log::error!("Verso failed to pause a webview, it doesn't exist");
+                    return;
+                };
+                for (window, _) in self.windows.values_mut() {
+                    if window.tab_manager.tab(webview_id).is_some() {
+                        window.pause_webview(&self.constellation_sender, webview_id);
+                    }
+                }
+            }
+            ToVersoMessage::ResumePipeline(webview_id) => {
+                if let Some(webview_id) = self.public_ids.find_webview(webview_id) {
+                    for (window, _) in self.windows.values_mut() {
+                        window.resume_webview(webview_id);
+                    }
+                }
+            }
+            ToVersoMessage::GetStartupReport => {
+                if let Err(error) = self.to_controller_sender.as_ref().unwrap().send(
+                    ToControllerMessage::GetStartupReportResponse(self.startup_report.clone()),
+                ) {
+                    log::error!(
+                        "Verso failed to send GetStartupReportResponse to controller: {error}"
+                    );
+                }
+            }
+            ToVersoMessage::SetOverlay(enabled) => {
+                if let Some(compositor) = &mut self.compositor {
+                    compositor.set_overlay(enabled);
+                }
+            }
+            ToVersoMessage::SetWebRenderDebugFlag(flag, enabled) => {
+                if let Some(compositor) = &mut self.compositor {
+                    compositor.set_webrender_debug_flag(flag, enabled);
+                }
+            }
+            ToVersoMessage::SetLogFilter(filter) => logging::set_filter(&filter),
+            ToVersoMessage::StartTrace => trace::start(),
+            ToVersoMessage::StopTrace(path) => {
+                if let Err(error) = self
+                    .to_controller_sender
+                    .as_ref()
+                    .unwrap()
+                    .send(ToControllerMessage::StopTraceResponse(trace::stop(&path)))
+                {
+                    log::error!("Verso failed to send StopTraceResponse to controller: {error}");
+                }
+            }
+            ToVersoMessage::GetMemoryReport => {
+                if let Some(to_controller_sender) = &self.to_controller_sender {
+                    memory::send_report(to_controller_sender.clone());
+                }
+            }
+            ToVersoMessage::SetWindowShape(window_id, shape) => {
+                let window_id = match window_id {
+                    Some(window_id) => self.public_ids.find_window(window_id),
+                    None => self.windows.keys().next().copied(),
+                };
+                let (Some((window, _)), Some(compositor)) = (
+                    window_id.and_then(|id| self.windows.get_mut(&id)),
+                    &mut self.compositor,
+                ) else {
+                    return;
+                };
+                compositor.set_window_shape(window, shape);
+            }
+            ToVersoMessage::GetDomSnapshot(webview_id) => {
+                let webview_id = match webview_id {
+                    Some(webview_id) => self.public_ids.find_webview(webview_id),
+                    None => self.first_webview_id(),
+                };
+                let Some(to_controller_sender) = &self.to_controller_sender else {
+                    return;
+                };
+                match webview_id {
+                    Some(webview_id) => dom_snapshot::capture(
+                        webview_id,
+                        self.constellation_sender.clone(),
+                        to_controller_sender.clone(),
+                    ),
+                    None => {
+                        if let Err(error) =
+                            to_controller_sender.send(ToControllerMessage::GetDomSnapshotResponse(
+                                Err("The webview doesn't exist".to_owned()),
+                            ))
+                        {
+                            log::error!(
+                                "Verso failed to send GetDomSnapshotResponse to controller: {error}"
+                            )
+                        }
+                    }
+                }
+            }
+            ToVersoMessage::SetNavigationPolicy(policy) => {
+                for (window, _) in self.windows.values_mut() {
+                    window.navigation_policy = policy.clone();
+                }
+                self.navigation_policy = policy;
+            }
+            ToVersoMessage::GetIdleTime => {
+                if let Err(error) = self.to_controller_sender.as_ref().unwrap().send(
+                    ToControllerMessage::GetIdleTimeResponse(self.last_input.elapsed()),
+                ) {
+                    log::error!("Verso failed to send GetIdleTimeResponse to controller: {error}")
+                }
+            }
+            ToVersoMessage::GetDevtoolsInfo => {
+                if self.devtools_server.is_some() {
+                    Self::send_devtools_started(&self.to_controller_sender, &self.devtools_server);
+                } else {
+                    log::error!(
+                        "Devtools server isn't running, launch Verso with --devtools-port to enable it"
+                    );
+                }
+            }
             _ => {}
         }
     }
 
+    /// Start a connectivity check if captive portal detection is enabled.
+    pub fn check_connectivity(&self) {
+        if let Some(probe) = &self.captive_portal_probe {
+            connectivity::check_connectivity(probe.clone(), self.event_loop_proxy.clone());
+        }
+    }
+
+    /// Handle the result of a connectivity check.
+    pub fn handle_connectivity_state(&mut self, state: ConnectivityState) {
+        if self.connectivity.as_ref() == Some(&state) {
+            return;
+        }
+        self.connectivity = Some(state.clone());
+
+        if let ConnectivityState::CaptivePortal(portal) = &state {
+            if self.open_captive_portal {
+                if let Some((window, _)) = self.windows.values_mut().next() {
+                    window.open_captive_portal(
+                        &self.constellation_sender,
+                        ServoUrl::from_url(portal.clone()),
+                    );
+                    Self::send_blocked_navigations(&self.to_controller_sender, window);
+                }
+            }
+        }
+
+        if self.connectivity_listener {
+            self.send_connectivity_state(state);
+        }
+    }
+
+    fn send_connectivity_state(&self, state: ConnectivityState) {
+        if let Some(to_controller_sender) = &self.to_controller_sender {
+            if let Err(error) =
+                to_controller_sender.send(ToControllerMessage::OnConnectivityChanged(state))
+            {
+                log::error!("Verso failed to send OnConnectivityChanged to controller: {error}")
+            }
+        }
+    }
+
+    /// Start Verso again with the same tabs once the constellation is gone. Nothing can be shown
+    /// without it and the compositor can't shut down cleanly, so this process exits right away.
+    /// Verso doesn't restart if a controller owns it, the controller sees the process exit.
+    fn recover_from_lost_constellation(&self) -> ! {
+        log::error!("Verso lost the constellation");
+        if self.to_controller_sender.is_some() {
+            log::error!("Not restarting Verso, it's owned by a controller");
+        } else if self.started_at.elapsed() < CONSTELLATION_RESTART_MIN_UPTIME {
+            log::error!("Not restarting Verso, the constellation was lost right after starting");
+        } else {
+            let session = updater::save_session(&Self::session_urls(&self.windows))
+                .inspect_err(|e| log::error!("Failed to save the session: {e}"))
+                .ok();
+            restart(session.as_deref());
+        }
+        std::process::exit(1);
+    }
+
+    /// Measure the memory used by WebRender for the mem profiler.
+    pub fn handle_memory_report_request(&self, reports_chan: ReportsChan) {
+        match &self.compositor {
+            Some(compositor) => compositor.collect_memory_reports(reports_chan),
+            None => reports_chan.send(Vec::new()),
+        }
+    }
+
+    /// Restart into a downloaded update once the user is idle.
+    pub fn handle_update_ready(&mut self, update: PathBuf) {
+        self.pending_update = Some(update);
+    }
+
+    /// Get the URLs of the tabs of all windows, the current tab of the first window first.
+    fn session_urls(windows: &HashMap<WindowId, (Window, DocumentId)>) -> Vec<url::Url> {
+        let mut urls = Vec::new();
+        for (index, (window, _)) in windows.values().enumerate() {
+            let current_tab_id = window.tab_manager.current_tab_id();
+            for tab_id in window.tab_manager.tab_ids() {
+                let Some(url) = window
+                    .tab_manager
+                    .history(tab_id)
+                    .and_then(|history| history.list.get(history.current_idx))
+                else {
+                    continue;
+                };
+                let url = url.clone().into_url();
+                if index == 0 && Some(tab_id) == current_tab_id {
+                    urls.insert(0, url);
+                } else {
+                    urls.push(url);
+                }
+            }
+        }
+        urls
+    }
+
+    /// Send fetched search suggestions to the panel of the window which asked for them.
+    pub fn handle_search_suggestions(
+        &self,
+        window_id: WindowId,
+        query: String,
+        suggestions: Vec<String>,
+    ) {
+        if let Some((window, _)) = self.windows.get(&window_id) {
+            window.show_search_suggestions(&self.constellation_sender, &query, &suggestions);
+        }
+    }
+
+    /// Run a command from the management endpoint.
+    pub fn handle_management_command(&mut self, command: ManagementCommand) {
+        match command {
+            ManagementCommand::Navigate { url } => {
+                self.handle_incoming_webview_message(ToVersoMessage::NavigateTo(url));
+            }
+            ManagementCommand::Reload => {
+                if let Some(webview_id) = self.first_webview_id() {
+                    send_to_constellation(
+                        &self.constellation_sender,
+                        ConstellationMsg::Reload(webview_id),
+                    );
+                }
+            }
+            ManagementCommand::UpdateSettings { settings } => {
+                for (window, _) in self.windows.values_mut() {
+                    if let Err(error) = window.apply_settings(settings.clone()) {
+                        log::error!("Failed to change settings from management endpoint: {error}");
+                    }
+                }
+                Self::sync_https_only(&mut self.windows, &mut self.saved_settings);
+            }
+            ManagementCommand::Screenshot => {
+                log::warn!("Screenshots aren't supported, ignoring the management command");
+            }
+        }
+    }
+
+    /// Share a change of HTTPS-only mode made in a window with all windows, and save it.
+    fn sync_https_only(
+        windows: &mut HashMap<WindowId, (Window, DocumentId)>,
+        saved_settings: &mut SavedSettings,
+    ) {
+        let mut changed = None;
+        for (window, _) in windows.values_mut() {
+            if std::mem::take(&mut window.https_only_changed) {
+                changed = Some(window.https_only);
+            }
+        }
+        let Some(https_only) = changed else {
+            return;
+        };
+        for (window, _) in windows.values_mut() {
+            window.https_only = https_only;
+        }
+        saved_settings.https_only = https_only;
+        saved_settings.save();
+    }
+
+    /// Send the devtools server port and token to the controller if there's one.
+    fn send_devtools_started(
+        to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
+        devtools_server: &Option<(u16, String)>,
+    ) {
+        let (Some(to_controller_sender), Some((port, token))) =
+            (to_controller_sender, devtools_server)
+        else {
+            return;
+        };
+        if let Err(error) = to_controller_sender.send(ToControllerMessage::OnDevtoolsStarted {
+            port: *port,
+            token: token.clone(),
+        }) {
+            log::error!("Verso failed to send OnDevtoolsStarted to controller: {error}")
+        }
+    }
+
+    /// Get the lifecycle event for a webview being opened, closed or crashing.
+    fn lifecycle_event(
+        windows: &HashMap<WindowId, (Window, DocumentId)>,
+        public_ids: &mut PublicIds,
+        msg: &EmbedderMsg,
+    ) -> Option<LifecycleEvent> {
+        match msg {
+            EmbedderMsg::WebViewOpened(webview_id) => windows.values().find_map(|(window, _)| {
+                Self::webview_created_event(window, public_ids, *webview_id)
+            }),
+            EmbedderMsg::WebViewClosed(webview_id) => Some(LifecycleEvent::WebViewDestroyed {
+                webview_id: public_ids.remove_webview(*webview_id),
+            }),
+            EmbedderMsg::Panic(webview_id, reason, _) => Some(LifecycleEvent::WebViewCrashed {
+                webview_id: public_ids.webview(*webview_id),
+                reason: reason.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Get the load stage a message reports for a tab, `None` for other webviews and messages.
+    ///
+    /// Servo doesn't tell the embedder about redirects or failed requests, a redirect only shows
+    /// as the committed URL differing from the requested one, and a load only fails when the
+    /// content crashes.
+    fn load_progress(
+        windows: &HashMap<WindowId, (Window, DocumentId)>,
+        public_ids: &mut PublicIds,
+        msg: &EmbedderMsg,
+    ) -> Option<LoadProgress> {
+        let (webview_id, stage) = match msg {
+            EmbedderMsg::AllowNavigationRequest(webview_id, _, url) => {
+                (webview_id, LoadStage::Requested(url.as_url().clone()))
+            }
+            EmbedderMsg::NotifyLoadStatusChanged(webview_id, status) => {
+                let stage = match status {
+                    LoadStatus::Started => LoadStage::Started,
+                    LoadStatus::HeadParsed => LoadStage::HeadParsed,
+                    LoadStatus::Complete => LoadStage::Finished,
+                };
+                (webview_id, stage)
+            }
+            EmbedderMsg::HistoryChanged(webview_id, list, index) => {
+                let url = list.get(*index)?;
+                (webview_id, LoadStage::Committed(url.as_url().clone()))
+            }
+            EmbedderMsg::Panic(webview_id, reason, _) => {
+                (webview_id, LoadStage::Failed(reason.clone()))
+            }
+            _ => return None,
+        };
+        windows
+            .values()
+            .any(|(window, _)| window.tab_manager.tab(*webview_id).is_some())
+            .then(|| LoadProgress {
+                webview_id: public_ids.webview(*webview_id),
+                stage,
+            })
+    }
+
+    /// Get the info of a window's current page for the controller.
+    fn page_info(
+        public_ids: &mut PublicIds,
+        window_id: WindowId,
+        (webview_id, url, title): &(WebViewId, ServoUrl, Option<String>),
+    ) -> PageInfo {
+        PageInfo {
+            window_id: public_ids.window(window_id),
+            webview_id: public_ids.webview(*webview_id),
+            url: url.as_url().clone(),
+            title: title.clone(),
+        }
+    }
+
+    /// Send the current page of a window to the controller if there's one.
+    fn send_page_changed(
+        to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
+        info: PageInfo,
+    ) {
+        let Some(to_controller_sender) = to_controller_sender else {
+            return;
+        };
+        if let Err(error) = to_controller_sender.send(ToControllerMessage::OnPageChanged(info)) {
+            log::error!("Verso failed to send OnPageChanged to controller: {error}")
+        }
+    }
+
+    /// Send a load stage to the controller if there's one.
+    fn send_load_progress(
+        to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
+        progress: LoadProgress,
+    ) {
+        let Some(to_controller_sender) = to_controller_sender else {
+            return;
+        };
+        if let Err(error) = to_controller_sender.send(ToControllerMessage::OnLoadProgress(progress))
+        {
+            log::error!("Verso failed to send OnLoadProgress to controller: {error}")
+        }
+    }
+
+    /// Get the text field change of a message showing or hiding the IME, `None` for other
+    /// messages or if the webview isn't in a window.
+    fn text_input_change(
+        windows: &HashMap<WindowId, (Window, DocumentId)>,
+        public_ids: &mut PublicIds,
+        msg: &EmbedderMsg,
+    ) -> Option<TextInputChange> {
+        match msg {
+            EmbedderMsg::ShowIME(webview_id, input_type, text, multiline, rect) => {
+                let area = windows
+                    .values()
+                    .find_map(|(window, _)| window.text_input_area(*webview_id, *rect))?;
+                let (value, caret) = match text {
+                    Some((value, caret)) => (Some(value.clone()), u32::try_from(*caret).ok()),
+                    None => (None, None),
+                };
+                Some(TextInputChange::Focused(TextInputState {
+                    webview_id: public_ids.webview(*webview_id),
+                    input_type: text_input_type(*input_type),
+                    value,
+                    caret,
+                    multiline: *multiline,
+                    position: PhysicalPosition::new(area.min.x, area.min.y),
+                    size: PhysicalSize::new(area.width() as u32, area.height() as u32),
+                }))
+            }
+            EmbedderMsg::HideIME(webview_id) => {
+                Some(TextInputChange::Blurred(public_ids.webview(*webview_id)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Send a text field change to the controller if there's one.
+    fn send_text_input_change(
+        to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
+        change: TextInputChange,
+    ) {
+        let Some(to_controller_sender) = to_controller_sender else {
+            return;
+        };
+        if let Err(error) =
+            to_controller_sender.send(ToControllerMessage::OnTextInputChanged(change))
+        {
+            log::error!("Verso failed to send OnTextInputChanged to controller: {error}")
+        }
+    }
+
+    /// Type into the focused text field for the controller. Servo sends input events without a
+    /// point to the focused element.
+    fn handle_text_input(&self, event: TextInputEvent) {
+        let events = match event {
+            TextInputEvent::Composition(data) => {
+                vec![InputEvent::Ime(ImeEvent::Composition(CompositionEvent {
+                    state: CompositionState::Update,
+                    data,
+                }))]
+            }
+            TextInputEvent::Commit(data) => {
+                vec![InputEvent::Ime(ImeEvent::Composition(CompositionEvent {
+                    state: CompositionState::End,
+                    data,
+                }))]
+            }
+            TextInputEvent::Key(key) => {
+                // Unknown names are typed as characters.
+                let key = Key::from_str(&key).unwrap_or(Key::Character(key));
+                [KeyState::Down, KeyState::Up]
+                    .into_iter()
+                    .map(|state| {
+                        InputEvent::Keyboard(KeyboardEvent {
+                            state,
+                            key: key.clone(),
+                            ..Default::default()
+                        })
+                    })
+                    .collect()
+            }
+            TextInputEvent::Dismiss => vec![InputEvent::Ime(ImeEvent::Dismissed)],
+        };
+        for event in events {
+            send_to_constellation(
+                &self.constellation_sender,
+                ConstellationMsg::ForwardInputEvent(event, None),
+            );
+        }
+    }
+
+    /// Get the created event of a webview, `None` if it isn't in the window.
+    fn webview_created_event(
+        window: &Window,
+        public_ids: &mut PublicIds,
+        webview_id: WebViewId,
+    ) -> Option<LifecycleEvent> {
+        let (kind, parent) = window.webview_kind(webview_id)?;
+        Some(LifecycleEvent::WebViewCreated {
+            webview_id: public_ids.webview(webview_id),
+            window_id: public_ids.window(window.id()),
+            kind,
+            parent: parent.map(|parent| public_ids.webview(parent)),
+        })
+    }
+
+    /// Send a lifecycle event to the controller if there's one.
+    fn send_lifecycle_event(
+        to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
+        event: LifecycleEvent,
+    ) {
+        let Some(to_controller_sender) = to_controller_sender else {
+            return;
+        };
+        if let Err(error) = to_controller_sender.send(ToControllerMessage::OnLifecycleEvent(event))
+        {
+            log::error!("Verso failed to send OnLifecycleEvent to controller: {error}")
+        }
+    }
+
+    fn send_idle_state(
+        to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
+        state: IdleState,
+    ) {
+        let Some(to_controller_sender) = to_controller_sender else {
+            return;
+        };
+        if let Err(error) =
+            to_controller_sender.send(ToControllerMessage::OnIdleStateChanged(state))
+        {
+            log::error!("Verso failed to send OnIdleStateChanged to controller: {error}")
+        }
+    }
+
+    /// Report the navigations a window has blocked to the controller if there's one.
+    fn send_blocked_navigations(
+        to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
+        window: &mut Window,
+    ) {
+        let blocked = std::mem::take(&mut window.blocked_navigations);
+        let Some(to_controller_sender) = to_controller_sender else {
+            return;
+        };
+        for url in blocked {
+            if let Err(error) =
+                to_controller_sender.send(ToControllerMessage::OnNavigationBlocked(url))
+            {
+                log::error!("Verso failed to send OnNavigationBlocked to controller: {error}")
+            }
+        }
+    }
+
+    /// When the next idle timeout, idle reset, playlist change, test timeout, update restart or
+    /// forced exit is due.
+    fn timer_deadline(&self) -> Option<Instant> {
+        let idle_timeout = self
+            .idle_timeout
+            .filter(|_| !self.idle)
+            .map(|timeout| self.last_input + timeout);
+        let idle_reset = self
+            .idle_reset
+            .filter(|_| self.input_since_start)
+            .map(|timeout| self.last_input + timeout);
+        // Without a tab to show the playlist in, the next tab created wakes Verso up anyway.
+        let playlist = self
+            .playlist
+            .as_ref()
+            .filter(|_| self.main_tab().is_some())
+            .map(Playlist::deadline);
+        let wpt = self
+            .wpt
+            .as_ref()
+            .filter(|_| self.main_tab().is_some())
+            .and_then(WptRunner::deadline);
+        let update = self
+            .pending_update
+            .as_ref()
+            .map(|_| self.last_input + updater::RESTART_IDLE_TIME);
+        [
+            idle_timeout,
+            idle_reset,
+            playlist,
+            wpt,
+            update,
+            self.shutdown_deadline,
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+
     fn first_window(&self) -> Option<&Window> {
         self.windows.values().next().map(|(window, _)| window)
     }
@@ -866,6 +2509,29 @@ impl Verso {
         self.windows.values_mut().next().map(|(window, _)| window)
     }
 
+    /// Check if a webview is paused, or shares its event loop with a paused tab since it's of the
+    /// same site.
+    fn is_paused(&self, webview_id: WebViewId) -> bool {
+        let site = self
+            .windows
+            .values()
+            .find_map(|(window, _)| window.current_url(webview_id))
+            .and_then(reg_host);
+        self.windows.values().any(|(window, _)| {
+            window.paused_webviews.contains_key(&webview_id)
+                || site
+                    .as_ref()
+                    .is_some_and(|site| window.paused_sites().any(|paused| &paused == site))
+        })
+    }
+
+    /// Current tab of the main window, `None` once the window is closed.
+    fn main_tab(&self) -> Option<WebViewId> {
+        self.windows
+            .get(&self.main_window)
+            .and_then(|(window, _)| window.tab_manager.current_tab_id())
+    }
+
     fn first_webview_id(&self) -> Option<TopLevelBrowsingContextId> {
         self.windows
             .values()
@@ -873,25 +2539,96 @@ impl Verso {
             .and_then(|(window, _)| window.tab_manager.current_tab().map(|tab| tab.id()))
     }
 
+    /// Interval between two frames based on the refresh rate of the current window's monitor.
+    /// Falls back to 60Hz if the platform doesn't report it.
+    fn frame_interval(&self) -> Duration {
+        const DEFAULT_FRAME_INTERVAL: Duration = Duration::from_micros(16_667);
+        self.compositor
+            .as_ref()
+            .and_then(|compositor| self.windows.get(&compositor.current_window))
+            .and_then(|(window, _)| window.window.current_monitor())
+            .and_then(|monitor| monitor.refresh_rate_millihertz())
+            .filter(|millihertz| *millihertz > 0)
+            .map(|millihertz| Duration::from_nanos(1_000_000_000_000 / millihertz as u64))
+            .unwrap_or(DEFAULT_FRAME_INTERVAL)
+    }
+
     /// Return true if one of the Verso windows is animating.
     pub fn is_animating(&self) -> bool {
         self.compositor
             .as_ref()
-            .map(|c| c.is_animating)
+            .map(|c| c.is_animating || c.is_flinging())
             .unwrap_or(false)
     }
 
-    fn setup_logging(&self) {
-        let constellation_chan = self.constellation_sender.clone();
-        let env = env_logger::Env::default();
-        let env_logger = env_logger::Builder::from_env(env).build();
-        let con_logger = FromCompositorLogger::new(constellation_chan);
+    fn setup_logging(&self, settings: LogSettings) {
+        logging::init(self.constellation_sender.clone(), settings);
+    }
+}
+
+/// Get the public type of a text field.
+fn text_input_type(input_type: InputMethodType) -> TextInputType {
+    match input_type {
+        InputMethodType::Color => TextInputType::Color,
+        InputMethodType::Date => TextInputType::Date,
+        InputMethodType::DatetimeLocal => TextInputType::DatetimeLocal,
+        InputMethodType::Email => TextInputType::Email,
+        InputMethodType::Month => TextInputType::Month,
+        InputMethodType::Number => TextInputType::Number,
+        InputMethodType::Password => TextInputType::Password,
+        InputMethodType::Search => TextInputType::Search,
+        InputMethodType::Tel => TextInputType::Tel,
+        InputMethodType::Text => TextInputType::Text,
+        InputMethodType::Time => TextInputType::Time,
+        InputMethodType::Url => TextInputType::Url,
+        InputMethodType::Week => TextInputType::Week,
+    }
+}
+
+/// Check if the event comes from the user interacting with the window.
+fn is_user_input(event: &WindowEvent) -> bool {
+    matches!(
+        event,
+        WindowEvent::KeyboardInput { .. }
+            | WindowEvent::Ime(_)
+            | WindowEvent::CursorMoved { .. }
+            | WindowEvent::MouseInput { .. }
+            | WindowEvent::MouseWheel { .. }
+            | WindowEvent::Touch(_)
+            | WindowEvent::PinchGesture { .. }
+    )
+}
 
-        let filter = std::cmp::max(env_logger.filter(), con_logger.filter());
-        let logger = BothLogger(env_logger, con_logger);
+/// Start a new Verso process with the same arguments, restoring the session if there's one.
+/// It's started once this one has shut down, but it can still be exiting, so the new process
+/// waits until it can listen on the devtools and WebDriver ports.
+fn restart(session: Option<&Path>) {
+    let result = std::env::current_exe().and_then(|exe| {
+        let mut command = std::process::Command::new(exe);
+        command.args(std::env::args_os().skip(1));
+        command.env(RESTARTED_VAR, "1");
+        match session {
+            Some(session) => command.env(updater::RESTORE_SESSION_VAR, session),
+            None => command.env_remove(updater::RESTORE_SESSION_VAR),
+        };
+        command.spawn()
+    });
+    if let Err(e) = result {
+        log::error!("Failed to restart Verso: {e}");
+    }
+}
 
-        log::set_boxed_logger(Box::new(logger)).expect("Failed to set logger.");
-        log::set_max_level(filter);
+/// Wait until nothing listens on the ports anymore, or give up after [`RESTART_PORT_TIMEOUT`].
+fn wait_for_ports(ports: impl Iterator<Item = u16>) {
+    let deadline = Instant::now() + RESTART_PORT_TIMEOUT;
+    for port in ports {
+        while std::net::TcpListener::bind(("127.0.0.1", port)).is_err() {
+            if Instant::now() >= deadline {
+                log::warn!("Port {port} is still in use after restarting");
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
     }
 }
 
@@ -902,6 +2639,23 @@ pub enum EventLoopProxyMessage {
     Wake,
     /// Message coming from the webview controller
     IpcMessage(ToVersoMessage),
+    /// Result of a connectivity check
+    ConnectivityChecked(ConnectivityState),
+    /// Search suggestions fetched for the URL bar input of a window
+    SearchSuggestions {
+        /// Window which asked for the suggestions
+        window_id: WindowId,
+        /// URL bar input the suggestions are for
+        query: String,
+        /// Suggested queries
+        suggestions: Vec<String>,
+    },
+    /// Command polled from the management endpoint
+    ManagementCommand(ManagementCommand),
+    /// A verified update was downloaded to this path
+    UpdateReady(PathBuf),
+    /// The mem profiler asks for the memory used by WebRender
+    CollectMemoryReports(ReportsChan),
 }
 
 #[derive(Debug, Clone)]
@@ -970,33 +2724,10 @@ impl webrender::api::RenderNotifier for RenderNotifier {
     }
 }
 
-// A logger that logs to two downstream loggers.
-// This should probably be in the log crate.
-struct BothLogger<Log1, Log2>(Log1, Log2);
-
-impl<Log1, Log2> Log for BothLogger<Log1, Log2>
-where
-    Log1: Log,
-    Log2: Log,
-{
-    fn enabled(&self, metadata: &Metadata) -> bool {
-        self.0.enabled(metadata) || self.1.enabled(metadata)
-    }
-
-    fn log(&self, record: &Record) {
-        self.0.log(record);
-        self.1.log(record);
-    }
-
-    fn flush(&self) {
-        self.0.flush();
-        self.1.flush();
-    }
-}
-
 pub(crate) fn send_to_constellation(sender: &Sender<ConstellationMsg>, msg: ConstellationMsg) {
     let variant_name = msg.variant_name();
     if let Err(e) = sender.send(msg) {
         log::warn!("Sending {variant_name} to constellation failed: {e:?}");
+        CONSTELLATION_LOST.store(true, Ordering::Relaxed);
     }
 }
@@ -1,4 +1,5 @@
-use arboard::Clipboard;
+use std::{future::Future, pin::Pin, thread};
+
 use base::id::WebViewId;
 use compositing_traits::ConstellationMsg;
 use crossbeam_channel::Sender;
@@ -6,19 +7,27 @@ use embedder_traits::{
     AllowOrDeny, ContextMenuResult, EmbedderMsg, LoadStatus, PromptDefinition, PromptResult,
     TraversalDirection,
 };
-use ipc_channel::ipc;
+use ipc_channel::{ipc, router::ROUTER};
 use script_traits::webdriver_msg::{WebDriverJSResult, WebDriverScriptCommand};
 use servo_url::ServoUrl;
-use url::Url;
-use versoview_messages::ToControllerMessage;
+use versoview_messages::{KeyAction, ToControllerMessage};
 use webrender_api::units::DeviceIntRect;
 
 use crate::{
+    clipboard::Clipboard,
     compositor::IOCompositor,
+    external,
+    newtab::{self, NEWTAB_URL},
+    popup_blocker::PopupDecision,
+    settings::{Settings, SETTINGS_PROMPT_PREFIX, SETTINGS_TOKEN},
     tab::{TabActivateRequest, TabCloseRequest, TabCreateResponse},
     verso::send_to_constellation,
     webview::prompt::{HttpBasicAuthInputResult, PromptDialog, PromptInputResult, PromptSender},
-    window::Window,
+    window::{
+        https_only_error_page, needs_https, NewWebViewRequests, Window, CERTIFICATE_EXCEPTIONS,
+        CERTIFICATE_EXCEPTION_PROMPT, HTTPS_LOAD_FAILED_PROMPT, HTTP_EXCEPTIONS,
+        HTTP_FALLBACK_PROMPT, LOAD_PROGRESS_HEAD_PARSED, LOAD_PROGRESS_STARTED, PAUSE_PROMPT,
+    },
 };
 
 #[cfg(linux)]
@@ -47,22 +56,42 @@ impl WebView {
 
 /// A panel is a special web view that focus on controlling states around window.
 /// It could be treated as the control panel or navigation bar of the window depending on usages.
+/// The built-in panel can be replaced with any page from a
+/// [`ChromeSource`](crate::config::ChromeSource). Verso injects the `verso.*` wrappers of
+/// [`VERSO_API_SCRIPT`] into the panel, which pages can use instead of these prompts.
 ///
 /// At the moment, following Web API is supported:
 /// - Close window: `window.close()`
 /// - Navigate to previous page: `window.prompt('PREV')`
 /// - Navigate to next page: `window.prompt('FORWARD')`
 /// - Refresh the page: `window.prompt('REFRESH')`
+/// - Load the home page: `window.prompt('HOME')`
 /// - Minimize the window: `window.prompt('MINIMIZE')`
 /// - Maximize the window: `window.prompt('MAXIMIZE')`
-/// - Navigate to a specific URL: `window.prompt('NAVIGATE_TO:${url}')`
+/// - Navigate to a specific URL, or search for other input: `window.prompt('NAVIGATE_TO:${url}')`
+/// - Set the window's drag regions: `window.prompt('SET_DRAG_REGIONS:[{"x":0,"y":0,"width":100,"height":40}]')`
+/// - Run a keyboard shortcut's action: `window.prompt('RUN_ACTION:"ZoomIn"')`
+/// - Decide on the blocked popup of the current tab: `window.prompt('POPUP_DECISION:"allowOnce"')`,
+///   `"allowSite"` or `"dismiss"`
+/// - Ask for search suggestions: `window.prompt('SUGGEST:${input}')`, they're sent to
+///   `window.navbar.setSuggestions(input, suggestions)` if `--search-suggestions` is set
+/// - Get the window's settings: `window.prompt('GET_SETTINGS')`
+/// - Change the window's settings: `window.prompt('SET_SETTINGS:{"httpsOnly":true}')`
 pub struct Panel {
     /// The panel's webview
     pub(crate) webview: WebView,
     /// The URL to load when the panel gets loaded
     pub(crate) initial_url: servo_url::ServoUrl,
+    /// URLs of other tabs to open again after restarting into an update
+    pub(crate) restored_urls: Vec<servo_url::ServoUrl>,
+    /// Height reserved for a custom chrome page in logical pixels, the webviews are laid out
+    /// around the built-in panel if None
+    pub(crate) height: Option<f64>,
 }
 
+/// The `verso.*` API, only injected into panels since it's backed by their prompts.
+pub const VERSO_API_SCRIPT: &str = include_str!("../../resources/components/verso.js");
+
 impl Window {
     /// Handle servo messages with corresponding web view ID.
     pub fn handle_servo_messages_with_webview(
@@ -71,6 +100,7 @@ impl Window {
         message: EmbedderMsg,
         sender: &Sender<ConstellationMsg>,
         to_controller_sender: &Option<ipc::IpcSender<ToControllerMessage>>,
+        new_webview_requests: &mut Option<NewWebViewRequests>,
         clipboard: Option<&mut Clipboard>,
         compositor: &mut IOCompositor,
     ) {
@@ -95,21 +125,33 @@ impl Window {
             }
             EmbedderMsg::NotifyLoadStatusChanged(_webview_id, status) => match status {
                 LoadStatus::Started => {
+                    // Pages of a paused site load too, their script runs once it's resumed.
                     if let Some(init_script) = &self.init_script {
-                        let _ = execute_script(sender, &webview_id, init_script);
+                        send_script(sender, &webview_id, init_script);
                     }
+                    self.clear_blocked_popup(sender, webview_id);
+                    self.set_load_progress(sender, webview_id, Some(LOAD_PROGRESS_STARTED));
+                }
+                LoadStatus::HeadParsed => {
+                    self.set_load_progress(sender, webview_id, Some(LOAD_PROGRESS_HEAD_PARSED));
                 }
                 LoadStatus::Complete => {
                     self.window.request_redraw();
                     send_to_constellation(sender, ConstellationMsg::FocusWebView(webview_id));
-                }
-                _ => {
-                    log::trace!(
-                            "Verso WebView {webview_id:?} ignores NotifyLoadStatusChanged status: {status:?}"
-                        );
+                    self.set_load_progress(sender, webview_id, None);
                 }
             },
+            EmbedderMsg::Panic(..) => {
+                // The page won't finish loading.
+                self.set_load_progress(sender, webview_id, None);
+            }
             EmbedderMsg::ChangePageTitle(_webview_id, title) => {
+                self.tab_manager.set_title(webview_id, title.clone());
+                if let Some(history) = self.tab_manager.history(webview_id) {
+                    if let Some(url) = history.list.get(history.current_idx) {
+                        newtab::record_visit(url.as_url(), title.clone());
+                    }
+                }
                 if let Some(panel) = self.panel.as_ref() {
                     let title = if let Some(title) = title {
                         format!("'{title}'")
@@ -126,6 +168,37 @@ impl Window {
                 }
             }
             EmbedderMsg::AllowNavigationRequest(_webview_id, id, url) => {
+                let upgraded = if self.captive_portal_tabs.contains(&webview_id) {
+                    url.clone()
+                } else {
+                    self.upgrade_insecure_url(url.clone())
+                };
+                if !self.allows_navigation(&upgraded) {
+                    send_to_constellation(
+                        sender,
+                        ConstellationMsg::AllowNavigationResponse(id, false),
+                    );
+                    self.open_blocked_externally(sender, Some(webview_id), &upgraded);
+                    return;
+                }
+                if external::is_external(url.as_url()) {
+                    // Servo can't load it, so ask before handing it to another application.
+                    send_to_constellation(
+                        sender,
+                        ConstellationMsg::AllowNavigationResponse(id, false),
+                    );
+                    self.confirm_open_externally(sender, webview_id, url.into_url());
+                    return;
+                }
+                if upgraded != url {
+                    // Cancel the insecure navigation and start over with the upgraded URL.
+                    send_to_constellation(
+                        sender,
+                        ConstellationMsg::AllowNavigationResponse(id, false),
+                    );
+                    send_to_constellation(sender, ConstellationMsg::LoadUrl(webview_id, upgraded));
+                    return;
+                }
                 if let Some(to_controller_sender) = to_controller_sender {
                     if self.event_listeners.on_navigation_starting {
                         if let Err(error) =
@@ -144,6 +217,38 @@ impl Window {
                 }
                 send_to_constellation(sender, ConstellationMsg::AllowNavigationResponse(id, true));
             }
+            EmbedderMsg::AllowOpeningWebView(_webview_id, response_sender) => {
+                if self.blocks_popup(sender, webview_id) {
+                    if let Err(error) = response_sender.send(None) {
+                        log::error!("Verso failed to respond to AllowOpeningWebView: {error}");
+                    }
+                    return;
+                }
+                if let Some(to_controller_sender) = to_controller_sender {
+                    if let Some(request_map) = new_webview_requests {
+                        let id = uuid::Uuid::new_v4();
+                        let opener_url = self
+                            .tab_manager
+                            .history(webview_id)
+                            .and_then(|history| history.list.get(history.current_idx))
+                            .map(|url| url.as_url().clone());
+                        match to_controller_sender
+                            .send(ToControllerMessage::OnNewWebViewRequested(id, opener_url))
+                        {
+                            Ok(_) => {
+                                request_map.insert(id, (webview_id, response_sender));
+                                // We will handle a ToVersoMessage::NewWebViewRequestResponse
+                                // and open the webview there if the call succeed
+                                return;
+                            }
+                            Err(error) => {
+                                log::error!("Verso failed to send OnNewWebViewRequested to controller: {error}")
+                            }
+                        }
+                    }
+                }
+                self.open_new_webview(sender, webview_id, self.new_webview_policy, response_sender);
+            }
             EmbedderMsg::WebResourceRequested(_webview_id, request, sender) => {
                 if let Some(to_controller_sender) = to_controller_sender {
                     if let Some(request_map) = &mut self.event_listeners.on_web_resource_requested {
@@ -205,6 +310,48 @@ impl Window {
                     }
                 }
             }
+            EmbedderMsg::SelectFiles(
+                _webview_id,
+                filter_patterns,
+                allow_select_multiple,
+                sender,
+            ) => {
+                // Servo gives the accepted file types as extensions without the leading dot.
+                let extensions: Vec<&str> = filter_patterns
+                    .iter()
+                    .map(|pattern| pattern.0.as_str())
+                    .collect();
+                let mut dialog = rfd::AsyncFileDialog::new().set_parent(&self.window);
+                if !extensions.is_empty() {
+                    dialog = dialog.add_filter("Accepted files", &extensions);
+                }
+                // The dialog is opened here, on the main thread, and waited for on another one so
+                // the windows keep rendering and handling input while it's open.
+                let files: Pin<Box<dyn Future<Output = Option<Vec<rfd::FileHandle>>> + Send>> =
+                    if allow_select_multiple {
+                        Box::pin(dialog.pick_files())
+                    } else {
+                        let file = dialog.pick_file();
+                        Box::pin(async move { file.await.map(|file| vec![file]) })
+                    };
+                let spawn_result =
+                    thread::Builder::new()
+                        .name("FileDialog".to_owned())
+                        .spawn(move || {
+                            let files = futures::executor::block_on(files).map(|files| {
+                                files.iter().map(|file| file.path().to_path_buf()).collect()
+                            });
+                            if let Err(e) = sender.send(files) {
+                                log::warn!(
+                                "Verso WebView {webview_id:?} failed to send selected files: {}",
+                                e
+                            );
+                            }
+                        });
+                if let Err(e) = spawn_result {
+                    log::error!("Failed to spawn file dialog thread: {e}");
+                }
+            }
             EmbedderMsg::HistoryChanged(_webview_id, list, index) => {
                 self.close_prompt_dialog(webview_id);
                 compositor.send_root_pipeline_display_list(self);
@@ -212,6 +359,18 @@ impl Window {
                 self.tab_manager
                     .set_history(webview_id, list.clone(), index);
                 let url = list.get(index).unwrap();
+                // Navigations are upgraded before they start, so an `http` page only commits in
+                // HTTPS-only mode if an upgraded load redirected back to it.
+                if self.https_only
+                    && url.scheme() == "http"
+                    && needs_https(url.as_url())
+                    && !self.captive_portal_tabs.contains(&webview_id)
+                {
+                    log::info!("HTTPS-only mode stopped {url}, it redirected back to http");
+                    let page = https_only_error_page(url.as_url());
+                    send_to_constellation(sender, ConstellationMsg::LoadUrl(webview_id, page));
+                }
+                newtab::record_visit(url.as_url(), None);
                 if let Some(panel) = self.panel.as_ref() {
                     let _ = execute_script(
                         sender,
@@ -220,6 +379,9 @@ impl Window {
                     );
                 }
             }
+            EmbedderMsg::NotifyFullscreenStateChanged(_webview_id, fullscreen) => {
+                self.set_content_fullscreen(webview_id, fullscreen, compositor);
+            }
             EmbedderMsg::ShowContextMenu(_webview_id, servo_sender, _title, _options) => {
                 #[cfg(linux)]
                 if self.context_menu.is_none() {
@@ -237,6 +399,55 @@ impl Window {
                 }
             }
             EmbedderMsg::Prompt(_webview_id, prompt_type, _origin) => {
+                // Prompts of the settings page aren't shown, they read and change the settings.
+                let prompt_type = match prompt_type {
+                    PromptDefinition::Input(message, _, prompt_sender)
+                        if message.starts_with(SETTINGS_PROMPT_PREFIX) =>
+                    {
+                        let _ =
+                            prompt_sender.send(self.handle_settings_prompt(webview_id, &message));
+                        return;
+                    }
+                    // The certificate error page is about to allow the certificate of its host.
+                    PromptDefinition::Input(message, _, prompt_sender)
+                        if message == CERTIFICATE_EXCEPTION_PROMPT =>
+                    {
+                        self.add_certificate_exception(webview_id);
+                        let _ = prompt_sender.send(None);
+                        return;
+                    }
+                    // The network error page reports the URL which failed to load.
+                    PromptDefinition::Input(message, _, prompt_sender)
+                        if message.starts_with(HTTPS_LOAD_FAILED_PROMPT) =>
+                    {
+                        let url = &message[HTTPS_LOAD_FAILED_PROMPT.len()..];
+                        self.handle_https_load_failure(sender, webview_id, url);
+                        let _ = prompt_sender.send(None);
+                        return;
+                    }
+                    // The HTTPS-only error page is about to load its page over http.
+                    PromptDefinition::Input(message, _, prompt_sender)
+                        if message == HTTP_FALLBACK_PROMPT =>
+                    {
+                        let _ = prompt_sender.send(None);
+                        self.continue_over_http(sender, webview_id);
+                        return;
+                    }
+                    // The pause prompt is answered when the controller resumes the tab.
+                    PromptDefinition::Input(message, _, prompt_sender)
+                        if message == PAUSE_PROMPT =>
+                    {
+                        match self.paused_webviews.get_mut(&webview_id) {
+                            Some(paused) => *paused = Some(prompt_sender),
+                            // Resumed before the page got to pause.
+                            None => {
+                                let _ = prompt_sender.send(None);
+                            }
+                        }
+                        return;
+                    }
+                    prompt_type => prompt_type,
+                };
                 if let Some(tab) = self.tab_manager.tab(webview_id) {
                     let mut prompt = PromptDialog::new();
                     let rect = tab.webview().rect;
@@ -294,6 +505,149 @@ impl Window {
         }
     }
 
+    /// Ask the user whether to open a link with an external scheme like `mailto:` with the
+    /// system's default handler.
+    pub(crate) fn confirm_open_externally(
+        &mut self,
+        sender: &Sender<ConstellationMsg>,
+        webview_id: WebViewId,
+        url: url::Url,
+    ) {
+        let Some(tab) = self.tab_manager.tab(webview_id) else {
+            log::error!("Failed to get WebView {webview_id:?} in this window.");
+            return;
+        };
+        let (prompt_sender, prompt_receiver) = match ipc::channel::<PromptResult>() {
+            Ok(channel) => channel,
+            Err(error) => {
+                log::error!("Failed to create the channel of an external link prompt: {error}");
+                return;
+            }
+        };
+        let message = format!("Open {url} with another application?");
+        let mut prompt = PromptDialog::new();
+        prompt.ok_cancel(sender, tab.webview().rect, message, prompt_sender);
+        self.tab_manager.set_prompt(webview_id, prompt);
+        ROUTER.add_typed_route(
+            prompt_receiver,
+            Box::new(move |result| {
+                if let Ok(PromptResult::Primary) = result {
+                    external::open_externally(&url);
+                }
+            }),
+        );
+    }
+
+    /// Remember that the certificate of the host of a tab's page was allowed, so its pages are
+    /// reported as insecure. A page can only mark its own host this way, which is harmless.
+    fn add_certificate_exception(&mut self, webview_id: WebViewId) {
+        let Some(history) = self.tab_manager.history(webview_id) else {
+            return;
+        };
+        let Some(host) = history
+            .list
+            .get(history.current_idx)
+            .and_then(|url| url.host_str())
+        else {
+            return;
+        };
+        log::info!("Certificate of {host} was allowed temporarily");
+        if let Ok(mut exceptions) = CERTIFICATE_EXCEPTIONS.lock() {
+            exceptions.insert(host.to_owned());
+        }
+    }
+
+    /// Show the HTTPS-only error page for a page which failed to load over `https` in HTTPS-only
+    /// mode, so the user can load its `http` version instead.
+    fn handle_https_load_failure(
+        &mut self,
+        sender: &Sender<ConstellationMsg>,
+        webview_id: WebViewId,
+        url: &str,
+    ) {
+        let Ok(mut url) = url::Url::parse(url) else {
+            return;
+        };
+        if !self.https_only
+            || url.scheme() != "https"
+            || !needs_https(&url)
+            || self.captive_portal_tabs.contains(&webview_id)
+        {
+            return;
+        }
+        let _ = url.set_scheme("http");
+        log::info!("{url} failed to load over https, offering to load it over http");
+        let page = https_only_error_page(&url);
+        send_to_constellation(sender, ConstellationMsg::LoadUrl(webview_id, page));
+    }
+
+    /// Load the `http` URL of the HTTPS-only error page shown in a tab, and stop upgrading its
+    /// host until Verso exits. Other pages can't raise the prompt for it, since the tab has to
+    /// show the error page.
+    fn continue_over_http(&mut self, sender: &Sender<ConstellationMsg>, webview_id: WebViewId) {
+        let Some(url) = self
+            .tab_manager
+            .history(webview_id)
+            .and_then(|history| history.list.get(history.current_idx))
+            .filter(|page| page.scheme() == "verso" && page.host_str() == Some("https-only"))
+            .and_then(|page| {
+                let (_, url) = page
+                    .as_url()
+                    .query_pairs()
+                    .find(|(name, _)| name == "url")?;
+                url::Url::parse(&url).ok()
+            })
+            .filter(|url| url.scheme() == "http")
+        else {
+            return;
+        };
+        let Some(host) = url.host_str() else {
+            return;
+        };
+        log::info!("Loading {host} over http until Verso exits");
+        if let Ok(mut exceptions) = HTTP_EXCEPTIONS.lock() {
+            exceptions.insert(host.to_owned());
+        }
+        let url = ServoUrl::from_url(url);
+        if self.allows_navigation(&url) {
+            send_to_constellation(sender, ConstellationMsg::LoadUrl(webview_id, url));
+        }
+    }
+
+    /// Handle a prompt of the `verso://settings` page and return the settings, or `None` if it
+    /// doesn't come from the settings page or doesn't have the right token.
+    fn handle_settings_prompt(&mut self, webview_id: WebViewId, message: &str) -> Option<String> {
+        let request = message.strip_prefix(SETTINGS_PROMPT_PREFIX)?;
+        let is_settings_page = self
+            .tab_manager
+            .history(webview_id)
+            .and_then(|history| history.list.get(history.current_idx))
+            .is_some_and(|url| url.scheme() == "verso" && url.host_str() == Some("settings"));
+        if !is_settings_page {
+            log::warn!(
+                "Verso WebView ignores a settings prompt of a page other than verso://settings"
+            );
+            return None;
+        }
+        let (token, changes) = match request.split_once(':') {
+            Some((token, changes)) => (token, Some(changes)),
+            None => (request, None),
+        };
+        if token != SETTINGS_TOKEN.as_str() {
+            log::warn!("Verso WebView ignores a settings prompt with an invalid token");
+            return None;
+        }
+        if let Some(changes) = changes {
+            let result = serde_json::from_str::<Settings>(changes)
+                .map_err(|e| e.to_string())
+                .and_then(|settings| self.apply_settings(settings));
+            if let Err(error) = result {
+                log::error!("Failed to change settings from the settings page: {error}");
+            }
+        }
+        serde_json::to_string(&self.settings()).ok()
+    }
+
     /// Handle servo messages with main panel. Return true it requests a new window.
     pub fn handle_servo_messages_with_panel(
         &mut self,
@@ -322,10 +676,29 @@ impl Window {
                 );
             }
             EmbedderMsg::NotifyLoadStatusChanged(_webview_id, status) => {
-                if status == LoadStatus::Complete {
+                if status == LoadStatus::Started {
+                    let _ = execute_script(sender, &panel_id, VERSO_API_SCRIPT);
+                } else if status == LoadStatus::Complete {
                     self.window.request_redraw();
                     send_to_constellation(sender, ConstellationMsg::FocusWebView(panel_id));
 
+                    if let Some(tab_id) = self.tab_manager.current_tab_id() {
+                        // The window was opened for a page's new webview, which is its only tab.
+                        let cmd = format!(
+                            "window.navbar.addTab('{}', true)",
+                            serde_json::to_string(&tab_id).unwrap()
+                        );
+                        let _ = execute_script(sender, &panel_id, cmd);
+                        self.activate_tab(compositor, tab_id, false);
+                        return false;
+                    }
+
+                    let restored_urls =
+                        std::mem::take(&mut self.panel.as_mut().unwrap().restored_urls);
+                    for url in restored_urls {
+                        self.create_tab(sender, url);
+                    }
+                    // Created last so it's the active tab.
                     self.create_tab(sender, self.panel.as_ref().unwrap().initial_url.clone());
                 } else {
                     log::trace!("Verso Panel ignores NotifyLoadStatusChanged status: {status:?}");
@@ -381,7 +754,7 @@ impl Window {
                             send_to_constellation(
                                 sender,
                                 ConstellationMsg::NewWebView(
-                                    ServoUrl::parse("https://example.com").unwrap(),
+                                    ServoUrl::parse(NEWTAB_URL).unwrap(),
                                     webview_id,
                                 ),
                             );
@@ -391,10 +764,48 @@ impl Window {
                             };
                             let _ = prompt_sender.send(Some(result.to_json()));
                             return false;
+                        } else if msg == "GET_SETTINGS" {
+                            let _ =
+                                prompt_sender.send(serde_json::to_string(&self.settings()).ok());
+                            return false;
                         }
 
                         let _ = prompt_sender.send(None);
 
+                        if let Some(settings) = msg.strip_prefix("SET_SETTINGS:") {
+                            let result = serde_json::from_str::<Settings>(settings)
+                                .map_err(|e| e.to_string())
+                                .and_then(|settings| self.apply_settings(settings));
+                            if let Err(error) = result {
+                                log::error!("Failed to change settings from panel: {error}")
+                            }
+                            return false;
+                        }
+                        if let Some(query) = msg.strip_prefix("SUGGEST:") {
+                            self.suggestions_requested = Some(query.to_owned());
+                            return false;
+                        }
+                        if let Some(decision) = msg.strip_prefix("POPUP_DECISION:") {
+                            match serde_json::from_str::<PopupDecision>(decision) {
+                                Ok(decision) => self.decide_blocked_popup(sender, decision),
+                                Err(error) => {
+                                    log::error!(
+                                        "Failed to parse popup decision from panel: {error}"
+                                    )
+                                }
+                            }
+                            return false;
+                        }
+                        if let Some(action) = msg.strip_prefix("RUN_ACTION:") {
+                            match serde_json::from_str::<KeyAction>(action) {
+                                Ok(action) => self.run_key_action(compositor, action),
+                                Err(error) => {
+                                    log::error!("Failed to parse action from panel: {error}")
+                                }
+                            }
+                            return false;
+                        }
+
                         /* Window */
                         match msg.as_str() {
                             "NEW_WINDOW" => {
@@ -417,27 +828,35 @@ impl Window {
                             _ => {}
                         }
 
+                        // The panel sits at the window's origin, so its regions are already in
+                        // window coordinates.
+                        if let Some(regions) = msg.strip_prefix("SET_DRAG_REGIONS:") {
+                            match serde_json::from_str(regions) {
+                                Ok(regions) => self.drag_regions = regions,
+                                Err(error) => {
+                                    log::error!("Failed to parse drag regions from panel: {error}")
+                                }
+                            }
+                            return false;
+                        }
+
                         /* Main WebView */
                         if let Some(tab) = self.tab_manager.current_tab() {
                             let id = tab.id();
-                            if msg.starts_with("NAVIGATE_TO:") {
-                                let unparsed_url = msg.strip_prefix("NAVIGATE_TO:").unwrap();
-                                let url = match Url::parse(unparsed_url) {
-                                    Ok(url_parsed) => url_parsed,
-                                    Err(e) => {
-                                        if e == url::ParseError::RelativeUrlWithoutBase {
-                                            Url::parse(&format!("https://{}", unparsed_url))
-                                                .unwrap()
-                                        } else {
-                                            panic!("Verso Panel failed to parse URL: {}", e);
-                                        }
-                                    }
+                            if let Some(input) = msg.strip_prefix("NAVIGATE_TO:") {
+                                // Input which isn't a URL is searched for.
+                                let Some(url) = self.search_engines.resolve(input) else {
+                                    log::error!("Verso Panel failed to parse URL: {input}");
+                                    return false;
                                 };
 
-                                send_to_constellation(
-                                    sender,
-                                    ConstellationMsg::LoadUrl(id, ServoUrl::from_url(url)),
-                                );
+                                let url = self.upgrade_insecure_url(ServoUrl::from_url(url));
+                                if self.allows_navigation(&url) {
+                                    send_to_constellation(
+                                        sender,
+                                        ConstellationMsg::LoadUrl(id, url),
+                                    );
+                                }
                             } else {
                                 match msg.as_str() {
                                     "PREV" => {
@@ -463,6 +882,7 @@ impl Window {
                                     "REFRESH" => {
                                         send_to_constellation(sender, ConstellationMsg::Reload(id));
                                     }
+                                    "HOME" => self.go_home(sender, id),
                                     e => log::trace!(
                                         "Verso Panel isn't supporting this prompt message yet: {e}"
                                     ),
@@ -561,6 +981,40 @@ impl Window {
         false
     }
 
+    /// Handle servo messages with a webview opened by a page which loads in its opener instead.
+    pub fn handle_servo_messages_with_redirect(
+        &mut self,
+        webview_id: WebViewId,
+        opener_id: WebViewId,
+        message: EmbedderMsg,
+        sender: &Sender<ConstellationMsg>,
+    ) {
+        log::trace!(
+            "Verso redirected WebView {webview_id:?} is handling Embedder message: {message:?}",
+        );
+        match message {
+            EmbedderMsg::AllowNavigationRequest(_webview_id, id, url) => {
+                send_to_constellation(sender, ConstellationMsg::AllowNavigationResponse(id, false));
+                send_to_constellation(sender, ConstellationMsg::CloseWebView(webview_id));
+                let url = self.upgrade_insecure_url(url);
+                if !self.allows_navigation(&url) {
+                    return;
+                }
+                if external::is_external(url.as_url()) {
+                    self.confirm_open_externally(sender, opener_id, url.into_url());
+                } else {
+                    send_to_constellation(sender, ConstellationMsg::LoadUrl(opener_id, url));
+                }
+            }
+            EmbedderMsg::WebViewClosed(_) => {
+                self.redirected_webviews.remove(&webview_id);
+            }
+            e => {
+                log::trace!("Verso redirected WebView ignores this message: {e:?}")
+            }
+        }
+    }
+
     /// Handle servo messages with prompt. Return true it requests a new window.
     pub fn handle_servo_messages_with_prompt(
         &mut self,
@@ -672,6 +1126,31 @@ impl Window {
     }
 }
 
+/// Execute a script on this webview without waiting for it. Used for pages whose event loop may
+/// be paused, Verso would hang until it's resumed otherwise.
+pub fn send_script(
+    constellation_sender: &Sender<ConstellationMsg>,
+    webview: &WebViewId,
+    js: impl ToString,
+) {
+    let (result_sender, result_receiver) = ipc::channel::<WebDriverJSResult>().unwrap();
+    send_to_constellation(
+        constellation_sender,
+        ConstellationMsg::WebDriverCommand(script_traits::WebDriverCommandMsg::ScriptCommand(
+            webview.0,
+            WebDriverScriptCommand::ExecuteScript(js.to_string(), result_sender),
+        )),
+    );
+    ROUTER.add_typed_route(
+        result_receiver,
+        Box::new(|result| {
+            if let Ok(Err(error)) = result {
+                log::warn!("Script sent to a webview failed: {error:?}");
+            }
+        }),
+    );
+}
+
 /// Blocking execute a script on this webview
 pub fn execute_script(
     constellation_sender: &Sender<ConstellationMsg>,
@@ -1,12 +1,17 @@
-use std::{cell::Cell, collections::HashMap};
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
 
 use base::id::WebViewId;
 use compositing_traits::ConstellationMsg;
 use crossbeam_channel::Sender;
 use embedder_traits::{
-    AllowOrDeny, ContextMenuResult, Cursor, EmbedderMsg, InputEvent, MouseButton,
-    MouseButtonAction, MouseButtonEvent, MouseMoveEvent, PromptResult, TouchEventAction,
-    TraversalDirection, WebResourceResponseMsg, WheelMode,
+    AllowOrDeny, ContextMenuResult, Cursor, EmbedderMsg, ImeEvent, InputEvent, MouseButton,
+    MouseButtonAction, MouseButtonEvent, MouseMoveEvent, PromptResult, TouchEvent,
+    TouchEventAction, TouchId, TraversalDirection, WebResourceResponseMsg, WheelMode,
 };
 use euclid::{Point2D, Size2D};
 use glutin::{
@@ -15,47 +20,92 @@ use glutin::{
 };
 use glutin_winit::DisplayBuilder;
 use ipc_channel::ipc::IpcSender;
-use keyboard_types::{Code, KeyState, KeyboardEvent, Modifiers};
+use keyboard_types::{Code, CompositionEvent, CompositionState, KeyState, KeyboardEvent};
 #[cfg(any(target_os = "macos", target_os = "windows"))]
 use muda::{Menu as MudaMenu, MenuEvent, MenuEventReceiver, MenuItem};
+use net_traits::pub_domains::reg_host;
 #[cfg(any(target_os = "macos", target_os = "windows"))]
 use raw_window_handle::HasWindowHandle;
 use script_traits::webdriver_msg::WebDriverJSValue;
+use servo_config::opts;
 use servo_url::ServoUrl;
-use versoview_messages::ToControllerMessage;
+use versoview_messages::{
+    ColorScheme, DragRegion, KeyAction, NavigationPolicy, NewWebViewPolicy, SecurityInfo,
+    SecurityState, ToControllerMessage, WebViewKind, WindowShape, WindowState,
+};
 use webrender_api::{
     units::{DeviceIntPoint, DeviceIntRect, DeviceIntSize, DevicePoint, LayoutVector2D},
-    ScrollLocation,
+    ImageKey, ScrollLocation,
 };
 #[cfg(any(linux, target_os = "windows"))]
 use winit::window::ResizeDirection;
 use winit::{
-    dpi::PhysicalPosition,
-    event::{ElementState, TouchPhase, WindowEvent},
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{ElementState, Ime, TouchPhase, WindowEvent},
     event_loop::ActiveEventLoop,
     keyboard::ModifiersState,
-    window::{CursorIcon, Window as WinitWindow, WindowAttributes, WindowId},
+    window::{CursorIcon, Theme, Window as WinitWindow, WindowAttributes, WindowId},
 };
 
 use crate::{
-    compositor::IOCompositor,
+    clipboard::Clipboard,
+    compositor::{IOCompositor, SWIPE_NAVIGATION_MIN_SCREEN_PX},
+    external,
+    gesture::{GestureAction, GestureResult, MouseGestureHandler},
+    http,
+    keybinding::KeyBindings,
     keyboard::keyboard_event_from_winit,
-    rendering::{gl_config_picker, RenderingContext},
+    navigation_policy,
+    newtab::NEWTAB_URL,
+    popup_blocker::{
+        PopupDecision, PopupExceptions, POPUP_INDICATOR_SCRIPT, USER_ACTIVATION_DURATION,
+    },
+    rendering::{gl_config_picker, RenderingContext, RenderingSettings},
+    search::SearchEngines,
+    settings::{servo_prefs, set_servo_prefs, Settings},
+    stats::resident_memory,
     tab::TabManager,
     verso::send_to_constellation,
     webview::{
         context_menu::{ContextMenu, Menu},
         execute_script,
         prompt::PromptSender,
-        Panel, WebView,
+        send_script, Panel, WebView,
     },
 };
 
-use arboard::Clipboard;
-
 const PANEL_HEIGHT: f64 = 50.0;
 const TAB_HEIGHT: f64 = 30.0;
 const PANEL_PADDING: f64 = 4.0;
+/// Zoom factor applied by each zoom in or out keyboard shortcut.
+const ZOOM_STEP: f32 = 1.1;
+/// How often the statistics overlay in the panel is refreshed.
+const STATS_INTERVAL: Duration = Duration::from_secs(1);
+/// Load progress shown once a document starts loading.
+pub(crate) const LOAD_PROGRESS_STARTED: f32 = 0.25;
+/// Load progress shown once the head of a document is parsed.
+pub(crate) const LOAD_PROGRESS_HEAD_PARSED: f32 = 0.6;
+/// Prompt which a paused page raises, its event loop waits until Verso answers it.
+pub(crate) const PAUSE_PROMPT: &str = "VERSO_PAUSE";
+/// Prompt which the certificate error page raises before allowing the certificate of its host.
+pub(crate) const CERTIFICATE_EXCEPTION_PROMPT: &str = "VERSO_CERTIFICATE_EXCEPTION";
+/// Hosts whose invalid certificate was allowed temporarily. Like Servo's list of allowed
+/// certificates, it's shared by all windows and lasts until Verso exits.
+pub(crate) static CERTIFICATE_EXCEPTIONS: LazyLock<Mutex<HashSet<String>>> =
+    LazyLock::new(Default::default);
+/// Prompt which the network error page raises with its URL, so HTTPS-only mode can offer the
+/// `http` version of a page which failed to load over `https`.
+pub(crate) const HTTPS_LOAD_FAILED_PROMPT: &str = "VERSO_HTTPS_LOAD_FAILED:";
+/// Prompt which the HTTPS-only error page raises when the user continues over `http`.
+pub(crate) const HTTP_FALLBACK_PROMPT: &str = "VERSO_HTTP_FALLBACK";
+/// Hosts which the user chose to load over `http` in HTTPS-only mode. Like
+/// [`CERTIFICATE_EXCEPTIONS`], it's shared by all windows and lasts until Verso exits.
+pub(crate) static HTTP_EXCEPTIONS: LazyLock<Mutex<HashSet<String>>> =
+    LazyLock::new(Default::default);
+
+/// Pages waiting for the controller to decide where their new webviews open, with their ids and
+/// response senders by request id.
+pub(crate) type NewWebViewRequests = HashMap<uuid::Uuid, (WebViewId, IpcSender<Option<WebViewId>>)>;
 
 #[derive(Default)]
 pub(crate) struct EventListeners {
@@ -66,6 +116,12 @@ pub(crate) struct EventListeners {
         Option<HashMap<uuid::Uuid, (ServoUrl, IpcSender<WebResourceResponseMsg>)>>,
     /// This is `true` if the controller wants to get and handle WindowEvent::CloseRequested
     pub(crate) on_close_requested: bool,
+    /// This is `Some` if the controller wants to get notified on window state changes,
+    /// it holds the last state reported to the controller
+    pub(crate) on_window_state_changed: Option<Option<WindowState>>,
+    /// This is `Some` if the controller wants to get notified on security state changes,
+    /// it holds the last security info reported to the controller
+    pub(crate) on_security_state_changed: Option<Option<SecurityInfo>>,
 }
 
 /// A Verso window is a Winit window containing several web views.
@@ -78,6 +134,45 @@ pub struct Window {
     pub(crate) panel: Option<Panel>,
     /// The WebView of this window.
     // pub(crate) webview: Option<WebView>,
+    /// The webview which requested element fullscreen, it covers the whole window while it's set.
+    /// The boolean is `true` if the window was already fullscreen before the request.
+    pub(crate) fullscreen_webview: Option<(WebViewId, bool)>,
+    /// Mouse gesture recognizer, `None` if mouse gestures are disabled.
+    pub(crate) mouse_gestures: Option<MouseGestureHandler>,
+    /// Whether navigations to `http` URLs are upgraded to `https`.
+    pub(crate) https_only: bool,
+    /// Whether the settings page changed HTTPS-only mode, for Verso to share it with all windows
+    /// and save it.
+    pub(crate) https_only_changed: bool,
+    /// Tabs opened for captive portal logins, which HTTPS-only mode doesn't upgrade since portals
+    /// serve their login pages over plain http.
+    pub(crate) captive_portal_tabs: HashSet<WebViewId>,
+    /// URL patterns which pages can and can't navigate to.
+    pub(crate) navigation_policy: NavigationPolicy,
+    /// Where pages open new webviews with `window.open` or `target="_blank"` links.
+    pub(crate) new_webview_policy: NewWebViewPolicy,
+    /// Whether popups which pages open without user activation are blocked.
+    pub(crate) block_popups: bool,
+    /// Origins which can open popups without user activation.
+    pub(crate) popup_exceptions: PopupExceptions,
+    /// The tab the user last clicked or typed in and when, it can open a popup for a short while.
+    user_activation: Option<(WebViewId, Instant)>,
+    /// Tabs which can open their next popup without user activation.
+    popups_allowed_once: HashSet<WebViewId>,
+    /// The tab whose popup was blocked last and the origin of its page, shown in the panel until
+    /// the user decides.
+    blocked_popup: Option<(WebViewId, Option<String>)>,
+    /// Origins the user always allows popups from, Verso saves them and clears them.
+    pub(crate) allowed_popup_origins: Vec<String>,
+    /// Tabs paused by the controller, with the pause prompt blocking their event loop once the
+    /// page has raised it.
+    pub(crate) paused_webviews: HashMap<WebViewId, Option<IpcSender<Option<String>>>>,
+    /// The current tab, its URL and its title when they were last checked.
+    current_page: Option<(WebViewId, ServoUrl, Option<String>)>,
+    /// Search engines for URL bar input which isn't a URL.
+    pub(crate) search_engines: SearchEngines,
+    /// Page loaded by the Home action and by panels without an initial URL.
+    pub(crate) homepage: ServoUrl,
     /// Script to run on document started to load
     pub(crate) init_script: Option<String>,
     /// Event listeners registered from the webview controller
@@ -88,6 +183,22 @@ pub struct Window {
     modifiers_state: Cell<ModifiersState>,
     /// State to indicate if the window is resizing.
     pub(crate) resizing: bool,
+    /// Set when a keyboard shortcut asks for a new window, Verso creates it and resets the flag.
+    pub(crate) new_window_requested: bool,
+    /// Set when a keyboard shortcut asks to quit, Verso shuts down gracefully.
+    pub(crate) quit_requested: bool,
+    /// URL bar input the panel wants search suggestions for, Verso fetches them and resets it.
+    pub(crate) suggestions_requested: Option<String>,
+    /// URLs blocked by the navigation policy, Verso reports them to the controller and clears them.
+    pub(crate) blocked_navigations: Vec<url::Url>,
+    /// Pages asking for a new webview in a new window, Verso creates the windows and responds.
+    pub(crate) opened_window_requests: Vec<IpcSender<Option<WebViewId>>>,
+    /// Webviews opened by pages which load in their opener instead, mapped to the opener.
+    pub(crate) redirected_webviews: HashMap<WebViewId, WebViewId>,
+    /// Whether the panel shows the compositor statistics overlay.
+    pub(crate) stats_overlay: bool,
+    /// When the statistics overlay was last refreshed.
+    pub(crate) stats_updated: Option<Instant>,
     // TODO: These two fields should unified once we figure out servo's menu events.
     /// Context menu webview. This is only used in wayland currently.
     #[cfg(linux)]
@@ -97,44 +208,64 @@ pub struct Window {
     pub(crate) menu_event_receiver: MenuEventReceiver,
     /// Window tabs manager
     pub(crate) tab_manager: TabManager,
+    /// Areas of the window which move the window when dragged, in logical pixels.
+    pub(crate) drag_regions: Vec<DragRegion>,
+    /// Shape the composited output is masked to, see [`IOCompositor::set_window_shape`].
+    pub(crate) shape: WindowShape,
+    /// WebRender image of the alpha mask when the shape is a [`WindowShape::Mask`].
+    pub(crate) shape_mask: Option<ImageKey>,
     pub(crate) focused_webview_id: Option<WebViewId>,
 }
 
 impl Window {
     /// Create a Verso window from Winit window and return the rendering context.
+    ///
+    /// If no GL context can be created with the provided rendering settings, it will try the
+    /// fallbacks of [`RenderingSettings`] until one works.
     pub fn new(
         evl: &ActiveEventLoop,
         window_attributes: WindowAttributes,
+        rendering_settings: RenderingSettings,
     ) -> (Self, RenderingContext) {
-        let window_attributes = window_attributes
-            .with_transparent(true)
-            .with_decorations(false);
-
-        let template = ConfigTemplateBuilder::new()
-            .with_alpha_size(8)
-            .with_transparency(cfg!(macos));
-
-        let (window, gl_config) = DisplayBuilder::new()
-            .with_window_attributes(Some(window_attributes))
-            .build(evl, template, gl_config_picker)
-            .expect("Failed to create window and gl config");
-
-        let window = window.ok_or("Failed to create window").unwrap();
-
-        log::debug!("Picked a config with {} samples", gl_config.num_samples());
+        #[cfg(macos)]
+        let decorated = window_attributes.decorations;
+        let mut settings = rendering_settings;
+        let (window, rendering_context, surface) = loop {
+            let result = match settings.backend.unavailable_reason() {
+                Some(reason) => Err(reason.into()),
+                None => Self::create_window_and_context(evl, window_attributes.clone(), settings),
+            };
+            match result {
+                Ok(result) => break result,
+                Err(error) => {
+                    log::warn!(
+                        "Failed to create rendering context with the {:?} GL backend: {error}",
+                        settings.backend
+                    );
+                    let Some(fallback) = settings.fallback() else {
+                        panic!(
+                            "Failed to create window and rendering context: {error}. With Mesa \
+                             GL drivers, --software-rendering may work"
+                        );
+                    };
+                    log::warn!("Falling back to the {:?} GL backend", fallback.backend);
+                    settings = fallback;
+                }
+            }
+        };
 
         #[cfg(macos)]
-        unsafe {
+        if !decorated {
             let rwh = window.window_handle().expect("Failed to get window handle");
             if let RawWindowHandle::AppKit(AppKitWindowHandle { ns_view, .. }) = rwh.as_ref() {
-                decorate_window(
-                    ns_view.as_ptr() as *mut AnyObject,
-                    LogicalPosition::new(8.0, 40.0),
-                );
+                unsafe {
+                    decorate_window(
+                        ns_view.as_ptr() as *mut AnyObject,
+                        LogicalPosition::new(8.0, 40.0),
+                    );
+                }
             }
         }
-        let (rendering_context, surface) = RenderingContext::create(&window, &gl_config)
-            .expect("Failed to create rendering context");
         log::trace!("Created rendering context for window {:?}", window);
 
         (
@@ -142,39 +273,96 @@ impl Window {
                 window,
                 surface,
                 panel: None,
+                fullscreen_webview: None,
+                mouse_gestures: None,
+                https_only: false,
+                https_only_changed: false,
+                captive_portal_tabs: HashSet::new(),
+                navigation_policy: NavigationPolicy::default(),
+                new_webview_policy: NewWebViewPolicy::default(),
+                block_popups: true,
+                popup_exceptions: PopupExceptions::default(),
+                user_activation: None,
+                popups_allowed_once: HashSet::new(),
+                blocked_popup: None,
+                allowed_popup_origins: Vec::new(),
+                paused_webviews: HashMap::new(),
+                current_page: None,
+                search_engines: SearchEngines::default(),
+                homepage: ServoUrl::parse(NEWTAB_URL).unwrap(),
                 init_script: None,
                 event_listeners: Default::default(),
                 mouse_position: Default::default(),
                 modifiers_state: Cell::new(ModifiersState::default()),
                 resizing: false,
+                new_window_requested: false,
+                quit_requested: false,
+                suggestions_requested: None,
+                blocked_navigations: Vec::new(),
+                opened_window_requests: Vec::new(),
+                redirected_webviews: HashMap::new(),
+                stats_overlay: false,
+                stats_updated: None,
                 #[cfg(linux)]
                 context_menu: None,
                 #[cfg(any(target_os = "macos", target_os = "windows"))]
                 menu_event_receiver: MenuEvent::receiver().clone(),
                 tab_manager: TabManager::new(),
+                drag_regions: Vec::new(),
+                shape: WindowShape::default(),
+                shape_mask: None,
                 focused_webview_id: None,
             },
             rendering_context,
         )
     }
 
+    /// Create the Winit window together with its GL display, context and surface.
+    fn create_window_and_context(
+        evl: &ActiveEventLoop,
+        window_attributes: WindowAttributes,
+        settings: RenderingSettings,
+    ) -> Result<(WinitWindow, RenderingContext, Surface<WindowSurface>), Box<dyn std::error::Error>>
+    {
+        let template = ConfigTemplateBuilder::new()
+            .with_alpha_size(8)
+            .with_transparency(window_attributes.transparent);
+
+        let (window, gl_config) = DisplayBuilder::new()
+            .with_preference(settings.backend.api_preference())
+            .with_window_attributes(Some(window_attributes))
+            .build(evl, template, gl_config_picker)?;
+
+        let window = window.ok_or("Failed to create window")?;
+
+        log::debug!("Picked a config with {} samples", gl_config.num_samples());
+
+        let (rendering_context, surface) = RenderingContext::create(&window, &gl_config)?;
+        Ok((window, rendering_context, surface))
+    }
+
     /// Create a Verso window with the rendering context.
-    pub fn new_with_compositor(evl: &ActiveEventLoop, compositor: &mut IOCompositor) -> Self {
-        let window_attrs = WinitWindow::default_attributes()
-            .with_decorations(false)
-            .with_transparent(true);
+    pub fn new_with_compositor(
+        evl: &ActiveEventLoop,
+        window_attributes: WindowAttributes,
+        compositor: &mut IOCompositor,
+    ) -> Self {
+        #[cfg(macos)]
+        let decorated = window_attributes.decorations;
         let window = evl
-            .create_window(window_attrs)
+            .create_window(window_attributes)
             .expect("Failed to create window.");
 
         #[cfg(macos)]
-        unsafe {
+        if !decorated {
             let rwh = window.window_handle().expect("Failed to get window handle");
             if let RawWindowHandle::AppKit(AppKitWindowHandle { ns_view, .. }) = rwh.as_ref() {
-                decorate_window(
-                    ns_view.as_ptr() as *mut AnyObject,
-                    LogicalPosition::new(8.0, 40.0),
-                );
+                unsafe {
+                    decorate_window(
+                        ns_view.as_ptr() as *mut AnyObject,
+                        LogicalPosition::new(8.0, 40.0),
+                    );
+                }
             }
         }
         let surface = compositor
@@ -187,16 +375,44 @@ impl Window {
             surface,
             panel: None,
             // webview: None,
+            fullscreen_webview: None,
+            mouse_gestures: None,
+            https_only: false,
+            https_only_changed: false,
+            captive_portal_tabs: HashSet::new(),
+            navigation_policy: NavigationPolicy::default(),
+            new_webview_policy: NewWebViewPolicy::default(),
+            block_popups: true,
+            popup_exceptions: PopupExceptions::default(),
+            user_activation: None,
+            popups_allowed_once: HashSet::new(),
+            blocked_popup: None,
+            allowed_popup_origins: Vec::new(),
+            paused_webviews: HashMap::new(),
+            current_page: None,
+            search_engines: SearchEngines::default(),
+            homepage: ServoUrl::parse(NEWTAB_URL).unwrap(),
             init_script: None,
             event_listeners: Default::default(),
             mouse_position: Default::default(),
             modifiers_state: Cell::new(ModifiersState::default()),
             resizing: false,
+            new_window_requested: false,
+            quit_requested: false,
+            suggestions_requested: None,
+            blocked_navigations: Vec::new(),
+            opened_window_requests: Vec::new(),
+            redirected_webviews: HashMap::new(),
+            stats_overlay: false,
+            stats_updated: None,
             #[cfg(linux)]
             context_menu: None,
             #[cfg(any(target_os = "macos", target_os = "windows"))]
             menu_event_receiver: MenuEvent::receiver().clone(),
             tab_manager: TabManager::new(),
+            drag_regions: Vec::new(),
+            shape: WindowShape::default(),
+            shape_mask: None,
             focused_webview_id: None,
         };
         compositor.swap_current_window(&mut window);
@@ -205,7 +421,13 @@ impl Window {
 
     /// Get the content area size for the webview to draw on
     pub fn get_content_size(&self, mut size: DeviceIntRect, include_tab: bool) -> DeviceIntRect {
-        if self.panel.is_some() {
+        if self.fullscreen_webview.is_some() {
+            return size;
+        }
+        if let Some(height) = self.panel.as_ref().and_then(|panel| panel.height) {
+            // A custom chrome page draws its own tab bar and margins.
+            size.min.y = size.max.y.min((height * self.scale_factor()) as i32);
+        } else if self.panel.is_some() {
             let height: f64 = if include_tab {
                 (PANEL_HEIGHT + TAB_HEIGHT + PANEL_PADDING) * self.scale_factor()
             } else {
@@ -219,25 +441,29 @@ impl Window {
         size
     }
 
-    /// Send the constellation message to start Panel UI
+    /// Send the constellation message to start Panel UI, `chrome` is the page to load instead of
+    /// the built-in panel and the height it takes in logical pixels.
     pub fn create_panel(
         &mut self,
         constellation_sender: &Sender<ConstellationMsg>,
         initial_url: Option<url::Url>,
+        chrome: Option<(ServoUrl, f64)>,
     ) {
         let size = self.window.inner_size();
         let size = Size2D::new(size.width as i32, size.height as i32);
         let panel_id = WebViewId::new();
         self.panel = Some(Panel {
             webview: WebView::new(panel_id, DeviceIntRect::from_size(size)),
-            initial_url: if let Some(initial_url) = initial_url {
-                ServoUrl::from_url(initial_url)
-            } else {
-                ServoUrl::parse("https://example.com").unwrap()
-            },
+            initial_url: initial_url
+                .map(ServoUrl::from_url)
+                .unwrap_or_else(|| self.homepage.clone()),
+            restored_urls: Vec::new(),
+            height: chrome.as_ref().map(|(_, height)| *height),
         });
 
-        let url = ServoUrl::parse("verso://resources/components/panel.html").unwrap();
+        let url = chrome
+            .map(|(url, _)| url)
+            .unwrap_or_else(|| ServoUrl::parse("verso://resources/components/panel.html").unwrap());
         send_to_constellation(
             constellation_sender,
             ConstellationMsg::NewWebView(url, panel_id),
@@ -250,7 +476,49 @@ impl Window {
         constellation_sender: &Sender<ConstellationMsg>,
         initial_url: ServoUrl,
     ) {
+        let initial_url = self.upgrade_insecure_url(initial_url);
+        self.open_tab(constellation_sender, initial_url);
+    }
+
+    /// Open the login page of a captive portal in a new tab if the navigation policy allows it.
+    /// Its URL comes from the network, so only `http` and `https` pages are opened.
+    pub(crate) fn open_captive_portal(
+        &mut self,
+        constellation_sender: &Sender<ConstellationMsg>,
+        portal: ServoUrl,
+    ) {
+        if !matches!(portal.scheme(), "http" | "https") {
+            log::warn!("Not opening captive portal {portal}, it isn't a web page");
+            return;
+        }
+        if !self.allows_navigation(&portal) {
+            return;
+        }
+        let webview_id = self.open_tab(constellation_sender, portal);
+        self.captive_portal_tabs.insert(webview_id);
+    }
+
+    fn open_tab(
+        &mut self,
+        constellation_sender: &Sender<ConstellationMsg>,
+        url: ServoUrl,
+    ) -> WebViewId {
         let webview_id = WebViewId::new();
+        self.add_tab(constellation_sender, webview_id);
+        send_to_constellation(
+            constellation_sender,
+            ConstellationMsg::NewWebView(url, webview_id),
+        );
+        log::debug!("Verso Window {:?} adds webview {}", self.id(), webview_id);
+        webview_id
+    }
+
+    /// Add a tab for the webview to the tab manager and the panel, without creating the webview
+    pub(crate) fn add_tab(
+        &mut self,
+        constellation_sender: &Sender<ConstellationMsg>,
+        webview_id: WebViewId,
+    ) {
         let size = self.size();
         let rect = DeviceIntRect::from_size(size);
 
@@ -271,16 +539,215 @@ impl Window {
         }
 
         self.tab_manager.append_tab(webview, true);
+    }
 
-        send_to_constellation(
-            constellation_sender,
-            ConstellationMsg::NewWebView(initial_url, webview_id),
+    /// Respond to a page asking for a new webview with the id Servo creates it with, or `None` to
+    /// deny it.
+    pub(crate) fn open_new_webview(
+        &mut self,
+        constellation_sender: &Sender<ConstellationMsg>,
+        opener_id: WebViewId,
+        policy: NewWebViewPolicy,
+        response_sender: IpcSender<Option<WebViewId>>,
+    ) {
+        let webview_id = match policy {
+            NewWebViewPolicy::NewTab => {
+                let webview_id = WebViewId::new();
+                self.add_tab(constellation_sender, webview_id);
+                Some(webview_id)
+            }
+            NewWebViewPolicy::NewWindow => {
+                self.opened_window_requests.push(response_sender);
+                return;
+            }
+            NewWebViewPolicy::SameWebView => {
+                // Servo doesn't tell which page is opened, so let it create a webview which is
+                // closed once it starts loading and load the page in the opener instead.
+                let webview_id = WebViewId::new();
+                self.redirected_webviews.insert(webview_id, opener_id);
+                Some(webview_id)
+            }
+            NewWebViewPolicy::Deny => None,
+        };
+        if let Err(error) = response_sender.send(webview_id) {
+            log::error!("Verso failed to respond to AllowOpeningWebView: {error}");
+        }
+    }
+
+    /// Check if a popup the page wants to open is blocked since the user didn't click or type in
+    /// the tab just before, and show it in the panel if so.
+    pub(crate) fn blocks_popup(
+        &mut self,
+        constellation_sender: &Sender<ConstellationMsg>,
+        webview_id: WebViewId,
+    ) -> bool {
+        if !self.block_popups {
+            return false;
+        }
+        // Like in other browsers, a click or key press lets the page open a single popup.
+        if self
+            .user_activation
+            .is_some_and(|(id, time)| id == webview_id && time.elapsed() < USER_ACTIVATION_DURATION)
+        {
+            self.user_activation = None;
+            return false;
+        }
+        if self.popups_allowed_once.remove(&webview_id) {
+            return false;
+        }
+        let origin = self
+            .tab_manager
+            .history(webview_id)
+            .and_then(|history| history.list.get(history.current_idx))
+            .map(|url| url.as_url().origin())
+            .filter(url::Origin::is_tuple)
+            .map(|origin| origin.ascii_serialization());
+        if origin
+            .as_deref()
+            .is_some_and(|origin| self.popup_exceptions.contains(origin))
+        {
+            return false;
+        }
+        log::info!(
+            "Blocked a popup opened without user activation by {}",
+            origin.as_deref().unwrap_or("a page without origin")
         );
-        log::debug!("Verso Window {:?} adds webview {}", self.id(), webview_id);
+        self.blocked_popup = Some((webview_id, origin));
+        self.update_popup_indicator(constellation_sender);
+        true
+    }
+
+    /// Apply what the user chose for the blocked popup shown in the panel.
+    pub(crate) fn decide_blocked_popup(
+        &mut self,
+        constellation_sender: &Sender<ConstellationMsg>,
+        decision: PopupDecision,
+    ) {
+        let Some((webview_id, origin)) = self.blocked_popup.take() else {
+            return;
+        };
+        match decision {
+            PopupDecision::AllowOnce => {
+                self.popups_allowed_once.insert(webview_id);
+            }
+            PopupDecision::AllowSite => match origin {
+                Some(origin) => self.allowed_popup_origins.push(origin),
+                // An opaque origin can't be allowed again, so only allow the tab once.
+                None => {
+                    self.popups_allowed_once.insert(webview_id);
+                }
+            },
+            PopupDecision::Dismiss => {}
+        }
+        self.update_popup_indicator(constellation_sender);
+    }
+
+    /// Close the window while others stay open. It's hidden right away, and the compositor
+    /// removes it once the constellation has closed its tabs.
+    pub(crate) fn close(&mut self, constellation_sender: &Sender<ConstellationMsg>) {
+        log::debug!("Verso Window {:?} is closing", self.id());
+        self.resume_all_webviews();
+        self.window.set_visible(false);
+        let panel_id = self.panel.as_ref().map(|panel| panel.webview.webview_id);
+        let webview_ids = self
+            .webview_ids()
+            .into_iter()
+            .filter(|id| Some(*id) != panel_id);
+        // The panel goes last, closing it would close the tabs again.
+        for webview_id in webview_ids.chain(panel_id) {
+            send_to_constellation(
+                constellation_sender,
+                ConstellationMsg::CloseWebView(webview_id),
+            );
+        }
+    }
+
+    /// Pause the event loop of a tab like a debugger would, timers, input and network callbacks
+    /// wait until it's resumed. The page raises a prompt which Verso doesn't answer until then, so
+    /// other pages sharing its event loop, like tabs of the same site, are paused too.
+    pub(crate) fn pause_webview(
+        &mut self,
+        constellation_sender: &Sender<ConstellationMsg>,
+        webview_id: WebViewId,
+    ) {
+        if self.paused_webviews.contains_key(&webview_id) {
+            return;
+        }
+        self.paused_webviews.insert(webview_id, None);
+        // The prompt is raised from a task, Verso would wait on the script otherwise. The tab may
+        // share its event loop with one which is already paused, so don't wait on it either.
+        let script = format!("void setTimeout(() => window.prompt('{PAUSE_PROMPT}'))");
+        send_script(constellation_sender, &webview_id, script);
+    }
+
+    /// Sites of the paused tabs. Servo runs the pages of a site in the same event loop, so their
+    /// scripts don't run until the tab is resumed either.
+    pub(crate) fn paused_sites(&self) -> impl Iterator<Item = url::Host> + '_ {
+        self.paused_webviews
+            .keys()
+            .filter_map(|webview_id| reg_host(self.current_url(*webview_id)?))
+    }
+
+    /// Current URL of a tab.
+    pub(crate) fn current_url(&self, webview_id: WebViewId) -> Option<&ServoUrl> {
+        let history = self.tab_manager.history(webview_id)?;
+        history.list.get(history.current_idx)
+    }
+
+    /// Let a paused tab run again.
+    pub(crate) fn resume_webview(&mut self, webview_id: WebViewId) {
+        if let Some(Some(prompt_sender)) = self.paused_webviews.remove(&webview_id) {
+            let _ = prompt_sender.send(None);
+        }
+    }
+
+    /// Let every paused tab run again.
+    pub(crate) fn resume_all_webviews(&mut self) {
+        for (_, prompt_sender) in self.paused_webviews.drain() {
+            if let Some(prompt_sender) = prompt_sender {
+                let _ = prompt_sender.send(None);
+            }
+        }
+    }
+
+    /// Hide the blocked popup of a tab once it loads another page.
+    pub(crate) fn clear_blocked_popup(
+        &mut self,
+        constellation_sender: &Sender<ConstellationMsg>,
+        webview_id: WebViewId,
+    ) {
+        if self
+            .blocked_popup
+            .as_ref()
+            .is_some_and(|(id, _)| *id == webview_id)
+        {
+            self.blocked_popup = None;
+            self.update_popup_indicator(constellation_sender);
+        }
+    }
+
+    /// Show the popup blocked in the current tab in the panel, or hide the indicator if there's
+    /// none.
+    pub(crate) fn update_popup_indicator(&self, constellation_sender: &Sender<ConstellationMsg>) {
+        let Some(panel) = &self.panel else {
+            return;
+        };
+        let origin = self
+            .blocked_popup
+            .as_ref()
+            .filter(|(id, _)| Some(*id) == self.tab_manager.current_tab_id())
+            .map(|(_, origin)| origin.as_deref().unwrap_or("this page"));
+        let cmd = format!(
+            "({POPUP_INDICATOR_SCRIPT})({})",
+            serde_json::to_string(&origin).unwrap()
+        );
+        let _ = execute_script(constellation_sender, &panel.webview.webview_id, cmd);
     }
 
     /// Close a tab
     pub fn close_tab(&mut self, compositor: &mut IOCompositor, tab_id: WebViewId) {
+        // A paused page can't handle being closed.
+        self.resume_webview(tab_id);
         // if there are more than 2 tabs, we need to ask for the new active tab after tab is closed
         if self.tab_manager.count() > 1 {
             if let Some(panel) = &self.panel {
@@ -344,30 +811,251 @@ impl Window {
 
                 // update painting order immediately to draw the active tab
                 compositor.send_root_pipeline_display_list(self);
+                self.update_popup_indicator(&compositor.constellation_chan);
+                self.update_progress_bar(&compositor.constellation_chan);
             }
         }
     }
 
+    /// Record how far the page load of a tab is, and show it in the panel if it's the current tab.
+    pub(crate) fn set_load_progress(
+        &mut self,
+        constellation_sender: &Sender<ConstellationMsg>,
+        tab_id: WebViewId,
+        progress: Option<f32>,
+    ) {
+        self.tab_manager.set_load_progress(tab_id, progress);
+        if self.tab_manager.current_tab_id() == Some(tab_id) {
+            self.update_progress_bar(constellation_sender);
+        }
+    }
+
+    /// Show the load progress of the current tab at the bottom of the panel, the bar fills up and
+    /// disappears once the tab isn't loading.
+    pub(crate) fn update_progress_bar(&self, constellation_sender: &Sender<ConstellationMsg>) {
+        let Some(panel) = &self.panel else {
+            return;
+        };
+        let progress = self
+            .tab_manager
+            .current_tab()
+            .and_then(|tab| tab.load_progress());
+        let cmd = format!(
+            "((progress) => {{
+                let bar = document.getElementById('verso-progress');
+                if (progress === null) {{
+                    if (bar) {{
+                        bar.style.width = '100%';
+                        setTimeout(() => bar.remove(), 200);
+                    }}
+                    return;
+                }}
+                if (!bar) {{
+                    bar = document.createElement('div');
+                    bar.id = 'verso-progress';
+                    bar.style.cssText = 'position: fixed; left: 0; bottom: 0; width: 0; height: 2px; z-index: 2147483647; background: #1a5fb4; transition: width 0.2s; pointer-events: none';
+                    document.body.appendChild(bar);
+                }}
+                bar.style.width = `${{progress * 100}}%`;
+            }})({})",
+            serde_json::to_string(&progress).unwrap()
+        );
+        let _ = execute_script(constellation_sender, &panel.webview.webview_id, cmd);
+    }
+
+    /// Show the statistics overlay in the panel if it's hidden, or hide it otherwise.
+    fn toggle_stats(&mut self, compositor: &IOCompositor) {
+        self.stats_overlay = !self.stats_overlay;
+        self.stats_updated = None;
+        if self.stats_overlay {
+            self.update_stats_overlay(compositor);
+        } else if let Some(panel) = &self.panel {
+            let _ = execute_script(
+                &compositor.constellation_chan,
+                &panel.webview.webview_id,
+                "document.getElementById('verso-stats')?.remove()",
+            );
+        }
+    }
+
+    /// Refresh the statistics overlay in the panel if it's shown and due.
+    pub fn update_stats_overlay(&mut self, compositor: &IOCompositor) {
+        if !self.stats_overlay
+            || self
+                .stats_updated
+                .is_some_and(|updated| updated.elapsed() < STATS_INTERVAL)
+        {
+            return;
+        }
+        let Some(panel) = &self.panel else {
+            return;
+        };
+        self.stats_updated = Some(Instant::now());
+
+        let stats = compositor.frame_stats(self.id());
+        let fps = stats.map_or(0, |stats| stats.fps());
+        let frame_time = stats
+            .and_then(|stats| stats.frame_time())
+            .map_or("-".to_owned(), |time| {
+                format!("{:.1} ms", time.as_secs_f64() * 1000.0)
+            });
+        let memory = resident_memory().map_or("-".to_owned(), |bytes| {
+            format!("{} MiB", bytes / (1024 * 1024))
+        });
+        let text = format!(
+            "{fps} fps | {frame_time} | {memory} | {} pipelines",
+            compositor.pipeline_count()
+        );
+        let cmd = format!(
+            "(() => {{
+                let overlay = document.getElementById('verso-stats');
+                if (!overlay) {{
+                    overlay = document.createElement('div');
+                    overlay.id = 'verso-stats';
+                    overlay.style.cssText = 'position: fixed; top: 2px; right: 8px; z-index: 2147483647; padding: 1px 6px; border-radius: 4px; background: rgba(0, 0, 0, 0.7); color: white; font: 11px monospace; pointer-events: none';
+                    document.body.appendChild(overlay);
+                }}
+                overlay.textContent = {};
+            }})()",
+            serde_json::to_string(&text).unwrap()
+        );
+        let _ = execute_script(
+            &compositor.constellation_chan,
+            &panel.webview.webview_id,
+            cmd,
+        );
+    }
+
+    /// Send search suggestions for URL bar input to the panel, if it defines
+    /// `window.navbar.setSuggestions`.
+    pub fn show_search_suggestions(
+        &self,
+        sender: &Sender<ConstellationMsg>,
+        query: &str,
+        suggestions: &[String],
+    ) {
+        let Some(panel) = &self.panel else {
+            return;
+        };
+        let cmd = format!(
+            "window.navbar?.setSuggestions?.({}, {})",
+            serde_json::to_string(query).unwrap(),
+            serde_json::to_string(suggestions).unwrap()
+        );
+        let _ = execute_script(sender, &panel.webview.webview_id, cmd);
+    }
+
     /// Set the init script that runs on document started to load.
     pub fn set_init_script(&mut self, init_script: Option<String>) {
         self.init_script = init_script;
     }
 
+    /// Enable or disable mouse gestures.
+    pub fn set_mouse_gestures(&mut self, enabled: bool) {
+        self.mouse_gestures = enabled.then(MouseGestureHandler::default);
+    }
+
+    /// Get the settings of this window and the Servo preferences.
+    pub fn settings(&self) -> Settings {
+        Settings {
+            https_only: Some(self.https_only),
+            mouse_gestures: Some(self.mouse_gestures.is_some()),
+            prefs: Some(servo_prefs()),
+        }
+    }
+
+    /// Change the settings of this window and the Servo preferences.
+    pub fn apply_settings(&mut self, settings: Settings) -> Result<(), String> {
+        if let Some(prefs) = settings.prefs {
+            set_servo_prefs(prefs)?;
+        }
+        if let Some(https_only) = settings.https_only {
+            if https_only != self.https_only {
+                self.https_only = https_only;
+                self.https_only_changed = true;
+            }
+        }
+        if let Some(mouse_gestures) = settings.mouse_gestures {
+            if mouse_gestures != self.mouse_gestures.is_some() {
+                self.set_mouse_gestures(mouse_gestures);
+            }
+        }
+        Ok(())
+    }
+
+    /// Load the home page in a tab.
+    pub(crate) fn go_home(&self, sender: &Sender<ConstellationMsg>, tab_id: WebViewId) {
+        let url = self.upgrade_insecure_url(self.homepage.clone());
+        send_to_constellation(sender, ConstellationMsg::LoadUrl(tab_id, url));
+    }
+
+    /// Check a navigation against the navigation policy. Blocked URLs are queued for Verso to
+    /// report to the controller.
+    pub(crate) fn allows_navigation(&mut self, url: &ServoUrl) -> bool {
+        if navigation_policy::is_allowed(&self.navigation_policy, url.as_url()) {
+            return true;
+        }
+        log::info!("Navigation policy blocked the navigation to {url}");
+        self.blocked_navigations.push(url.clone().into_url());
+        false
+    }
+
+    /// Open a URL blocked by the navigation policy in the system browser if the policy says so.
+    /// Only `http` and `https` URLs are, other schemes can start any application. The user is
+    /// asked first if a page in `webview_id` navigated, navigations of the controller are trusted.
+    pub(crate) fn open_blocked_externally(
+        &mut self,
+        sender: &Sender<ConstellationMsg>,
+        webview_id: Option<WebViewId>,
+        url: &ServoUrl,
+    ) {
+        if !self.navigation_policy.open_blocked_externally
+            || !matches!(url.scheme(), "http" | "https")
+        {
+            return;
+        }
+        match webview_id {
+            Some(webview_id) => {
+                self.confirm_open_externally(sender, webview_id, url.as_url().clone())
+            }
+            None => external::open_externally(url.as_url()),
+        }
+    }
+
+    /// Upgrade an `http` URL to `https` if HTTPS-only mode is enabled. Loopback hosts are kept
+    /// as they are, since local servers rarely serve `https`, and so are the hosts the user chose
+    /// to load over `http` on the HTTPS-only error page.
+    pub(crate) fn upgrade_insecure_url(&self, url: ServoUrl) -> ServoUrl {
+        if !self.https_only || url.scheme() != "http" || !needs_https(url.as_url()) {
+            return url;
+        }
+        // The default port is implicit, so it becomes the default https port.
+        let mut upgraded = url.into_url();
+        let _ = upgraded.set_scheme("https");
+        log::debug!("HTTPS-only mode upgraded the navigation to {upgraded}");
+        ServoUrl::from_url(upgraded)
+    }
+
     /// Handle Winit window event and return a boolean to indicate if the compositor should repaint immediately.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, level = "trace"))]
     pub fn handle_winit_window_event(
         &mut self,
         sender: &Sender<ConstellationMsg>,
         compositor: &mut IOCompositor,
+        keybindings: &KeyBindings,
         event: &winit::event::WindowEvent,
     ) {
         match event {
             WindowEvent::RedrawRequested => {
-                if compositor.ready_to_present {
+                if compositor.ready_to_present == Some(self.id()) {
                     self.window.pre_present_notify();
-                    if let Err(err) = compositor.rendering_context.present(&self.surface) {
+                    if let Err(err) = compositor
+                        .rendering_context
+                        .present_with_damage(&self.surface, &compositor.damage_rects)
+                    {
                         log::warn!("Failed to present surface: {:?}", err);
                     }
-                    compositor.ready_to_present = false;
+                    compositor.ready_to_present = None;
                 }
             }
             WindowEvent::Focused(focused) => {
@@ -391,9 +1079,29 @@ impl Window {
             WindowEvent::CursorLeft { .. } => {
                 self.mouse_position.set(None);
             }
+            WindowEvent::DroppedFile(path) => {
+                // Servo can't dispatch drag and drop events to content yet, so do what browsers do
+                // when a file is dropped outside of a drop target and open it in the current tab.
+                let Some(tab_id) = self.tab_manager.current_tab_id() else {
+                    return;
+                };
+                match url::Url::from_file_path(path) {
+                    Ok(url) => send_to_constellation(
+                        sender,
+                        ConstellationMsg::LoadUrl(tab_id, ServoUrl::from_url(url)),
+                    ),
+                    Err(()) => log::error!("Failed to open dropped file {}", path.display()),
+                }
+            }
             WindowEvent::CursorMoved { position, .. } => {
                 let point: DevicePoint = DevicePoint::new(position.x as f32, position.y as f32);
                 self.mouse_position.set(Some(*position));
+                if !self.is_in_shape(point) {
+                    return;
+                }
+                if let Some(mouse_gestures) = &mut self.mouse_gestures {
+                    mouse_gestures.on_mouse_move(point);
+                }
                 forward_input_event(
                     compositor,
                     sender,
@@ -417,6 +1125,9 @@ impl Window {
                         return;
                     }
                 };
+                if !self.is_in_shape(point) {
+                    return;
+                }
 
                 /* handle context menu */
                 if let (ElementState::Pressed, winit::event::MouseButton::Right) = (state, button) {
@@ -426,6 +1137,36 @@ impl Window {
                     }
                 }
 
+                /* handle mouse gestures */
+                if let Some(mouse_gestures) = &mut self.mouse_gestures {
+                    let scale_factor = self.window.scale_factor() as f32;
+                    match mouse_gestures.on_mouse_input(*button, *state, point, scale_factor) {
+                        GestureResult::Pass => {}
+                        GestureResult::Consumed => return,
+                        GestureResult::Action(action) => {
+                            self.handle_gesture_action(sender, action);
+                            return;
+                        }
+                        GestureResult::Replay(start) => {
+                            let event = MouseButtonEvent {
+                                point: start,
+                                action: MouseButtonAction::Down,
+                                button: MouseButton::Right,
+                            };
+                            forward_input_event(compositor, sender, InputEvent::MouseButton(event));
+                        }
+                    }
+                }
+
+                /* handle custom drag regions */
+                if *state == ElementState::Pressed
+                    && *button == winit::event::MouseButton::Left
+                    && self.is_in_drag_region(point)
+                {
+                    let _ = self.window.drag_window();
+                    return;
+                }
+
                 /* handle Windows and Linux non-decoration window resize */
                 #[cfg(any(linux, target_os = "windows"))]
                 {
@@ -439,6 +1180,18 @@ impl Window {
 
                 /* handle mouse events */
 
+                if *state == ElementState::Pressed {
+                    // Remember the click on the page for the popup blocker.
+                    let point = Point2D::new(point.x as i32, point.y as i32);
+                    if let Some(tab) = self
+                        .tab_manager
+                        .current_tab()
+                        .filter(|tab| tab.webview().rect.contains(point))
+                    {
+                        self.user_activation = Some((tab.id(), Instant::now()));
+                    }
+                }
+
                 let button: MouseButton = match button {
                     winit::event::MouseButton::Left => MouseButton::Left,
                     winit::event::MouseButton::Right => MouseButton::Right,
@@ -478,6 +1231,19 @@ impl Window {
                     forward_input_event(compositor, sender, InputEvent::MouseButton(event));
                 }
             }
+            WindowEvent::Touch(touch) => {
+                let action = match touch.phase {
+                    TouchPhase::Started => TouchEventAction::Down,
+                    TouchPhase::Moved => TouchEventAction::Move,
+                    TouchPhase::Ended => TouchEventAction::Up,
+                    TouchPhase::Cancelled => TouchEventAction::Cancel,
+                };
+                compositor.on_touch_event(TouchEvent {
+                    action,
+                    id: TouchId(touch.id as i32),
+                    point: DevicePoint::new(touch.location.x as f32, touch.location.y as f32),
+                });
+            }
             WindowEvent::PinchGesture { delta, .. } => {
                 compositor.on_zoom_window_event(1.0 + *delta as f32, self);
             }
@@ -489,6 +1255,9 @@ impl Window {
                         return;
                     }
                 };
+                if !self.is_in_shape(DevicePoint::new(point.x as f32, point.y as f32)) {
+                    return;
+                }
 
                 // FIXME: Pixels per line, should be configurable (from browser setting?) and vary by zoom level.
                 const LINE_HEIGHT: f32 = 38.0;
@@ -511,6 +1280,27 @@ impl Window {
                     y = 0.0;
                 }
 
+                /* handle trackpad swipe navigation */
+                if let winit::event::MouseScrollDelta::PixelDelta(_) = delta {
+                    match phase {
+                        TouchPhase::Started => compositor.begin_swipe(),
+                        TouchPhase::Ended | TouchPhase::Cancelled => {
+                            let overscroll = compositor.end_swipe(self);
+                            if *phase == TouchPhase::Ended
+                                && overscroll.abs() >= SWIPE_NAVIGATION_MIN_SCREEN_PX
+                            {
+                                let action = if overscroll > 0. {
+                                    GestureAction::Back
+                                } else {
+                                    GestureAction::Forward
+                                };
+                                self.handle_gesture_action(sender, action);
+                            }
+                        }
+                        TouchPhase::Moved => {}
+                    }
+                }
+
                 let phase: TouchEventAction = match phase {
                     TouchPhase::Started => TouchEventAction::Down,
                     TouchPhase::Moved => TouchEventAction::Move,
@@ -525,6 +1315,28 @@ impl Window {
                 );
             }
             WindowEvent::ModifiersChanged(modifier) => self.modifiers_state.set(modifier.state()),
+            WindowEvent::Ime(ime) => {
+                if self.focused_webview_id.is_none() {
+                    log::trace!("No focused webview, skipping Ime event.");
+                    return;
+                }
+                let event = match ime {
+                    Ime::Enabled => ImeEvent::Composition(CompositionEvent {
+                        state: CompositionState::Start,
+                        data: String::new(),
+                    }),
+                    Ime::Preedit(text, _) => ImeEvent::Composition(CompositionEvent {
+                        state: CompositionState::Update,
+                        data: text.clone(),
+                    }),
+                    Ime::Commit(text) => ImeEvent::Composition(CompositionEvent {
+                        state: CompositionState::End,
+                        data: text.clone(),
+                    }),
+                    Ime::Disabled => ImeEvent::Dismissed,
+                };
+                forward_input_event(compositor, sender, InputEvent::Ime(event));
+            }
             WindowEvent::KeyboardInput { event, .. } => {
                 let webview_id = match self.focused_webview_id {
                     Some(webview_id) => webview_id,
@@ -545,63 +1357,145 @@ impl Window {
                 log::trace!("Verso is handling {:?}", event);
 
                 /* Window operation keyboard shortcut */
-                if self.handle_keyboard_shortcut(compositor, &event) {
+                if self.handle_keyboard_shortcut(compositor, keybindings, &event) {
                     return;
                 }
+                if event.state == KeyState::Down && self.tab_manager.tab(webview_id).is_some() {
+                    self.user_activation = Some((webview_id, Instant::now()));
+                }
                 forward_input_event(compositor, sender, InputEvent::Keyboard(event));
             }
             e => log::trace!("Verso Window isn't supporting this window event yet: {e:?}"),
         }
     }
 
+    /// Navigate the current tab according to the recognized mouse gesture.
+    fn handle_gesture_action(&self, sender: &Sender<ConstellationMsg>, action: GestureAction) {
+        let Some(tab_id) = self.tab_manager.current_tab_id() else {
+            return;
+        };
+        let msg = match action {
+            GestureAction::Back => {
+                ConstellationMsg::TraverseHistory(tab_id, TraversalDirection::Back(1))
+            }
+            GestureAction::Forward => {
+                ConstellationMsg::TraverseHistory(tab_id, TraversalDirection::Forward(1))
+            }
+            GestureAction::Reload => ConstellationMsg::Reload(tab_id),
+        };
+        send_to_constellation(sender, msg);
+    }
+
     /// Handle Window keyboard shortcut
     ///
     /// - Returns `true` if the event is handled, then we should skip sending it to constellation
     fn handle_keyboard_shortcut(
         &mut self,
         compositor: &mut IOCompositor,
+        keybindings: &KeyBindings,
         event: &KeyboardEvent,
     ) -> bool {
-        let is_macos = cfg!(target_os = "macos");
-        let control_or_meta = if is_macos {
-            Modifiers::META
-        } else {
-            Modifiers::CONTROL
+        if event.state != KeyState::Down {
+            return false;
+        }
+
+        if let Some((webview_id, _)) = self.fullscreen_webview {
+            if event.code == Code::Escape {
+                send_to_constellation(
+                    &compositor.constellation_chan,
+                    ConstellationMsg::ExitFullScreen(webview_id),
+                );
+                return true;
+            }
+        }
+
+        let Some(action) = keybindings.action(event.modifiers, event.code) else {
+            return false;
         };
+        log::trace!("Verso Window is handling keyboard shortcut {action:?}");
+        self.run_key_action(compositor, action);
+        true
+    }
 
-        if event.state == KeyState::Down {
-            // TODO: New Window, Close Browser
-            match (event.modifiers, event.code) {
-                (modifiers, Code::KeyT) if modifiers == control_or_meta => {
-                    (*self).create_tab(
+    /// Run the action of a keyboard shortcut, also used by the panel.
+    pub(crate) fn run_key_action(&mut self, compositor: &mut IOCompositor, action: KeyAction) {
+        let tab_id = self.tab_manager.current_tab_id();
+        match action {
+            KeyAction::Reload => {
+                if let Some(tab_id) = tab_id {
+                    send_to_constellation(
                         &compositor.constellation_chan,
-                        ServoUrl::parse("https://example.com").unwrap(),
+                        ConstellationMsg::Reload(tab_id),
                     );
-                    return true;
                 }
-                (modifiers, Code::KeyW) if modifiers == control_or_meta => {
-                    if let Some(tab_id) = self.tab_manager.current_tab_id() {
-                        (*self).close_tab(compositor, tab_id);
-                    }
-                    return true;
+            }
+            KeyAction::Back => {
+                if let Some(tab_id) = tab_id {
+                    send_to_constellation(
+                        &compositor.constellation_chan,
+                        ConstellationMsg::TraverseHistory(tab_id, TraversalDirection::Back(1)),
+                    );
+                }
+            }
+            KeyAction::Forward => {
+                if let Some(tab_id) = tab_id {
+                    send_to_constellation(
+                        &compositor.constellation_chan,
+                        ConstellationMsg::TraverseHistory(tab_id, TraversalDirection::Forward(1)),
+                    );
                 }
-                _ => (),
             }
+            KeyAction::Home => {
+                if let Some(tab_id) = tab_id {
+                    self.go_home(&compositor.constellation_chan, tab_id);
+                }
+            }
+            KeyAction::ZoomIn => compositor.on_zoom_window_event(ZOOM_STEP, self),
+            KeyAction::ZoomOut => compositor.on_zoom_window_event(1.0 / ZOOM_STEP, self),
+            KeyAction::ResetZoom => compositor.on_zoom_reset_window_event(self),
+            KeyAction::ToggleStats => self.toggle_stats(compositor),
+            KeyAction::ToggleOverlay => compositor.set_overlay(!compositor.overlay()),
+            KeyAction::NewTab => {
+                self.create_tab(
+                    &compositor.constellation_chan,
+                    ServoUrl::parse(NEWTAB_URL).unwrap(),
+                );
+            }
+            KeyAction::CloseTab => {
+                if let Some(tab_id) = tab_id {
+                    self.close_tab(compositor, tab_id);
+                }
+            }
+            KeyAction::NewWindow => self.new_window_requested = true,
+            KeyAction::Quit => self.quit_requested = true,
         }
-
-        false
     }
 
     /// Handle servo messages. Return true if it requests a new window
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, level = "trace"))]
     pub fn handle_servo_message(
         &mut self,
         webview_id: WebViewId,
         message: EmbedderMsg,
         sender: &Sender<ConstellationMsg>,
         to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
+        new_webview_requests: &mut Option<NewWebViewRequests>,
         clipboard: Option<&mut Clipboard>,
         compositor: &mut IOCompositor,
     ) -> bool {
+        // The IME belongs to the window, so handle it the same way for every webview.
+        match message {
+            EmbedderMsg::ShowIME(_webview_id, _, _, _, rect) => {
+                self.show_ime(webview_id, rect);
+                return false;
+            }
+            EmbedderMsg::HideIME(_webview_id) => {
+                self.window.set_ime_allowed(false);
+                return false;
+            }
+            _ => {}
+        }
+
         // Handle message in Verso Panel
         if let Some(panel) = &self.panel {
             if panel.webview.webview_id == webview_id {
@@ -619,6 +1513,10 @@ impl Window {
                 return false;
             }
         }
+        if let Some(&opener_id) = self.redirected_webviews.get(&webview_id) {
+            self.handle_servo_messages_with_redirect(webview_id, opener_id, message, sender);
+            return false;
+        }
         if self.tab_manager.has_prompt(webview_id) {
             self.handle_servo_messages_with_prompt(
                 webview_id, message, sender, clipboard, compositor,
@@ -632,12 +1530,40 @@ impl Window {
             message,
             sender,
             to_controller_sender,
+            new_webview_requests,
             clipboard,
             compositor,
         );
         false
     }
 
+    /// Enable the IME and place its candidate window below the focused text field. The text field's
+    /// rect is relative to the webview it's in.
+    fn show_ime(&self, webview_id: WebViewId, rect: DeviceIntRect) {
+        let Some(rect) = self.text_input_area(webview_id, rect) else {
+            return;
+        };
+        self.window.set_ime_allowed(true);
+        self.window.set_ime_cursor_area(
+            PhysicalPosition::new(rect.min.x, rect.min.y),
+            PhysicalSize::new(rect.width(), rect.height()),
+        );
+    }
+
+    /// Area of a focused text field in the window, from its area in the webview. `None` if the
+    /// webview isn't shown in the window.
+    pub(crate) fn text_input_area(
+        &self,
+        webview_id: WebViewId,
+        rect: DeviceIntRect,
+    ) -> Option<DeviceIntRect> {
+        let webview = self
+            .painting_order()
+            .into_iter()
+            .find(|webview| webview.webview_id == webview_id)?;
+        Some(rect.translate(webview.rect.min.to_vector()))
+    }
+
     /// Queues a Winit `WindowEvent::RedrawRequested` event to be emitted that aligns with the windowing system drawing loop.
     pub fn request_redraw(&self) {
         self.window.request_redraw()
@@ -665,6 +1591,52 @@ impl Window {
         self.window.scale_factor()
     }
 
+    /// Get what the webview is used for and the webview it belongs to, `None` if it isn't in
+    /// this window.
+    pub(crate) fn webview_kind(&self, id: WebViewId) -> Option<(WebViewKind, Option<WebViewId>)> {
+        #[cfg(linux)]
+        if self
+            .context_menu
+            .as_ref()
+            .is_some_and(|w| w.webview().webview_id == id)
+        {
+            return Some((WebViewKind::ContextMenu, None));
+        }
+        if let Some(tab_id) = self.tab_manager.prompt_tab_id(id) {
+            return Some((WebViewKind::Prompt, Some(tab_id)));
+        }
+        if let Some(&opener_id) = self.redirected_webviews.get(&id) {
+            return Some((WebViewKind::Redirect, Some(opener_id)));
+        }
+        if self
+            .panel
+            .as_ref()
+            .is_some_and(|panel| panel.webview.webview_id == id)
+        {
+            return Some((WebViewKind::Panel, None));
+        }
+        if self.tab_manager.tab(id).is_some() {
+            return Some((WebViewKind::Tab, None));
+        }
+        None
+    }
+
+    /// Get the ids of all webviews in this window.
+    pub(crate) fn webview_ids(&self) -> Vec<WebViewId> {
+        let mut ids = vec![];
+        if let Some(panel) = &self.panel {
+            ids.push(panel.webview.webview_id);
+        }
+        ids.extend(self.tab_manager.tab_ids());
+        ids.extend(self.tab_manager.prompt_ids());
+        ids.extend(self.redirected_webviews.keys());
+        #[cfg(linux)]
+        if let Some(context_menu) = &self.context_menu {
+            ids.push(context_menu.webview().webview_id);
+        }
+        ids
+    }
+
     /// Check if the window has such webview.
     pub fn has_webview(&self, id: WebViewId) -> bool {
         #[cfg(linux)]
@@ -680,6 +1652,10 @@ impl Window {
             return true;
         }
 
+        if self.redirected_webviews.contains_key(&id) {
+            return true;
+        }
+
         if let Some(panel) = &self.panel {
             if panel.webview.webview_id == id {
                 return true;
@@ -732,6 +1708,7 @@ impl Window {
             (self.panel.take().map(|panel| panel.webview), false)
         } else if let Ok(tab) = self.tab_manager.close_tab(id) {
             let close_window = self.tab_manager.count() == 0 || self.panel.is_none();
+            self.captive_portal_tabs.remove(&id);
             if self.focused_webview_id == Some(id) {
                 self.focused_webview_id = None;
             }
@@ -741,9 +1718,179 @@ impl Window {
         }
     }
 
+    /// Get the current state of the window.
+    pub(crate) fn window_state(&self) -> WindowState {
+        WindowState {
+            size: self.window.inner_size(),
+            position: self.window.inner_position().ok(),
+            maximized: self.window.is_maximized(),
+            minimized: self.window.is_minimized(),
+            fullscreen: self.window.fullscreen().is_some(),
+        }
+    }
+
+    /// Send the window state to the controller if it's listening and the state has changed
+    /// since the last time it was sent.
+    pub(crate) fn notify_window_state_changed(
+        &mut self,
+        to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
+    ) {
+        let Some(to_controller_sender) = to_controller_sender else {
+            return;
+        };
+        let state = self.window_state();
+        let Some(last_state) = self.event_listeners.on_window_state_changed.as_mut() else {
+            return;
+        };
+        if *last_state == Some(state) {
+            return;
+        }
+        *last_state = Some(state);
+        if let Err(error) =
+            to_controller_sender.send(ToControllerMessage::OnWindowStateChanged(state))
+        {
+            log::error!("Verso failed to send OnWindowStateChanged to controller: {error}")
+        }
+    }
+
+    /// Get the security information of the current tab's page, `None` if nothing is loaded yet.
+    pub(crate) fn security_info(&self) -> Option<SecurityInfo> {
+        let history = self.tab_manager.current_tab()?.history();
+        let url = history.list.get(history.current_idx)?.as_url().clone();
+        Some(SecurityInfo {
+            state: security_state(&url),
+            url,
+        })
+    }
+
+    /// Check if the current tab, its URL or its title changed since the last check, and show the
+    /// title of the new page in the title bar. Returns the new page if it changed.
+    pub(crate) fn update_current_page(&mut self) -> Option<&(WebViewId, ServoUrl, Option<String>)> {
+        let tab = self.tab_manager.current_tab()?;
+        let history = tab.history();
+        let url = history.list.get(history.current_idx)?;
+        let title = tab.title(url).map(str::to_owned);
+        let page = (tab.id(), url.clone(), title);
+        if self.current_page.as_ref() == Some(&page) {
+            return None;
+        }
+        self.window
+            .set_title(page.2.as_deref().unwrap_or(page.1.as_str()));
+        self.current_page = Some(page);
+        self.current_page.as_ref()
+    }
+
+    /// The current tab, its URL and its title when they were last checked.
+    pub(crate) fn current_page(&self) -> Option<&(WebViewId, ServoUrl, Option<String>)> {
+        self.current_page.as_ref()
+    }
+
+    /// Send the security info of the current page to the controller if it's listening and it
+    /// has changed since the last time it was sent.
+    pub(crate) fn notify_security_state_changed(
+        &mut self,
+        to_controller_sender: &Option<IpcSender<ToControllerMessage>>,
+    ) {
+        let Some(to_controller_sender) = to_controller_sender else {
+            return;
+        };
+        let Some(info) = self.security_info() else {
+            return;
+        };
+        let Some(last_info) = self.event_listeners.on_security_state_changed.as_mut() else {
+            return;
+        };
+        if last_info.as_ref() == Some(&info) {
+            return;
+        }
+        *last_info = Some(info.clone());
+        if let Err(error) =
+            to_controller_sender.send(ToControllerMessage::OnSecurityStateChanged(info))
+        {
+            log::error!("Verso failed to send OnSecurityStateChanged to controller: {error}")
+        }
+    }
+
+    /// Check if the physical point is inside one of the drag regions.
+    fn is_in_drag_region(&self, point: DevicePoint) -> bool {
+        let scale_factor = self.scale_factor();
+        let (x, y) = (point.x as f64 / scale_factor, point.y as f64 / scale_factor);
+        self.drag_regions.iter().any(|region| {
+            x >= region.x
+                && x < region.x + region.width
+                && y >= region.y
+                && y < region.y + region.height
+        })
+    }
+
+    /// Check if the physical point is inside the shape of the window, the masked out parts don't
+    /// receive mouse input.
+    fn is_in_shape(&self, point: DevicePoint) -> bool {
+        if self.shape == WindowShape::Rectangle {
+            return true;
+        }
+        let size = self.window.inner_size();
+        if size.width == 0 || size.height == 0 {
+            return false;
+        }
+        let (width, height) = (size.width as f64, size.height as f64);
+        let corner_radius = match self.shape {
+            WindowShape::RoundedRectangle { radius } => {
+                let radius = radius * self.scale_factor();
+                (radius / width, radius / height)
+            }
+            _ => (0., 0.),
+        };
+        self.shape.contains(
+            point.x as f64 / width,
+            point.y as f64 / height,
+            corner_radius,
+        )
+    }
+
+    /// Make the webview cover the whole window and switch the window to fullscreen when an element
+    /// requests fullscreen, and restore the layout when it exits.
+    pub(crate) fn set_content_fullscreen(
+        &mut self,
+        webview_id: WebViewId,
+        fullscreen: bool,
+        compositor: &mut IOCompositor,
+    ) {
+        if fullscreen {
+            if self.fullscreen_webview.is_some() {
+                return;
+            }
+            let window_was_fullscreen = self.window.fullscreen().is_some();
+            self.fullscreen_webview = Some((webview_id, window_was_fullscreen));
+            if !window_was_fullscreen {
+                self.window
+                    .set_fullscreen(Some(winit::window::Fullscreen::Borderless(None)));
+            }
+        } else {
+            let Some((_, window_was_fullscreen)) = self.fullscreen_webview.take() else {
+                return;
+            };
+            if !window_was_fullscreen {
+                self.window.set_fullscreen(None);
+            }
+        }
+        let size = self.window.inner_size();
+        compositor.resize(Size2D::new(size.width as i32, size.height as i32), self);
+    }
+
     /// Get the painting order of this window.
     pub fn painting_order(&self) -> Vec<&WebView> {
         let mut order = vec![];
+        if self.fullscreen_webview.is_some() {
+            if let Some(tab) = self.tab_manager.current_tab() {
+                order.push(tab.webview());
+            }
+            if let Some(prompt) = self.tab_manager.current_prompt() {
+                order.push(prompt.webview());
+            }
+            return order;
+        }
+
         if let Some(panel) = &self.panel {
             order.push(&panel.webview);
         }
@@ -1116,3 +2263,50 @@ fn forward_input_event(
         event, None, /* hit_test */
     ));
 }
+
+/// Get the winit theme of a color scheme.
+pub fn winit_theme(color_scheme: ColorScheme) -> Theme {
+    match color_scheme {
+        ColorScheme::Light => Theme::Light,
+        ColorScheme::Dark => Theme::Dark,
+    }
+}
+
+/// Check if the user allowed an invalid certificate of the URL's host on the error page.
+fn has_certificate_exception(url: &url::Url) -> bool {
+    url.host_str().is_some_and(|host| {
+        CERTIFICATE_EXCEPTIONS
+            .lock()
+            .is_ok_and(|exceptions| exceptions.contains(host))
+    })
+}
+
+/// Check if HTTPS-only mode has to load the URL's host over `https`, which isn't the case for
+/// loopback hosts and the hosts the user allowed `http` for.
+pub(crate) fn needs_https(url: &url::Url) -> bool {
+    !http::is_loopback(url)
+        && url.host_str().is_some_and(|host| {
+            HTTP_EXCEPTIONS
+                .lock()
+                .is_ok_and(|exceptions| !exceptions.contains(host))
+        })
+}
+
+/// Get the `verso://https-only` error page offering to load the `http` URL anyway.
+pub(crate) fn https_only_error_page(url: &url::Url) -> ServoUrl {
+    let mut page = url::Url::parse("verso://https-only").unwrap();
+    page.query_pairs_mut().append_pair("url", url.as_str());
+    ServoUrl::from_url(page)
+}
+
+/// Derive the security state of a page from its URL.
+fn security_state(url: &url::Url) -> SecurityState {
+    match url.scheme() {
+        "https" | "wss" if opts::get().ignore_certificate_errors => SecurityState::Insecure,
+        "https" | "wss" if has_certificate_exception(url) => SecurityState::Insecure,
+        "https" | "wss" => SecurityState::Secure,
+        "http" | "ws" if http::is_loopback(url) => SecurityState::Local,
+        "http" | "ws" => SecurityState::Insecure,
+        _ => SecurityState::Local,
+    }
+}
@@ -0,0 +1,239 @@
+use std::{
+    collections::VecDeque,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use base::id::WebViewId;
+use embedder_traits::{EmbedderMsg, PromptDefinition};
+use serde::{Deserialize, Serialize};
+
+/// Collects the results of testharness.js tests, run as the init script of every test page.
+pub const WPT_HOOK_SCRIPT: &str = include_str!("../resources/components/wpt.js");
+
+/// How long a test has to report its result by default, longer than testharness.js' own timeout
+/// so it can report the subtests which timed out.
+pub const DEFAULT_TEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Prefix of the prompts the hook script reports results with.
+const RESULT_PROMPT_PREFIX: &str = "WPT_RESULT:";
+
+/// Load the test URLs from a file with one URL per line, blank lines and lines starting with `#`
+/// are skipped.
+pub fn load_tests(path: &Path) -> Result<Vec<url::Url>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| url::Url::parse(line).map_err(|e| format!("Invalid test URL {line}: {e}")))
+        .collect()
+}
+
+/// What the hook script reports for a page.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PageReport {
+    /// The page is a reftest, which needs screenshots.
+    Reftest {
+        #[serde(rename = "reftest")]
+        _reftest: bool,
+    },
+    /// testharness.js completed.
+    Harness {
+        status: String,
+        message: Option<String>,
+        subtests: Vec<SubtestResult>,
+    },
+}
+
+/// Result of a subtest in the wptreport format.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubtestResult {
+    name: String,
+    status: String,
+    message: Option<String>,
+}
+
+/// Result of a test in the wptreport format.
+#[derive(Debug, Serialize)]
+pub struct TestResult {
+    /// Path and query of the test URL, like `/dom/nodes/Node-cloneNode.html`
+    test: String,
+    status: String,
+    message: Option<String>,
+    subtests: Vec<SubtestResult>,
+    /// How long the test ran in milliseconds
+    duration: u128,
+}
+
+/// Results of a run in the wptreport format, which tools like `wpt.fyi` read.
+#[derive(Debug, Serialize)]
+pub struct WptReport {
+    results: Vec<TestResult>,
+    /// Paths of the reftests which weren't run, they're left out of `results` so they don't
+    /// count as skipped
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    unsupported_reftests: Vec<String>,
+}
+
+/// How a test ended.
+#[derive(Debug)]
+enum Outcome {
+    /// Status, message and subtests of a test which ran
+    Ran(String, Option<String>, Vec<SubtestResult>),
+    /// The test is a reftest, which isn't run
+    Reftest,
+}
+
+/// What the runner needs Verso to do next.
+#[derive(Debug)]
+pub enum WptStep {
+    /// Load the next test in the main window
+    Load(url::Url),
+    /// Every test has run, print the report and exit
+    Finished(WptReport),
+}
+
+/// Runs web platform tests one after another in the main window for `--wpt`. Results of
+/// testharness.js tests are collected by the hook script. Reftests aren't run since Verso can't
+/// read the rendered frames back yet, they're listed apart from the results instead. Tests which
+/// don't report in time count as timed out, and tests whose content panics as crashed.
+#[derive(Debug)]
+pub struct WptRunner {
+    /// Tests which haven't started yet.
+    pending: VecDeque<url::Url>,
+    /// The test being run.
+    current: url::Url,
+    /// When the test started loading.
+    started_at: Instant,
+    /// Result of the running test once it's known.
+    result: Option<Outcome>,
+    /// How long a test has to report its result.
+    timeout: Duration,
+    /// Results of the tests which have run.
+    results: Vec<TestResult>,
+    /// Paths of the reftests found.
+    unsupported_reftests: Vec<String>,
+    /// Whether every test has run.
+    finished: bool,
+}
+
+impl WptRunner {
+    /// Create a runner starting at the first test, `None` if there's none.
+    pub fn new(tests: Vec<url::Url>, timeout: Duration) -> Option<Self> {
+        let mut pending = VecDeque::from(tests);
+        let current = pending.pop_front()?;
+        Some(Self {
+            pending,
+            current,
+            started_at: Instant::now(),
+            result: None,
+            timeout,
+            results: Vec::new(),
+            unsupported_reftests: Vec::new(),
+            finished: false,
+        })
+    }
+
+    /// URL of the test being run.
+    pub fn current_url(&self) -> &url::Url {
+        &self.current
+    }
+
+    /// Collect the result of the test running in `webview_id`. Returns `true` if the message was
+    /// the result reported by the hook script, which nothing else has to handle.
+    pub fn handle_message(&mut self, webview_id: Option<WebViewId>, message: &EmbedderMsg) -> bool {
+        match message {
+            EmbedderMsg::Prompt(id, PromptDefinition::Input(text, _, sender), _)
+                if Some(*id) == webview_id =>
+            {
+                let Some(report) = text.strip_prefix(RESULT_PROMPT_PREFIX) else {
+                    return false;
+                };
+                let _ = sender.send(None);
+                if self.result.is_none() {
+                    self.result = Some(match serde_json::from_str(report) {
+                        Ok(PageReport::Harness {
+                            status,
+                            message,
+                            subtests,
+                        }) => Outcome::Ran(status, message, subtests),
+                        Ok(PageReport::Reftest { .. }) => Outcome::Reftest,
+                        Err(e) => Outcome::Ran(
+                            "ERROR".to_owned(),
+                            Some(format!("Invalid result of the hook script: {e}")),
+                            Vec::new(),
+                        ),
+                    });
+                }
+                true
+            }
+            EmbedderMsg::Panic(id, reason, _) if Some(*id) == webview_id => {
+                if self.result.is_none() {
+                    self.result = Some(Outcome::Ran(
+                        "CRASH".to_owned(),
+                        Some(reason.clone()),
+                        Vec::new(),
+                    ));
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// When the running test times out, `None` once every test has run.
+    pub fn deadline(&self) -> Option<Instant> {
+        (!self.finished).then_some(self.started_at + self.timeout)
+    }
+
+    /// Move on to the next test if the running one has a result or has timed out.
+    pub fn poll(&mut self) -> Option<WptStep> {
+        if self.finished {
+            return None;
+        }
+        let elapsed = self.started_at.elapsed();
+        let outcome = match self.result.take() {
+            Some(outcome) => outcome,
+            None if elapsed >= self.timeout => Outcome::Ran("TIMEOUT".to_owned(), None, Vec::new()),
+            None => return None,
+        };
+        let mut test = self.current.path().to_owned();
+        if let Some(query) = self.current.query() {
+            test.push('?');
+            test.push_str(query);
+        }
+        match outcome {
+            Outcome::Ran(status, message, subtests) => {
+                log::info!("WPT {status} {}", self.current);
+                self.results.push(TestResult {
+                    test,
+                    status,
+                    message,
+                    subtests,
+                    duration: elapsed.as_millis(),
+                });
+            }
+            Outcome::Reftest => {
+                log::warn!("WPT reftest {} isn't supported yet", self.current);
+                self.unsupported_reftests.push(test);
+            }
+        }
+
+        match self.pending.pop_front() {
+            Some(url) => {
+                self.current = url.clone();
+                self.started_at = Instant::now();
+                Some(WptStep::Load(url))
+            }
+            None => {
+                self.finished = true;
+                Some(WptStep::Finished(WptReport {
+                    results: std::mem::take(&mut self.results),
+                    unsupported_reftests: std::mem::take(&mut self.unsupported_reftests),
+                }))
+            }
+        }
+    }
+}
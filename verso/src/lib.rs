@@ -4,9 +4,15 @@ use std::{
     path::Path,
     process::Command,
     sync::{mpsc::Sender as MpscSender, Arc, Mutex},
+    time::Duration,
 };
 use versoview_messages::{
-    ToControllerMessage, ToVersoMessage, WebResourceRequest, WebResourceRequestResponse,
+    ColorScheme, ConnectivityState, ConsoleMessage, CrashReport, DomSnapshot, DragRegion,
+    HistoryList, IdleState, KeyBinding, LifecycleEvent, LoadProgress, MemoryReport,
+    NavigationPolicy, NewWebViewPolicy, PageInfo, PipelineDiagnostics, SecurityInfo, StartupReport,
+    TextInputChange, TextInputEvent, ToControllerMessage, ToVersoMessage, VersoWebviewId,
+    VersoWindowId, WebRenderDebugFlag, WebResourceRequest, WebResourceRequestResponse, WindowShape,
+    WindowState,
 };
 
 use ipc_channel::{
@@ -21,8 +27,22 @@ type Listener<T> = Arc<Mutex<Option<T>>>;
 struct EventListeners {
     on_close_requested: Listener<Box<dyn Fn() + Send + 'static>>,
     on_navigation_starting: Listener<Box<dyn Fn(url::Url) -> bool + Send + 'static>>,
+    on_new_webview_requested:
+        Listener<Box<dyn Fn(Option<url::Url>) -> NewWebViewPolicy + Send + 'static>>,
     on_web_resource_requested:
         Listener<Box<dyn Fn(WebResourceRequest, ResponseFunction) + Send + 'static>>,
+    on_devtools_started: Listener<Box<dyn Fn(u16, String) + Send + 'static>>,
+    on_window_state_changed: Listener<Box<dyn Fn(WindowState) + Send + 'static>>,
+    on_connectivity_changed: Listener<Box<dyn Fn(ConnectivityState) + Send + 'static>>,
+    on_security_state_changed: Listener<Box<dyn Fn(SecurityInfo) + Send + 'static>>,
+    on_lifecycle_event: Listener<Box<dyn Fn(LifecycleEvent) + Send + 'static>>,
+    on_load_progress: Listener<Box<dyn Fn(LoadProgress) + Send + 'static>>,
+    on_text_input_changed: Listener<Box<dyn Fn(TextInputChange) + Send + 'static>>,
+    on_crash_reported: Listener<Box<dyn Fn(CrashReport) + Send + 'static>>,
+    on_page_changed: Listener<Box<dyn Fn(PageInfo) + Send + 'static>>,
+    on_idle_state_changed: Listener<Box<dyn Fn(IdleState) + Send + 'static>>,
+    on_navigation_blocked: Listener<Box<dyn Fn(url::Url) + Send + 'static>>,
+    on_repl_console_message: Listener<Box<dyn Fn(ConsoleMessage) + Send + 'static>>,
     size_response: Listener<MpscSender<PhysicalSize<u32>>>,
     position_response: Listener<MpscSender<Option<PhysicalPosition<i32>>>>,
     maximized_response: Listener<MpscSender<bool>>,
@@ -31,6 +51,16 @@ struct EventListeners {
     visible_response: Listener<MpscSender<bool>>,
     scale_factor_response: Listener<MpscSender<f64>>,
     get_url_response: Listener<MpscSender<url::Url>>,
+    history_list_response: Listener<MpscSender<HistoryList>>,
+    key_bindings_response: Listener<MpscSender<Vec<KeyBinding>>>,
+    security_info_response: Listener<MpscSender<Option<SecurityInfo>>>,
+    pipeline_diagnostics_response: Listener<MpscSender<PipelineDiagnostics>>,
+    idle_time_response: Listener<MpscSender<Duration>>,
+    evaluate_in_repl_response: Listener<MpscSender<Result<String, String>>>,
+    dom_snapshot_response: Listener<MpscSender<Result<DomSnapshot, String>>>,
+    memory_report_response: Listener<MpscSender<MemoryReport>>,
+    stop_trace_response: Listener<MpscSender<Result<(), String>>>,
+    startup_report_response: Listener<MpscSender<StartupReport>>,
 }
 
 pub struct VersoviewController {
@@ -41,12 +71,41 @@ pub struct VersoviewController {
 #[derive(Debug, Default)]
 pub struct VersoviewSettings {
     pub with_panel: bool,
+    /// Page to draw as the browser UI instead of the built-in panel, needs `with_panel`
+    pub chrome_url: Option<url::Url>,
+    /// HTML file to draw as the browser UI instead of the built-in panel, needs `with_panel`
+    pub chrome_path: Option<String>,
+    /// Height of the `chrome_url` or `chrome_path` page in logical pixels
+    pub chrome_height: Option<f64>,
+    /// Page loaded by the Home action, the new tab page by default
+    pub homepage: Option<url::Url>,
+    /// JSON file with bookmarks for the new tab page, a list of `{ "url", "title" }`
+    pub bookmarks_path: Option<String>,
+    /// JSON file with pages to rotate through for digital signage, a list of
+    /// `{ "url", "seconds" }`, the initial URL is ignored if it's set
+    pub playlist_path: Option<String>,
+    /// JSON file with the [`NavigationPolicy`] to start with
+    pub navigation_policy_path: Option<String>,
+    /// JSON file with compatibility fixes for sites, a list of
+    /// `{ "domains", "userAgent", "script" }`
+    pub site_overrides_path: Option<String>,
+    /// Where pages open new webviews, a new tab by default
+    pub new_webview_policy: Option<NewWebViewPolicy>,
     pub size: Option<PhysicalSize<u32>>,
     pub position: Option<PhysicalPosition<i32>>,
     pub maximized: bool,
     pub resources_directory: Option<String>,
     pub userscripts_directory: Option<String>,
     pub devtools_port: Option<u16>,
+    pub webdriver_port: Option<u16>,
+    pub opaque: bool,
+    pub decorated: bool,
+    pub color_scheme: Option<ColorScheme>,
+    /// Search engine for URL bar input which isn't a URL, a built-in name like `DuckDuckGo`,
+    /// `Google`, `Bing` or `Wikipedia`, or a URL with `%s` in place of the query
+    pub search_engine: Option<String>,
+    /// Send URL bar input to the search engine for suggestions
+    pub search_suggestions: bool,
 }
 
 impl VersoviewController {
@@ -65,6 +124,44 @@ impl VersoviewController {
         if !settings.with_panel {
             command.arg("--no-panel");
         }
+        if let Some(chrome_url) = settings.chrome_url {
+            command.arg(format!("--chrome-url={chrome_url}"));
+        }
+        if let Some(chrome_path) = settings.chrome_path {
+            command.arg("--chrome-path");
+            command.arg(chrome_path);
+        }
+        if let Some(chrome_height) = settings.chrome_height {
+            command.arg(format!("--chrome-height={chrome_height}"));
+        }
+
+        if let Some(homepage) = settings.homepage {
+            command.arg(format!("--homepage={homepage}"));
+        }
+        if let Some(bookmarks_path) = settings.bookmarks_path {
+            command.arg("--bookmarks");
+            command.arg(bookmarks_path);
+        }
+        if let Some(playlist_path) = settings.playlist_path {
+            command.arg("--playlist");
+            command.arg(playlist_path);
+        }
+        if let Some(navigation_policy_path) = settings.navigation_policy_path {
+            command.arg("--navigation-policy");
+            command.arg(navigation_policy_path);
+        }
+        if let Some(site_overrides_path) = settings.site_overrides_path {
+            command.arg("--site-overrides");
+            command.arg(site_overrides_path);
+        }
+        if let Some(new_webview_policy) = settings.new_webview_policy {
+            command.arg(match new_webview_policy {
+                NewWebViewPolicy::NewTab => "--new-webview-policy=tab",
+                NewWebViewPolicy::NewWindow => "--new-webview-policy=window",
+                NewWebViewPolicy::SameWebView => "--new-webview-policy=same",
+                NewWebViewPolicy::Deny => "--new-webview-policy=deny",
+            });
+        }
 
         if let Some(size) = settings.size {
             let width = size.width;
@@ -93,6 +190,27 @@ impl VersoviewController {
         if let Some(devtools_port) = settings.devtools_port {
             command.arg(format!("--devtools-port={devtools_port}"));
         }
+        if let Some(webdriver_port) = settings.webdriver_port {
+            command.arg(format!("--webdriver-port={webdriver_port}"));
+        }
+        if settings.opaque {
+            command.arg("--opaque");
+        }
+        if settings.decorated {
+            command.arg("--decorated");
+        }
+        if let Some(color_scheme) = settings.color_scheme {
+            command.arg(match color_scheme {
+                ColorScheme::Light => "--color-scheme=light",
+                ColorScheme::Dark => "--color-scheme=dark",
+            });
+        }
+        if let Some(search_engine) = settings.search_engine {
+            command.arg(format!("--search-engine={search_engine}"));
+        }
+        if settings.search_suggestions {
+            command.arg("--search-suggestions");
+        }
 
         command.spawn().unwrap();
 
@@ -104,7 +222,20 @@ impl VersoviewController {
         let event_listeners = EventListeners::default();
         let on_close_requested = event_listeners.on_close_requested.clone();
         let on_navigation_starting = event_listeners.on_navigation_starting.clone();
+        let on_new_webview_requested = event_listeners.on_new_webview_requested.clone();
         let on_web_resource_requested = event_listeners.on_web_resource_requested.clone();
+        let on_devtools_started = event_listeners.on_devtools_started.clone();
+        let on_window_state_changed = event_listeners.on_window_state_changed.clone();
+        let on_connectivity_changed = event_listeners.on_connectivity_changed.clone();
+        let on_security_state_changed = event_listeners.on_security_state_changed.clone();
+        let on_lifecycle_event = event_listeners.on_lifecycle_event.clone();
+        let on_load_progress = event_listeners.on_load_progress.clone();
+        let on_text_input_changed = event_listeners.on_text_input_changed.clone();
+        let on_crash_reported = event_listeners.on_crash_reported.clone();
+        let on_page_changed = event_listeners.on_page_changed.clone();
+        let on_idle_state_changed = event_listeners.on_idle_state_changed.clone();
+        let on_navigation_blocked = event_listeners.on_navigation_blocked.clone();
+        let on_repl_console_message = event_listeners.on_repl_console_message.clone();
         let size_response = event_listeners.size_response.clone();
         let position_response = event_listeners.position_response.clone();
         let minimized_response = event_listeners.minimized_response.clone();
@@ -113,6 +244,16 @@ impl VersoviewController {
         let visible_response = event_listeners.visible_response.clone();
         let scale_factor_response = event_listeners.scale_factor_response.clone();
         let get_url_response = event_listeners.get_url_response.clone();
+        let history_list_response = event_listeners.history_list_response.clone();
+        let key_bindings_response = event_listeners.key_bindings_response.clone();
+        let security_info_response = event_listeners.security_info_response.clone();
+        let pipeline_diagnostics_response = event_listeners.pipeline_diagnostics_response.clone();
+        let idle_time_response = event_listeners.idle_time_response.clone();
+        let evaluate_in_repl_response = event_listeners.evaluate_in_repl_response.clone();
+        let dom_snapshot_response = event_listeners.dom_snapshot_response.clone();
+        let memory_report_response = event_listeners.memory_report_response.clone();
+        let stop_trace_response = event_listeners.stop_trace_response.clone();
+        let startup_report_response = event_listeners.startup_report_response.clone();
         let to_verso_sender = sender.clone();
         ROUTER.add_typed_route(
             receiver,
@@ -134,6 +275,17 @@ impl VersoviewController {
                             }
                         }
                     }
+                    ToControllerMessage::OnNewWebViewRequested(id, url) => {
+                        if let Some(ref callback) = *on_new_webview_requested.lock().unwrap() {
+                            if let Err(error) = to_verso_sender.send(
+                                ToVersoMessage::NewWebViewRequestResponse(id, callback(url)),
+                            ) {
+                                error!(
+                                    "Error while sending back OnNewWebViewRequested result: {error}"
+                                );
+                            }
+                        }
+                    }
                     ToControllerMessage::OnWebResourceRequested(request) => {
                         if let Some(ref callback) = *on_web_resource_requested.lock().unwrap() {
                             let sender_clone = to_verso_sender.clone();
@@ -150,6 +302,76 @@ impl VersoviewController {
                             );
                         }
                     }
+                    ToControllerMessage::OnDevtoolsStarted { port, token } => {
+                        if let Some(ref callback) = *on_devtools_started.lock().unwrap() {
+                            callback(port, token);
+                        }
+                    }
+                    ToControllerMessage::OnLifecycleEvent(event) => {
+                        if let Some(ref callback) = *on_lifecycle_event.lock().unwrap() {
+                            callback(event);
+                        }
+                    }
+                    ToControllerMessage::OnPageChanged(info) => {
+                        if let Some(ref callback) = *on_page_changed.lock().unwrap() {
+                            callback(info);
+                        }
+                    }
+                    ToControllerMessage::OnLoadProgress(progress) => {
+                        if let Some(ref callback) = *on_load_progress.lock().unwrap() {
+                            callback(progress);
+                        }
+                    }
+                    ToControllerMessage::OnTextInputChanged(change) => {
+                        if let Some(ref callback) = *on_text_input_changed.lock().unwrap() {
+                            callback(change);
+                        }
+                    }
+                    ToControllerMessage::OnCrashReported(report) => {
+                        if let Some(ref callback) = *on_crash_reported.lock().unwrap() {
+                            callback(report);
+                        }
+                    }
+                    ToControllerMessage::OnConnectivityChanged(state) => {
+                        if let Some(ref callback) = *on_connectivity_changed.lock().unwrap() {
+                            callback(state);
+                        }
+                    }
+                    ToControllerMessage::OnWindowStateChanged(state) => {
+                        if let Some(ref callback) = *on_window_state_changed.lock().unwrap() {
+                            callback(state);
+                        }
+                    }
+                    ToControllerMessage::OnIdleStateChanged(state) => {
+                        if let Some(ref callback) = *on_idle_state_changed.lock().unwrap() {
+                            callback(state);
+                        }
+                    }
+                    ToControllerMessage::OnNavigationBlocked(url) => {
+                        if let Some(ref callback) = *on_navigation_blocked.lock().unwrap() {
+                            callback(url);
+                        }
+                    }
+                    ToControllerMessage::OnReplConsoleMessage(message) => {
+                        if let Some(ref callback) = *on_repl_console_message.lock().unwrap() {
+                            callback(message);
+                        }
+                    }
+                    ToControllerMessage::OnSecurityStateChanged(info) => {
+                        if let Some(ref callback) = *on_security_state_changed.lock().unwrap() {
+                            callback(info);
+                        }
+                    }
+                    ToControllerMessage::GetSecurityInfoResponse(info) => {
+                        if let Some(sender) = security_info_response.lock().unwrap().take() {
+                            sender.send(info).unwrap();
+                        }
+                    }
+                    ToControllerMessage::GetPipelineDiagnosticsResponse(diagnostics) => {
+                        if let Some(sender) = pipeline_diagnostics_response.lock().unwrap().take() {
+                            sender.send(diagnostics).unwrap();
+                        }
+                    }
                     ToControllerMessage::GetSizeResponse(size) => {
                         if let Some(sender) = size_response.lock().unwrap().take() {
                             sender.send(size).unwrap();
@@ -190,6 +412,46 @@ impl VersoviewController {
                             sender.send(url).unwrap();
                         }
                     }
+                    ToControllerMessage::GetHistoryListResponse(history) => {
+                        if let Some(sender) = history_list_response.lock().unwrap().take() {
+                            sender.send(history).unwrap();
+                        }
+                    }
+                    ToControllerMessage::GetKeyBindingsResponse(bindings) => {
+                        if let Some(sender) = key_bindings_response.lock().unwrap().take() {
+                            sender.send(bindings).unwrap();
+                        }
+                    }
+                    ToControllerMessage::GetIdleTimeResponse(idle_time) => {
+                        if let Some(sender) = idle_time_response.lock().unwrap().take() {
+                            sender.send(idle_time).unwrap();
+                        }
+                    }
+                    ToControllerMessage::EvaluateInReplResponse(result) => {
+                        if let Some(sender) = evaluate_in_repl_response.lock().unwrap().take() {
+                            sender.send(result).unwrap();
+                        }
+                    }
+                    ToControllerMessage::GetDomSnapshotResponse(result) => {
+                        if let Some(sender) = dom_snapshot_response.lock().unwrap().take() {
+                            sender.send(result).unwrap();
+                        }
+                    }
+                    ToControllerMessage::GetMemoryReportResponse(report) => {
+                        if let Some(sender) = memory_report_response.lock().unwrap().take() {
+                            sender.send(report).unwrap();
+                        }
+                    }
+                    ToControllerMessage::StopTraceResponse(result) => {
+                        if let Some(sender) = stop_trace_response.lock().unwrap().take() {
+                            sender.send(result).unwrap();
+                        }
+                    }
+                    ToControllerMessage::GetStartupReportResponse(report) => {
+                        if let Some(sender) = startup_report_response.lock().unwrap().take() {
+                            sender.send(report).unwrap();
+                        }
+                    }
                     _ => {}
                 },
                 Err(e) => error!("Error while receiving VersoMessage: {e}"),
@@ -215,11 +477,17 @@ impl VersoviewController {
         Self::create(verso_path, initial_url, settings)
     }
 
-    /// Exit
+    /// Exit, like [`Self::shutdown`] with a timeout of 5 seconds
     pub fn exit(&self) -> Result<(), Box<ipc_channel::ErrorKind>> {
         self.sender.send(ToVersoMessage::Exit)
     }
 
+    /// Close every webview and shut down, letting Servo save cookies and local storage, verso
+    /// exits anyway if it takes longer than `timeout`
+    pub fn shutdown(&self, timeout: Duration) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::Shutdown(timeout))
+    }
+
     /// Listen on close requested from the OS,
     /// if you decide to use it, verso will not close the window by itself anymore,
     /// so make sure you handle it properly by either do your own logic or call [`Self::exit`] as a fallback
@@ -268,6 +536,48 @@ impl VersoviewController {
         Ok(())
     }
 
+    /// Replace the URL patterns which pages can and can't navigate to
+    pub fn set_navigation_policy(
+        &self,
+        policy: NavigationPolicy,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::SetNavigationPolicy(policy))
+    }
+
+    /// Listen on navigations blocked by the navigation policy, the callback receives the URL
+    pub fn on_navigation_blocked(
+        &self,
+        callback: impl Fn(url::Url) + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.event_listeners
+            .on_navigation_blocked
+            .lock()
+            .unwrap()
+            .replace(Box::new(callback));
+        Ok(())
+    }
+
+    /// Listen on pages asking for a new webview with `window.open` or a link with
+    /// `target="_blank"`, the callback receives the URL of the page and returns where to open it.
+    /// Servo doesn't tell the URL to open and the `window.open` features yet
+    pub fn on_new_webview_requested(
+        &self,
+        callback: impl Fn(Option<url::Url>) -> NewWebViewPolicy + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        let old_listener = self
+            .event_listeners
+            .on_new_webview_requested
+            .lock()
+            .unwrap()
+            .replace(Box::new(callback));
+        if old_listener.is_none() {
+            self.sender
+                .send(ToVersoMessage::ListenToNewWebViewRequests)?;
+        }
+        Ok(())
+    }
+
     /// Listen on web resource requests,
     /// return a boolean in the callback to decide whether or not allowing this navigation
     pub fn on_web_resource_requested(
@@ -287,6 +597,156 @@ impl VersoviewController {
         Ok(())
     }
 
+    /// Listen on the devtools server being started, the callback receives the port and the
+    /// connection token. Requires [`VersoviewSettings::devtools_port`] to be set.
+    pub fn on_devtools_started(
+        &self,
+        callback: impl Fn(u16, String) + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.event_listeners
+            .on_devtools_started
+            .lock()
+            .unwrap()
+            .replace(Box::new(callback));
+        Ok(())
+    }
+
+    /// Ask verso to report its devtools server again through [`Self::on_devtools_started`],
+    /// useful when the listener was registered after the server has started. This doesn't start
+    /// the server, which only runs if [`VersoviewSettings::devtools_port`] was set at launch
+    pub fn get_devtools_info(&self) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::GetDevtoolsInfo)
+    }
+
+    /// Listen on windows and webviews being created, destroyed or crashing,
+    /// the existing windows and webviews are reported as created first
+    pub fn on_lifecycle_event(
+        &self,
+        callback: impl Fn(LifecycleEvent) + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        let old_listener = self
+            .event_listeners
+            .on_lifecycle_event
+            .lock()
+            .unwrap()
+            .replace(Box::new(callback));
+        if old_listener.is_none() {
+            self.sender.send(ToVersoMessage::ListenToLifecycleEvents)?;
+        }
+        Ok(())
+    }
+
+    /// Listen on the current page of a window changing its URL or title, or another tab becoming
+    /// the current one, the current pages are reported first
+    pub fn on_page_changed(
+        &self,
+        callback: impl Fn(PageInfo) + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        let old_listener = self
+            .event_listeners
+            .on_page_changed
+            .lock()
+            .unwrap()
+            .replace(Box::new(callback));
+        if old_listener.is_none() {
+            self.sender.send(ToVersoMessage::ListenToPageChanged)?;
+        }
+        Ok(())
+    }
+
+    /// Listen on the stages of loads in every tab, from the navigation request to the load
+    /// finishing or the tab crashing
+    pub fn on_load_progress(
+        &self,
+        callback: impl Fn(LoadProgress) + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        let old_listener = self
+            .event_listeners
+            .on_load_progress
+            .lock()
+            .unwrap()
+            .replace(Box::new(callback));
+        if old_listener.is_none() {
+            self.sender.send(ToVersoMessage::ListenToLoadProgress)?;
+        }
+        Ok(())
+    }
+
+    /// Listen on text fields gaining and losing focus, to show an on-screen keyboard and type
+    /// into them with [`Self::text_input`]
+    pub fn on_text_input_changed(
+        &self,
+        callback: impl Fn(TextInputChange) + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        let old_listener = self
+            .event_listeners
+            .on_text_input_changed
+            .lock()
+            .unwrap()
+            .replace(Box::new(callback));
+        if old_listener.is_none() {
+            self.sender.send(ToVersoMessage::ListenToTextInput)?;
+        }
+        Ok(())
+    }
+
+    /// Type into the focused text field, like an on-screen keyboard would
+    pub fn text_input(&self, event: TextInputEvent) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::TextInput(event))
+    }
+
+    /// Listen on native crashes of previous runs, with the minidumps written for them. Each crash
+    /// is reported once, on the next start after it,
+    /// verso has to be launched with `--crash-reports` and `--config-dir` for this to be called
+    pub fn on_crash_reported(
+        &self,
+        callback: impl Fn(CrashReport) + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        let old_listener = self
+            .event_listeners
+            .on_crash_reported
+            .lock()
+            .unwrap()
+            .replace(Box::new(callback));
+        if old_listener.is_none() {
+            self.sender.send(ToVersoMessage::ListenToCrashReports)?;
+        }
+        Ok(())
+    }
+
+    /// Listen on connectivity changes found by captive portal checks,
+    /// the callback is called with the last known state right away if a check has finished already,
+    /// verso has to be launched with `--captive-portal-check` for this to be called
+    pub fn on_connectivity_changed(
+        &self,
+        callback: impl Fn(ConnectivityState) + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        let old_listener = self
+            .event_listeners
+            .on_connectivity_changed
+            .lock()
+            .unwrap()
+            .replace(Box::new(callback));
+        if old_listener.is_none() {
+            self.sender.send(ToVersoMessage::ListenToConnectivity)?;
+        }
+        Ok(())
+    }
+
+    /// Check the connectivity and captive portals again, for example after the network changed
+    pub fn check_connectivity(&self) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::CheckConnectivity)
+    }
+
+    /// Set the areas of the window in logical pixels which move the window when dragged,
+    /// useful for frameless windows without a panel
+    pub fn set_drag_regions(
+        &self,
+        regions: Vec<DragRegion>,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::SetDragRegions(regions))
+    }
+
     /// Sets the webview window's size
     pub fn set_size<S: Into<Size>>(&self, size: S) -> Result<(), Box<ipc_channel::ErrorKind>> {
         self.sender.send(ToVersoMessage::SetSize(size.into()))?;
@@ -322,12 +782,274 @@ impl VersoviewController {
         Ok(())
     }
 
+    /// Force the light or dark theme of the windows, or follow the system's with `None`
+    pub fn set_color_scheme(
+        &self,
+        color_scheme: Option<ColorScheme>,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::SetColorScheme(color_scheme))?;
+        Ok(())
+    }
+
     /// Show or hide the window
     pub fn set_visible(&self, visible: bool) -> Result<(), Box<ipc_channel::ErrorKind>> {
         self.sender.send(ToVersoMessage::SetVisible(visible))?;
         Ok(())
     }
 
+    /// Keep the window above other windows or not
+    pub fn set_always_on_top(
+        &self,
+        always_on_top: bool,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::SetAlwaysOnTop(always_on_top))?;
+        Ok(())
+    }
+
+    /// Sets the window's title, until the current page changes its title or another tab becomes
+    /// the current one
+    pub fn set_title(&self, title: impl Into<String>) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::SetTitle(title.into()))?;
+        Ok(())
+    }
+
+    /// Listen on the window's size, position, maximized, minimized or fullscreen state changes
+    pub fn on_window_state_changed(
+        &self,
+        callback: impl Fn(WindowState) + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        let old_listener = self
+            .event_listeners
+            .on_window_state_changed
+            .lock()
+            .unwrap()
+            .replace(Box::new(callback));
+        if old_listener.is_none() {
+            self.sender
+                .send(ToVersoMessage::ListenToWindowStateChanged)?;
+        }
+        Ok(())
+    }
+
+    /// Listen on security state changes of the current page, from navigating or switching tabs
+    pub fn on_security_state_changed(
+        &self,
+        callback: impl Fn(SecurityInfo) + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        let old_listener = self
+            .event_listeners
+            .on_security_state_changed
+            .lock()
+            .unwrap()
+            .replace(Box::new(callback));
+        if old_listener.is_none() {
+            self.sender
+                .send(ToVersoMessage::ListenToSecurityStateChanged)?;
+        }
+        Ok(())
+    }
+
+    /// Get the security state of the current page, `None` if no page is loaded yet
+    pub fn get_security_info(&self) -> Result<Option<SecurityInfo>, Box<ipc_channel::ErrorKind>> {
+        let mut security_info_response =
+            self.event_listeners.security_info_response.lock().unwrap();
+        self.sender.send(ToVersoMessage::GetSecurityInfo)?;
+        let (sender, receiver) = std::sync::mpsc::channel();
+        security_info_response.replace(sender);
+        drop(security_info_response);
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Listen on the user becoming idle after `timeout` without any input in the windows, and
+    /// becoming active again on the next input, for example to start and stop an attract loop.
+    /// Calling this again replaces the callback and the timeout.
+    pub fn on_idle_state_changed(
+        &self,
+        timeout: Duration,
+        callback: impl Fn(IdleState) + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.event_listeners
+            .on_idle_state_changed
+            .lock()
+            .unwrap()
+            .replace(Box::new(callback));
+        self.sender
+            .send(ToVersoMessage::ListenToIdleStateChanged(timeout))?;
+        Ok(())
+    }
+
+    /// Get how long there has been no user input in any window
+    pub fn get_idle_time(&self) -> Result<Duration, Box<ipc_channel::ErrorKind>> {
+        let mut idle_time_response = self.event_listeners.idle_time_response.lock().unwrap();
+        self.sender.send(ToVersoMessage::GetIdleTime)?;
+        let (sender, receiver) = std::sync::mpsc::channel();
+        idle_time_response.replace(sender);
+        drop(idle_time_response);
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Open a JavaScript console in a webview, the current tab of the first window if `None`,
+    /// replacing the console opened before. The callback receives the messages the page logs
+    pub fn open_repl(
+        &self,
+        webview_id: Option<VersoWebviewId>,
+        on_console_message: impl Fn(ConsoleMessage) + Send + 'static,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.event_listeners
+            .on_repl_console_message
+            .lock()
+            .unwrap()
+            .replace(Box::new(on_console_message));
+        self.sender.send(ToVersoMessage::OpenRepl(webview_id))
+    }
+
+    /// Evaluate JavaScript in the console opened with [`Self::open_repl`], and get the evaluated
+    /// value or the thrown error as text. `var` and function declarations are kept between
+    /// evaluations, `let` and `const` aren't
+    pub fn evaluate_in_repl(
+        &self,
+        code: String,
+    ) -> Result<Result<String, String>, Box<ipc_channel::ErrorKind>> {
+        let mut evaluate_in_repl_response = self
+            .event_listeners
+            .evaluate_in_repl_response
+            .lock()
+            .unwrap();
+        self.sender.send(ToVersoMessage::EvaluateInRepl(code))?;
+        let (sender, receiver) = std::sync::mpsc::channel();
+        evaluate_in_repl_response.replace(sender);
+        drop(evaluate_in_repl_response);
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Close the console opened with [`Self::open_repl`]
+    pub fn close_repl(&self) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.event_listeners
+            .on_repl_console_message
+            .lock()
+            .unwrap()
+            .take();
+        self.sender.send(ToVersoMessage::CloseRepl)
+    }
+
+    /// Get the DOM of a webview with the computed style and layout box of every element, the
+    /// current tab of the first window if `None`. Serialize it with `serde_json` to run audit
+    /// tools against it
+    pub fn get_dom_snapshot(
+        &self,
+        webview_id: Option<VersoWebviewId>,
+    ) -> Result<Result<DomSnapshot, String>, Box<ipc_channel::ErrorKind>> {
+        let mut dom_snapshot_response = self.event_listeners.dom_snapshot_response.lock().unwrap();
+        self.sender
+            .send(ToVersoMessage::GetDomSnapshot(webview_id))?;
+        let (sender, receiver) = std::sync::mpsc::channel();
+        dom_snapshot_response.replace(sender);
+        drop(dom_snapshot_response);
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Measure the memory used by every page and WebRender, broken down by component
+    pub fn get_memory_report(&self) -> Result<MemoryReport, Box<ipc_channel::ErrorKind>> {
+        let mut memory_report_response =
+            self.event_listeners.memory_report_response.lock().unwrap();
+        self.sender.send(ToVersoMessage::GetMemoryReport)?;
+        let (sender, receiver) = std::sync::mpsc::channel();
+        memory_report_response.replace(sender);
+        drop(memory_report_response);
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Start recording the timings of Servo's time profiler, to find what makes pages janky. A
+    /// trace already running starts over
+    pub fn start_trace(&self) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::StartTrace)
+    }
+
+    /// Stop recording and write the trace to a Chrome `trace_event` JSON file, which Perfetto and
+    /// `chrome://tracing` show as a flame chart
+    pub fn stop_trace(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<Result<(), String>, Box<ipc_channel::ErrorKind>> {
+        let mut stop_trace_response = self.event_listeners.stop_trace_response.lock().unwrap();
+        self.sender
+            .send(ToVersoMessage::StopTrace(path.as_ref().to_path_buf()))?;
+        let (sender, receiver) = std::sync::mpsc::channel();
+        stop_trace_response.replace(sender);
+        drop(stop_trace_response);
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Get how long each phase of versoview's startup took, to catch regressions in cold start
+    /// time
+    pub fn get_startup_report(&self) -> Result<StartupReport, Box<ipc_channel::ErrorKind>> {
+        let mut startup_report_response =
+            self.event_listeners.startup_report_response.lock().unwrap();
+        self.sender.send(ToVersoMessage::GetStartupReport)?;
+        let (sender, receiver) = std::sync::mpsc::channel();
+        startup_report_response.replace(sender);
+        drop(startup_report_response);
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Show or hide the performance overlay drawn over the window, with the frame rate, frame
+    /// times and memory use
+    pub fn set_overlay(&self, enabled: bool) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::SetOverlay(enabled))
+    }
+
+    /// Turn one of WebRender's debug views or measurements on or off, like the texture cache view
+    /// or GPU time queries
+    pub fn set_webrender_debug_flag(
+        &self,
+        flag: WebRenderDebugFlag,
+        enabled: bool,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::SetWebRenderDebugFlag(flag, enabled))
+    }
+
+    /// Change which logs versoview writes while running, with the syntax of `RUST_LOG` like
+    /// `info,net=debug`
+    pub fn set_log_filter(
+        &self,
+        filter: impl Into<String>,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::SetLogFilter(filter.into()))
+    }
+
+    /// Pause the event loop of a tab like a debugger would, its timers, input and network
+    /// callbacks wait until [`Self::resume_pipeline`]. Other pages sharing the event loop, like
+    /// tabs of the same site, are paused too, and scripts can't run in them meanwhile
+    pub fn pause_pipeline(
+        &self,
+        webview_id: VersoWebviewId,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::PausePipeline(webview_id))
+    }
+
+    /// Let a tab paused with [`Self::pause_pipeline`] run again
+    pub fn resume_pipeline(
+        &self,
+        webview_id: VersoWebviewId,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::ResumePipeline(webview_id))
+    }
+
+    /// Mask a window to a shape, the first window if `None`, for frameless widget windows. The
+    /// window has to be transparent, and the rest of it doesn't receive mouse input
+    pub fn set_window_shape(
+        &self,
+        window_id: Option<VersoWindowId>,
+        shape: WindowShape,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender
+            .send(ToVersoMessage::SetWindowShape(window_id, shape))
+    }
+
     /// Moves the window with the left mouse button until the button is released
     pub fn start_dragging(&self) -> Result<(), Box<ipc_channel::ErrorKind>> {
         self.sender.send(ToVersoMessage::StartDragging)?;
@@ -417,6 +1139,59 @@ impl VersoviewController {
         Ok(receiver.recv().unwrap())
     }
 
+    /// Get the session history of the current tab, the list is empty if there is no tab
+    pub fn get_history_list(&self) -> Result<HistoryList, Box<ipc_channel::ErrorKind>> {
+        let mut history_list_response = self.event_listeners.history_list_response.lock().unwrap();
+        self.sender.send(ToVersoMessage::GetHistoryList)?;
+        let (sender, receiver) = std::sync::mpsc::channel();
+        history_list_response.replace(sender);
+        drop(history_list_response);
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Navigate to the entry at this index of the session history of the current tab,
+    /// see [`Self::get_history_list`]
+    pub fn go_to_history_index(&self, index: usize) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::GoToHistoryIndex(index))?;
+        Ok(())
+    }
+
+    /// Get the live webviews and pipelines of versoview, for diagnosing leaks
+    pub fn get_pipeline_diagnostics(
+        &self,
+    ) -> Result<PipelineDiagnostics, Box<ipc_channel::ErrorKind>> {
+        let mut pipeline_diagnostics_response = self
+            .event_listeners
+            .pipeline_diagnostics_response
+            .lock()
+            .unwrap();
+        self.sender.send(ToVersoMessage::GetPipelineDiagnostics)?;
+        let (sender, receiver) = std::sync::mpsc::channel();
+        pipeline_diagnostics_response.replace(sender);
+        drop(pipeline_diagnostics_response);
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Get the keyboard shortcuts of the browser, the defaults and the ones from
+    /// `--keybindings` or [`Self::set_key_bindings`]
+    pub fn get_key_bindings(&self) -> Result<Vec<KeyBinding>, Box<ipc_channel::ErrorKind>> {
+        let mut key_bindings_response = self.event_listeners.key_bindings_response.lock().unwrap();
+        self.sender.send(ToVersoMessage::GetKeyBindings)?;
+        let (sender, receiver) = std::sync::mpsc::channel();
+        key_bindings_response.replace(sender);
+        drop(key_bindings_response);
+        Ok(receiver.recv().unwrap())
+    }
+
+    /// Add or replace keyboard shortcuts, a binding with a `None` action removes the shortcut
+    pub fn set_key_bindings(
+        &self,
+        bindings: Vec<KeyBinding>,
+    ) -> Result<(), Box<ipc_channel::ErrorKind>> {
+        self.sender.send(ToVersoMessage::SetKeyBindings(bindings))?;
+        Ok(())
+    }
+
     // /// Add init script to run on document started to load
     // pub fn add_init_script(&self, script: String) -> Result<(), Box<ipc_channel::ErrorKind>> {
     //     self.sender.send(ToVersoMessage::AddInitScript(script))
@@ -1,10 +1,26 @@
-use std::{env::current_exe, thread::sleep, time::Duration};
+use std::{
+    env::{args, current_exe},
+    io::{stdin, stdout, Write},
+    path::Path,
+    thread::sleep,
+    time::Duration,
+};
 
-use verso::VersoviewSettings;
+use verso::{VersoviewController, VersoviewSettings};
 
 fn main() {
     let versoview_path = current_exe().unwrap().parent().unwrap().join("versoview");
-    let controller = verso::VersoviewController::new_with_settings(
+    let mut args = args().skip(1);
+    if args.next().as_deref() == Some("repl") {
+        let url = args.next().map_or_else(
+            || url::Url::parse("about:blank").unwrap(),
+            |url| url::Url::parse(&url).expect("Invalid URL"),
+        );
+        repl(&versoview_path, url);
+        return;
+    }
+
+    let controller = VersoviewController::new_with_settings(
         versoview_path,
         url::Url::parse("https://example.com").unwrap(),
         VersoviewSettings {
@@ -27,3 +43,37 @@ fn main() {
         sleep(Duration::MAX);
     }
 }
+
+/// `verso repl [url]`: evaluate the JavaScript typed on each line in the page, printing what the
+/// page logs to its console along the way.
+fn repl(versoview_path: &Path, url: url::Url) {
+    let controller = VersoviewController::new_with_settings(
+        versoview_path,
+        url,
+        VersoviewSettings {
+            with_panel: false,
+            ..Default::default()
+        },
+    );
+    controller
+        .open_repl(None, |message| {
+            eprintln!("[{:?}] {}", message.level, message.message);
+        })
+        .unwrap();
+    loop {
+        print!("> ");
+        stdout().flush().unwrap();
+        let mut line = String::new();
+        if stdin().read_line(&mut line).unwrap() == 0 {
+            break;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        match controller.evaluate_in_repl(line).unwrap() {
+            Ok(value) => println!("{value}"),
+            Err(error) => println!("Uncaught {error}"),
+        }
+    }
+    controller.exit().unwrap();
+}
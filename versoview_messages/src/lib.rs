@@ -13,8 +13,10 @@ type SerializedPipelineId = Vec<u8>;
 #[derive(Debug, Serialize, Deserialize)]
 #[non_exhaustive]
 pub enum ToVersoMessage {
-    /// Exit
+    /// Exit, like [`ToVersoMessage::Shutdown`] with a timeout of 5 seconds
     Exit,
+    /// Close every webview and shut down, versoview exits anyway if it takes longer than this
+    Shutdown(std::time::Duration),
     /// Register a listener on versoview for getting notified on close requested from the OS,
     /// veroview will send a [`ToControllerMessage::OnCloseRequested`] when that happens
     ListenToOnCloseRequested,
@@ -31,6 +33,11 @@ pub enum ToVersoMessage {
     ListenToWebResourceRequests,
     /// Response to a [`ToControllerMessage::OnWebResourceRequested`] message from versoview
     WebResourceRequestResponse(WebResourceRequestResponse),
+    /// Register a listener on versoview for deciding where pages open new webviews,
+    /// veroview will send a [`ToControllerMessage::OnNewWebViewRequested`] when that happens
+    ListenToNewWebViewRequests,
+    /// Response to a [`ToControllerMessage::OnNewWebViewRequested`] message from versoview
+    NewWebViewRequestResponse(uuid::Uuid, NewWebViewPolicy),
     /// Sets the webview window's size
     SetSize(Size),
     /// Sets the webview window's position
@@ -43,6 +50,16 @@ pub enum ToVersoMessage {
     SetFullscreen(bool),
     /// Show or hide the window
     SetVisible(bool),
+    /// Keep the window above other windows or not
+    SetAlwaysOnTop(bool),
+    /// Sets the window's title, until the current page changes its title or another tab becomes
+    /// the current one
+    SetTitle(String),
+    /// Force the light or dark theme of the windows, or follow the system's with `None`
+    SetColorScheme(Option<ColorScheme>),
+    /// Register a listener on versoview for getting notified on window state changes,
+    /// veroview will send a [`ToControllerMessage::OnWindowStateChanged`] when that happens
+    ListenToWindowStateChanged,
     /// Moves the window with the left mouse button until the button is released
     StartDragging,
     /// Get the window's size, need a response with [`ToControllerMessage::GetSizeResponse`]
@@ -61,6 +78,111 @@ pub enum ToVersoMessage {
     GetScaleFactor,
     /// Get the current URL of the webview, need a response with [`ToControllerMessage::GetCurrentUrlResponse`]
     GetCurrentUrl,
+    /// Get the session history of the current tab, need a response with [`ToControllerMessage::GetHistoryListResponse`]
+    GetHistoryList,
+    /// Navigate to the entry at this index of the session history of the current tab
+    GoToHistoryIndex(usize),
+    /// Check the network connectivity and captive portals again, versoview will send a
+    /// [`ToControllerMessage::OnConnectivityChanged`] if the state has changed and the controller
+    /// listens with [`ToVersoMessage::ListenToConnectivity`].
+    /// Versoview has to be launched with `--captive-portal-check` for this to do anything
+    CheckConnectivity,
+    /// Get the security state of the current page, need a response with [`ToControllerMessage::GetSecurityInfoResponse`]
+    GetSecurityInfo,
+    /// Register a listener on versoview for getting notified on security state changes of the current page,
+    /// veroview will send a [`ToControllerMessage::OnSecurityStateChanged`] when that happens
+    ListenToSecurityStateChanged,
+    /// Register a listener on versoview for getting notified on windows and webviews being created,
+    /// destroyed or crashing, veroview will send a [`ToControllerMessage::OnLifecycleEvent`] for
+    /// each existing window and webview first, and then for every change
+    ListenToLifecycleEvents,
+    /// Register a listener on versoview for getting notified on the current page of a window
+    /// changing its URL or title, or another tab becoming the current one, veroview will send a
+    /// [`ToControllerMessage::OnPageChanged`] for the current page of each window first, and then
+    /// for every change
+    ListenToPageChanged,
+    /// Register a listener on versoview for getting notified on the loading progress of every tab,
+    /// veroview will send a [`ToControllerMessage::OnLoadProgress`] for each stage of a load
+    ListenToLoadProgress,
+    /// Get the live webviews and their pipelines for diagnosing leaks, need a response with
+    /// [`ToControllerMessage::GetPipelineDiagnosticsResponse`]
+    GetPipelineDiagnostics,
+    /// Get the keyboard shortcuts, need a response with [`ToControllerMessage::GetKeyBindingsResponse`]
+    GetKeyBindings,
+    /// Add, replace or remove keyboard shortcuts, bindings of other accelerators are kept
+    SetKeyBindings(Vec<KeyBinding>),
+    /// Ask versoview to report its devtools server again, versoview will send a
+    /// [`ToControllerMessage::OnDevtoolsStarted`] if the server is running. The server can't be
+    /// started lazily, Servo only starts it at launch when versoview gets `--devtools-port`
+    GetDevtoolsInfo,
+    /// Set the areas of the window which move the window when dragged with the left mouse button,
+    /// this replaces the previous regions
+    SetDragRegions(Vec<DragRegion>),
+    /// Register a listener on versoview for getting notified when there has been no user input in
+    /// any window for this long, and when the user interacts again, veroview will send a
+    /// [`ToControllerMessage::OnIdleStateChanged`] when that happens
+    ListenToIdleStateChanged(std::time::Duration),
+    /// Get how long there has been no user input in any window, need a response with
+    /// [`ToControllerMessage::GetIdleTimeResponse`]
+    GetIdleTime,
+    /// Replace the URL patterns which pages can and can't navigate to, versoview will send a
+    /// [`ToControllerMessage::OnNavigationBlocked`] for each navigation it blocks
+    SetNavigationPolicy(NavigationPolicy),
+    /// Open a JavaScript console in a webview, the current tab of the first window if `None`,
+    /// replacing the console opened before. versoview will send a
+    /// [`ToControllerMessage::OnReplConsoleMessage`] for each message the page logs
+    OpenRepl(Option<VersoWebviewId>),
+    /// Evaluate JavaScript in the console opened with [`ToVersoMessage::OpenRepl`], need a
+    /// response with [`ToControllerMessage::EvaluateInReplResponse`]
+    EvaluateInRepl(String),
+    /// Close the console opened with [`ToVersoMessage::OpenRepl`]
+    CloseRepl,
+    /// Get the DOM of a webview with the computed style and layout box of every element, the
+    /// current tab of the first window if `None`, need a response with
+    /// [`ToControllerMessage::GetDomSnapshotResponse`]
+    GetDomSnapshot(Option<VersoWebviewId>),
+    /// Pause the event loop of a tab like a debugger would, its timers, input and network
+    /// callbacks wait until [`ToVersoMessage::ResumePipeline`]. Other pages sharing the event
+    /// loop, like tabs of the same site, are paused too, and scripts can't run in them meanwhile
+    PausePipeline(VersoWebviewId),
+    /// Let a tab paused with [`ToVersoMessage::PausePipeline`] run again
+    ResumePipeline(VersoWebviewId),
+    /// Mask a window to a shape, the first window if `None`. The rest of the window is
+    /// transparent and doesn't receive mouse input, useful for frameless widget windows
+    SetWindowShape(Option<VersoWindowId>, WindowShape),
+    /// Measure the memory used by every page and WebRender, need a response with
+    /// [`ToControllerMessage::GetMemoryReportResponse`]
+    GetMemoryReport,
+    /// Start recording the timings of Servo's time profiler, a trace already running starts over
+    StartTrace,
+    /// Stop recording and write the trace to this path as a Chrome `trace_event` JSON file, need
+    /// a response with [`ToControllerMessage::StopTraceResponse`]
+    StopTrace(std::path::PathBuf),
+    /// Show or hide the performance overlay drawn over the window by the compositor, with the
+    /// frame rate, frame times of WebRender's backend and renderer, and memory use. Unlike the
+    /// statistics of [`KeyAction::ToggleStats`], it doesn't need a panel
+    SetOverlay(bool),
+    /// Get how long each phase of versoview's startup took, need a response with
+    /// [`ToControllerMessage::GetStartupReportResponse`]
+    GetStartupReport,
+    /// Turn one of WebRender's debug views or measurements on or off while running
+    SetWebRenderDebugFlag(WebRenderDebugFlag, bool),
+    /// Change which logs are written while running, with the syntax of `RUST_LOG` like
+    /// `info,net=debug`
+    SetLogFilter(String),
+    /// Register a listener on versoview for getting notified on text fields gaining and losing
+    /// focus, veroview will send a [`ToControllerMessage::OnTextInputChanged`] when that happens
+    ListenToTextInput,
+    /// Type into the focused text field, from an on-screen keyboard for example
+    TextInput(TextInputEvent),
+    /// Register a listener on versoview for getting notified on crashes of previous runs, veroview
+    /// will send a [`ToControllerMessage::OnCrashReported`] for each crash found when it started,
+    /// verso has to be launched with `--crash-reports` for this to be called
+    ListenToCrashReports,
+    /// Register a listener on versoview for getting notified on connectivity changes, veroview
+    /// will send a [`ToControllerMessage::OnConnectivityChanged`] with the last known state right
+    /// away and then on each change
+    ListenToConnectivity,
 }
 
 /// Message sent from versoview to the controller
@@ -73,6 +195,10 @@ pub enum ToControllerMessage {
     OnNavigationStarting(SerializedPipelineId, url::Url),
     /// Sent on a new web resource request, need a response with [`ToVersoMessage::WebResourceRequestResponse`]
     OnWebResourceRequested(WebResourceRequest),
+    /// Sent when a page wants to open a new webview with `window.open` or a link with
+    /// `target="_blank"`, with the URL of that page. Needs a response with
+    /// [`ToVersoMessage::NewWebViewRequestResponse`]
+    OnNewWebViewRequested(uuid::Uuid, Option<url::Url>),
     /// Response to a [`ToVersoMessage::GetSize`]
     GetSizeResponse(PhysicalSize<u32>),
     /// Response to a [`ToVersoMessage::GetPosition`]
@@ -89,8 +215,644 @@ pub enum ToControllerMessage {
     GetScaleFactorResponse(f64),
     /// Response to a [`ToVersoMessage::GetCurrentUrl`]
     GetCurrentUrlResponse(url::Url),
+    /// Response to a [`ToVersoMessage::GetSecurityInfo`], `None` if no page is loaded yet
+    GetSecurityInfoResponse(Option<SecurityInfo>),
+    /// Response to a [`ToVersoMessage::GetPipelineDiagnostics`]
+    GetPipelineDiagnosticsResponse(PipelineDiagnostics),
+    /// Response to a [`ToVersoMessage::GetKeyBindings`]
+    GetKeyBindingsResponse(Vec<KeyBinding>),
+    /// Response to a [`ToVersoMessage::GetHistoryList`]
+    GetHistoryListResponse(HistoryList),
+    /// Response to a [`ToVersoMessage::GetIdleTime`]
+    GetIdleTimeResponse(std::time::Duration),
     /// Verso have recieved a close request from the OS
     OnCloseRequested,
+    /// Sent when a connectivity check finds a different state than the previous one, and with the
+    /// last known state when the controller starts listening
+    OnConnectivityChanged(ConnectivityState),
+    /// Sent when a window or webview is created, destroyed or has crashed
+    OnLifecycleEvent(LifecycleEvent),
+    /// Sent when the current page of a window changed its URL or title, or another tab became
+    /// the current one
+    OnPageChanged(PageInfo),
+    /// Sent when a load of a tab reached another stage
+    OnLoadProgress(LoadProgress),
+    /// Sent when the current page's security state changed, by navigating or switching tabs
+    OnSecurityStateChanged(SecurityInfo),
+    /// Sent when the window's size, position, maximized, minimized or fullscreen state changed
+    OnWindowStateChanged(WindowState),
+    /// Sent when the user stopped interacting with the windows, or started again
+    OnIdleStateChanged(IdleState),
+    /// Sent when a navigation to this URL was blocked by the [`NavigationPolicy`]
+    OnNavigationBlocked(url::Url),
+    /// Response to a [`ToVersoMessage::EvaluateInRepl`], the evaluated value or the thrown error
+    /// as text
+    EvaluateInReplResponse(Result<String, String>),
+    /// Sent when the page of the console opened with [`ToVersoMessage::OpenRepl`] logs a message
+    OnReplConsoleMessage(ConsoleMessage),
+    /// Response to a [`ToVersoMessage::GetDomSnapshot`], or why the page couldn't be captured
+    GetDomSnapshotResponse(Result<DomSnapshot, String>),
+    /// Response to a [`ToVersoMessage::GetMemoryReport`]
+    GetMemoryReportResponse(MemoryReport),
+    /// Response to a [`ToVersoMessage::StopTrace`], or why the trace couldn't be written
+    StopTraceResponse(Result<(), String>),
+    /// Sent when a text field gained or lost focus, so an on-screen keyboard can be shown or hidden
+    OnTextInputChanged(TextInputChange),
+    /// Sent for a crash of a previous run of versoview which hasn't been reported yet
+    OnCrashReported(CrashReport),
+    /// Response to a [`ToVersoMessage::GetStartupReport`]
+    GetStartupReportResponse(StartupReport),
+    /// Sent when the devtools server has started, or in response to a [`ToVersoMessage::GetDevtoolsInfo`]
+    OnDevtoolsStarted {
+        /// The port the devtools server is listening to
+        port: u16,
+        /// The token a devtools client needs to connect with
+        token: String,
+    },
+}
+
+/// Browser action that can be triggered with a keyboard shortcut
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum KeyAction {
+    /// Reload the current tab
+    Reload,
+    /// Go back in the current tab's history
+    Back,
+    /// Go forward in the current tab's history
+    Forward,
+    /// Load the home page in the current tab
+    Home,
+    /// Zoom in the page
+    ZoomIn,
+    /// Zoom out the page
+    ZoomOut,
+    /// Reset the page zoom
+    ResetZoom,
+    /// Show or hide the compositor statistics in the panel
+    ToggleStats,
+    /// Show or hide the performance overlay drawn over the window
+    ToggleOverlay,
+    /// Open a new tab
+    NewTab,
+    /// Close the current tab
+    CloseTab,
+    /// Open a new window
+    NewWindow,
+    /// Quit versoview
+    Quit,
+}
+
+/// A keyboard shortcut
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    /// Modifiers and key joined with `+`, like `CmdOrCtrl+Shift+R` or `F5`
+    pub accelerator: String,
+    /// Action of the shortcut, `None` removes the shortcut
+    pub action: Option<KeyAction>,
+}
+
+/// Result of a connectivity check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConnectivityState {
+    /// The probe was reached without interference
+    Online,
+    /// The probe was intercepted by a captive portal, which needs to be opened to sign in
+    CaptivePortal(url::Url),
+    /// The probe couldn't be reached
+    Offline,
+}
+
+/// Whether the user is interacting with versoview, reported by
+/// [`ToControllerMessage::OnIdleStateChanged`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IdleState {
+    /// There was user input in one of the windows
+    Active,
+    /// There was no user input in any window for the duration of
+    /// [`ToVersoMessage::ListenToIdleStateChanged`]
+    Idle,
+}
+
+/// URL patterns which pages can and can't navigate to, like `https://*.example.com/*`. The
+/// scheme, host and path of a pattern are matched separately: `*` as the scheme matches `http`
+/// and `https`, `*` as the host matches any host and a leading `*.` the domain and its
+/// subdomains, and `*` in the path matches any number of characters. A pattern without a path
+/// matches every path. Internal `about:` and `verso:` pages are always allowed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct NavigationPolicy {
+    /// Patterns of the URLs which can be navigated to, all URLs can if it's empty
+    pub allow: Vec<String>,
+    /// Patterns of the URLs which can't be navigated to, even if they're allowed
+    pub deny: Vec<String>,
+    /// Open blocked `http` and `https` URLs in the system's default browser instead, pages have
+    /// to ask the user first
+    pub open_blocked_externally: bool,
+}
+
+/// Where a new webview requested by a page with `window.open` or a link with `target="_blank"`
+/// is opened
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum NewWebViewPolicy {
+    /// In a new tab of the page's window
+    #[default]
+    NewTab,
+    /// In a new window
+    NewWindow,
+    /// In the page's webview, replacing the page
+    SameWebView,
+    /// Not at all, `window.open` returns `null`
+    Deny,
+}
+
+/// A message logged with `console.log` or its siblings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsoleMessage {
+    /// Which console method logged it
+    pub level: ConsoleLevel,
+    /// The logged values as text, separated by spaces
+    pub message: String,
+}
+
+/// Console method a [`ConsoleMessage`] was logged with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConsoleLevel {
+    /// `console.log`
+    Log,
+    /// `console.info`
+    Info,
+    /// `console.warn`
+    Warn,
+    /// `console.error`
+    Error,
+    /// `console.debug`
+    Debug,
+}
+
+/// The DOM of a page with the computed style and layout of its elements, for running audits
+/// against what Verso rendered. Serialize it with `serde_json` to export it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DomSnapshot {
+    /// URL of the document
+    pub url: String,
+    /// Title of the document
+    pub title: String,
+    /// Width and height of the viewport in CSS pixels, at the scroll position of the page
+    pub viewport: DomRect,
+    /// Nodes of the document in tree order, the document itself first
+    pub nodes: Vec<DomNode>,
+}
+
+/// A node of a [`DomSnapshot`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DomNode {
+    /// Index of the parent node in [`DomSnapshot::nodes`], `None` for the document
+    pub parent: Option<usize>,
+    /// The DOM `nodeType`, like 1 for elements and 3 for text
+    pub node_type: u16,
+    /// The DOM `nodeName`, like `DIV` or `#text`
+    pub node_name: String,
+    /// Text of text and comment nodes
+    pub node_value: Option<String>,
+    /// Attributes of an element in source order
+    pub attributes: Vec<(String, String)>,
+    /// Every computed CSS property of an element
+    pub computed_style: Vec<(String, String)>,
+    /// Border box of an element relative to the document, `None` if it isn't rendered
+    pub bounds: Option<DomRect>,
+}
+
+/// Rectangle in CSS pixels
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DomRect {
+    /// Left edge
+    pub x: f64,
+    /// Top edge
+    pub y: f64,
+    /// Width
+    pub width: f64,
+    /// Height
+    pub height: f64,
+}
+
+/// Memory used by versoview, by component
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryReport {
+    /// Bytes used by the JavaScript heaps of all pages
+    pub js_heap: u64,
+    /// Bytes used by decoded images
+    pub images: u64,
+    /// Bytes used by layout, like the DOM, styles and fragment trees
+    pub layout: u64,
+    /// Bytes used by WebRender, like display lists and textures
+    pub webrender: u64,
+    /// Bytes measured which don't belong to the other components
+    pub other: u64,
+    /// Every measurement the totals are made of
+    pub entries: Vec<MemoryReportEntry>,
+}
+
+/// A measurement of a [`MemoryReport`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryReportEntry {
+    /// Path of the measurement, like `["url(https://servo.org/)", "js", "gc-heap", "used"]`
+    pub path: Vec<String>,
+    /// Component the measurement is counted in
+    pub component: MemoryComponent,
+    /// Size in bytes
+    pub size: u64,
+}
+
+/// A component of a [`MemoryReport`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MemoryComponent {
+    /// The JavaScript heaps
+    JsHeap,
+    /// Decoded images
+    Images,
+    /// Layout
+    Layout,
+    /// WebRender
+    WebRender,
+    /// Anything else
+    Other,
+}
+
+/// How long the startup of versoview took, until its first window and the constellation were ready
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StartupReport {
+    /// The phases of startup in the order they ran
+    pub phases: Vec<StartupPhase>,
+    /// How long the whole startup took
+    pub total: std::time::Duration,
+}
+
+/// A phase of a [`StartupReport`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StartupPhase {
+    /// Name of the phase, like `webrender` or `constellation`
+    pub name: String,
+    /// How long the phase took
+    pub duration: std::time::Duration,
+}
+
+/// Light or dark theme
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorScheme {
+    /// Dark text on a light background
+    Light,
+    /// Light text on a dark background
+    Dark,
+}
+
+/// How the current page was loaded, for showing a lock indicator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SecurityState {
+    /// Loaded over an encrypted connection (`https` or `wss`)
+    Secure,
+    /// Loaded over an unencrypted connection
+    Insecure,
+    /// Local or browser internal page which wasn't loaded over the network, like `file`,
+    /// `about`, `data` or `http` on a loopback address
+    Local,
+}
+
+/// Security information of a page
+///
+/// Note that Servo doesn't expose the TLS connection of a page to the embedder, so the state is
+/// derived from the page's URL. Pages with an invalid certificate show an error page instead,
+/// which lets the user allow the certificate temporarily. Encrypted pages of a host whose
+/// certificate was allowed this way are reported as [`SecurityState::Insecure`], and so are all
+/// encrypted pages if certificate errors are ignored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecurityInfo {
+    /// URL of the page
+    pub url: url::Url,
+    /// Security state of the page
+    pub state: SecurityState,
+}
+
+/// Id of a window, unique for the lifetime of the versoview process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct VersoWindowId(pub u64);
+
+/// Id of a webview, unique for the lifetime of the versoview process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct VersoWebviewId(pub u64);
+
+/// Lifecycle change of a window or webview reported by [`ToControllerMessage::OnLifecycleEvent`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LifecycleEvent {
+    /// A window was created
+    WindowCreated {
+        /// Id of the window
+        window_id: VersoWindowId,
+    },
+    /// A window was closed
+    WindowDestroyed {
+        /// Id of the window
+        window_id: VersoWindowId,
+    },
+    /// A webview was created in a window
+    WebViewCreated {
+        /// Id of the webview
+        webview_id: VersoWebviewId,
+        /// Id of the window the webview belongs to
+        window_id: VersoWindowId,
+        /// What the webview is used for
+        kind: WebViewKind,
+        /// The webview this one belongs to, like the tab of a prompt
+        parent: Option<VersoWebviewId>,
+    },
+    /// A webview was closed
+    WebViewDestroyed {
+        /// Id of the webview
+        webview_id: VersoWebviewId,
+    },
+    /// The content of a webview has panicked
+    WebViewCrashed {
+        /// Id of the webview
+        webview_id: VersoWebviewId,
+        /// Panic message
+        reason: String,
+    },
+}
+
+/// The current page of a window reported by [`ToControllerMessage::OnPageChanged`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageInfo {
+    /// Id of the window
+    pub window_id: VersoWindowId,
+    /// Id of the current tab's webview
+    pub webview_id: VersoWebviewId,
+    /// URL of the page
+    pub url: url::Url,
+    /// Title of the page, `None` if it has none
+    pub title: Option<String>,
+}
+
+/// Stage of a load in a tab reported by [`ToControllerMessage::OnLoadProgress`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadProgress {
+    /// Id of the tab's webview
+    pub webview_id: VersoWebviewId,
+    /// The stage the load reached
+    pub stage: LoadStage,
+}
+
+/// Stage of a load, in the order they happen
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LoadStage {
+    /// The page asked to navigate to this URL, it may still be blocked
+    Requested(url::Url),
+    /// The new document started loading
+    Started,
+    /// The navigation was committed to this URL, which differs from the requested one if the
+    /// request was redirected
+    Committed(url::Url),
+    /// The head of the document was parsed, close to when `DOMContentLoaded` fires
+    HeadParsed,
+    /// The document and its subresources finished loading
+    Finished,
+    /// The content of the tab crashed, with the panic message
+    Failed(String),
+}
+
+/// What a webview is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebViewKind {
+    /// The control panel of a window
+    Panel,
+    /// A tab showing web content
+    Tab,
+    /// A prompt dialog of a tab
+    Prompt,
+    /// A context menu
+    ContextMenu,
+    /// A webview opened by a page which is closed once it starts loading, the page loads in the
+    /// opener instead
+    Redirect,
+}
+
+/// Live webviews and pipelines known to the compositor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineDiagnostics {
+    /// Webviews with a frame tree in the compositor
+    pub webviews: Vec<WebViewDiagnostics>,
+    /// Pipelines which don't belong to the frame tree of any webview
+    pub orphaned_pipelines: usize,
+}
+
+/// Diagnostics of a single webview
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebViewDiagnostics {
+    /// Id of the webview
+    pub webview_id: VersoWebviewId,
+    /// Whether a window displays the webview, detached webviews are closed by the next audits
+    pub attached: bool,
+    /// Number of pipelines in the webview's frame tree, including the root one
+    pub pipelines: usize,
+}
+
+/// Session history of a webview
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryList {
+    /// History entries from the oldest to the newest
+    pub entries: Vec<HistoryEntry>,
+    /// Index of the current entry in `entries`
+    pub current_index: usize,
+}
+
+/// An entry of the session history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// URL of the entry
+    pub url: url::Url,
+    /// Page title of the entry if it has one
+    pub title: Option<String>,
+}
+
+/// State of the window reported by [`ToControllerMessage::OnWindowStateChanged`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowState {
+    /// Inner size of the window
+    pub size: PhysicalSize<u32>,
+    /// Inner position of the window, `None` if the platform doesn't support it
+    pub position: Option<PhysicalPosition<i32>>,
+    /// Whether the window is maximized
+    pub maximized: bool,
+    /// Whether the window is minimized, `None` if the platform can't tell
+    pub minimized: Option<bool>,
+    /// Whether the window is fullscreen
+    pub fullscreen: bool,
+}
+
+/// A rectangle area of the window in logical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DragRegion {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// A native crash of versoview, reported by [`ToControllerMessage::OnCrashReported`] on the next
+/// start
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrashReport {
+    /// Path to the minidump of the crashed process, in the `crashes` directory of the config
+    /// directory
+    pub minidump: std::path::PathBuf,
+    /// When the minidump was written
+    pub crashed_at: std::time::SystemTime,
+}
+
+/// A text field gaining or losing focus, reported by [`ToControllerMessage::OnTextInputChanged`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TextInputChange {
+    /// A text field gained focus
+    Focused(TextInputState),
+    /// The focused text field of the webview lost focus
+    Blurred(VersoWebviewId),
+}
+
+/// A focused text field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextInputState {
+    /// Id of the webview the field is in
+    pub webview_id: VersoWebviewId,
+    /// The kind of text the field takes, to pick a keyboard layout with
+    pub input_type: TextInputType,
+    /// The text in the field, `None` if the page doesn't tell
+    pub value: Option<String>,
+    /// Position of the caret in the text, `None` if the page doesn't tell
+    pub caret: Option<u32>,
+    /// Whether the field takes several lines of text, like a `<textarea>`
+    pub multiline: bool,
+    /// Position of the field in the window, to avoid covering it with the keyboard
+    pub position: PhysicalPosition<i32>,
+    /// Size of the field
+    pub size: PhysicalSize<u32>,
+}
+
+/// Kind of text a field takes, from the `type` of an `<input>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextInputType {
+    Color,
+    Date,
+    DatetimeLocal,
+    Email,
+    Month,
+    Number,
+    Password,
+    Search,
+    Tel,
+    Text,
+    Time,
+    Url,
+    Week,
+}
+
+/// Typing into the focused text field with [`ToVersoMessage::TextInput`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextInputEvent {
+    /// Text being composed which isn't final yet, like a word the keyboard is still suggesting
+    /// completions for
+    Composition(String),
+    /// Insert this text into the field, ending the composition if there's one
+    Commit(String),
+    /// Press and release a key by its name in the UI Events spec, like `Backspace`, `Enter` or
+    /// `ArrowLeft`
+    Key(String),
+    /// Cancel the composition, the keyboard was closed
+    Dismiss,
+}
+
+/// Debug views and measurements of WebRender set with [`ToVersoMessage::SetWebRenderDebugFlag`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WebRenderDebugFlag {
+    /// WebRender's profiler drawn over the window, slow frames show up in its frame time graphs
+    Profiler,
+    /// Measure how long the GPU takes to draw each pass, shown by the profiler
+    GpuTimeQueries,
+    /// Count the pixels the GPU draws, shown by the profiler
+    GpuSampleQueries,
+    /// Show the contents of the texture cache
+    TextureCache,
+    /// Show the render targets of intermediate passes
+    RenderTargets,
+    /// Show the contents of the GPU cache
+    GpuCache,
+    /// Tint pixels by how many times they are drawn
+    Overdraw,
+    /// Outline the tiles of picture caching and what invalidated them
+    PictureCaching,
+    /// Show the epoch of every pipeline in the frame
+    Epochs,
+}
+
+/// Shape of a window set with [`ToVersoMessage::SetWindowShape`]. Only the window's content is
+/// masked, so the window has to be transparent and undecorated for the desktop to show through.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum WindowShape {
+    /// The whole window
+    #[default]
+    Rectangle,
+    /// The window with its corners rounded
+    RoundedRectangle {
+        /// Radius of the corners in logical pixels
+        radius: f64,
+    },
+    /// An alpha mask stretched over the window, pixels with an alpha below 128 don't receive
+    /// mouse input
+    Mask {
+        /// Width of the mask in pixels
+        width: u32,
+        /// Height of the mask in pixels
+        height: u32,
+        /// Alpha of every pixel, row by row
+        alpha: Vec<u8>,
+    },
+}
+
+impl WindowShape {
+    /// Whether a point, relative to the window's size in the range of 0 to 1, is inside the shape.
+    /// `corner_radius` is the radius of [`WindowShape::RoundedRectangle`] relative to the width
+    /// and height of the window.
+    pub fn contains(&self, x: f64, y: f64, corner_radius: (f64, f64)) -> bool {
+        if !(0. ..1.).contains(&x) || !(0. ..1.).contains(&y) {
+            return false;
+        }
+        match self {
+            WindowShape::Rectangle => true,
+            WindowShape::RoundedRectangle { .. } => {
+                let (rx, ry) = corner_radius;
+                if rx <= 0. || ry <= 0. {
+                    return true;
+                }
+                // Distance from the center of the nearest corner's circle, 0 along the edges.
+                let dx = (rx - x).max(x - (1. - rx)).max(0.) / rx;
+                let dy = (ry - y).max(y - (1. - ry)).max(0.) / ry;
+                dx * dx + dy * dy <= 1.
+            }
+            WindowShape::Mask {
+                width,
+                height,
+                alpha,
+            } => {
+                let column = (x * *width as f64) as usize;
+                let row = (y * *height as f64) as usize;
+                alpha
+                    .get(row * *width as usize + column)
+                    .is_some_and(|alpha| *alpha >= 128)
+            }
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -106,3 +868,58 @@ pub struct WebResourceRequestResponse {
     #[serde(with = "http_serde_ext::response::option")]
     pub response: Option<http::Response<Vec<u8>>>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rectangle() {
+        let shape = WindowShape::Rectangle;
+        assert!(shape.contains(0., 0., (0., 0.)));
+        assert!(shape.contains(0.999, 0.999, (0., 0.)));
+        assert!(!shape.contains(1., 0.5, (0., 0.)));
+        assert!(!shape.contains(-0.1, 0.5, (0., 0.)));
+    }
+
+    #[test]
+    fn rounded_corners() {
+        let shape = WindowShape::RoundedRectangle { radius: 10. };
+        // Radius of 10% of the width and 20% of the height.
+        let radius = (0.1, 0.2);
+        for (x, y) in [(0., 0.), (0.99, 0.), (0., 0.99), (0.99, 0.99)] {
+            assert!(!shape.contains(x, y, radius), "corner {x},{y}");
+        }
+        assert!(shape.contains(0.1, 0.2, radius));
+        assert!(shape.contains(0.03, 0.06, radius));
+        assert!(!shape.contains(0.02, 0.04, radius));
+        // Edges between the corners are straight.
+        assert!(shape.contains(0., 0.5, radius));
+        assert!(shape.contains(0.5, 0., radius));
+        assert!(shape.contains(0.5, 0.5, radius));
+        // Without a radius, it's a rectangle.
+        assert!(shape.contains(0., 0., (0., 0.)));
+    }
+
+    #[test]
+    fn mask() {
+        // A 2x2 mask with the top left and bottom right pixels opaque.
+        let shape = WindowShape::Mask {
+            width: 2,
+            height: 2,
+            alpha: vec![255, 0, 127, 128],
+        };
+        assert!(shape.contains(0.25, 0.25, (0., 0.)));
+        assert!(!shape.contains(0.75, 0.25, (0., 0.)));
+        assert!(!shape.contains(0.25, 0.75, (0., 0.)));
+        assert!(shape.contains(0.75, 0.75, (0., 0.)));
+        assert!(!shape.contains(1., 0.75, (0., 0.)));
+        // A mask with missing pixels doesn't panic.
+        let shape = WindowShape::Mask {
+            width: 2,
+            height: 2,
+            alpha: vec![255],
+        };
+        assert!(!shape.contains(0.75, 0.75, (0., 0.)));
+    }
+}